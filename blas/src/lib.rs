@@ -2,7 +2,7 @@ extern crate blas;
 extern crate openblas_src;
 
 use blas::*;
-use matrix_base::{Dense, COO};
+use matrix_base::{Dense, DenseView, Layout, COO};
 
 pub struct BlasDense {
     pub data: Vec<f64>,    // matrix [[1,2],[3,4]] is stored as [1,3,2,4]
@@ -40,3 +40,49 @@ impl BlasDense {
         BlasDense::new_with_data(c, (m, n))
     }
 }
+
+// Transpose flag and leading dimension for dgemm, which expects column-major matrices.
+// A row-major view is the column-major storage of its transpose.
+fn blas_operand(view: &DenseView<f64>) -> Option<(u8, i32)> {
+    view.blas_layout().map(|(layout, ld)| match layout {
+        Layout::ColumnMajor => (b'N', ld as i32),
+        Layout::RowMajor => (b'T', ld as i32),
+    })
+}
+
+// C = A*B directly on views of Dense matrices (submatrices, transposes, either layout)
+// without converting them to BlasDense. Views with strides BLAS can't describe are copied first.
+// The result is column-major.
+pub fn gemm_view(a: &DenseView<f64>, b: &DenseView<f64>) -> Dense {
+    assert_eq!(a.shape.1, b.shape.0, "matrix dimension mismatch");
+    let (m, n, k) = (a.shape.0, b.shape.1, a.shape.1);
+    let mut c = Dense {
+        data: vec![0.; m * n],
+        shape: (m, n),
+        layout: Layout::ColumnMajor,
+    };
+    if m == 0 || n == 0 || k == 0 {
+        return c;
+    }
+
+    let (a_copy, b_copy);
+    let (a, (trans_a, lda)) = match blas_operand(a) {
+        Some(op) => (a.data, op),
+        None => {
+            a_copy = a.to_dense();
+            (&a_copy.data[..], (b'T', k as i32))
+        }
+    };
+    let (b, (trans_b, ldb)) = match blas_operand(b) {
+        Some(op) => (b.data, op),
+        None => {
+            b_copy = b.to_dense();
+            (&b_copy.data[..], (b'T', n as i32))
+        }
+    };
+    let (m, n, k) = (m as i32, n as i32, k as i32);
+    unsafe {
+        dgemm(trans_a, trans_b, m, n, k, 1.0, a, lda, b, ldb, 0.0, &mut c.data, m);
+    }
+    c
+}
@@ -1,7 +1,7 @@
 use std::path::Path;
 
-use blas_dense::BlasDense;
-use matrix_base::COO;
+use blas_dense::{gemm_view, BlasDense};
+use matrix_base::{Dense, DenseView, Layout, COO};
 
 // Im Endeffekt etwas umständlich über Path joinen.
 // Kann man auch mit String-Concat machen, aber
@@ -52,3 +52,57 @@ fn test_product_blas() {
         assert!(cmp_dense(&c, &c_test, eps));
     }
 }
+
+#[test]
+fn test_gemm_view() {
+    let eps = 1e-12;
+
+    let mut a = Dense::new_zeros((5, 4));
+    for i in 0..5 {
+        for j in 0..4 {
+            a.set(i, j, (i * 7 + j * 3) as f64 - 5.0);
+        }
+    }
+    let a_col = a.as_column_major();
+    let mut b = Dense::new_zeros((4, 3));
+    for i in 0..4 {
+        for j in 0..3 {
+            b.set(i, j, 0.5 * (i + 2 * j) as f64 - 1.0);
+        }
+    }
+    let b_col = b.as_column_major();
+
+    let check = |x: DenseView<f64>, y: DenseView<f64>| {
+        let c = gemm_view(&x, &y);
+        assert_eq!(c.layout, Layout::ColumnMajor);
+        let expected = &x.to_dense() * &y.to_dense();
+        assert_eq!(c.shape, expected.shape);
+        for i in 0..c.shape.0 {
+            for j in 0..c.shape.1 {
+                assert!((c.get(i, j) - expected.get(i, j)).abs() < eps);
+            }
+        }
+    };
+
+    // Row-major, column-major and mixed
+    check(a.view(), b.view());
+    check(a_col.view(), b_col.view());
+    check(a.view(), b_col.view());
+    check(a_col.view(), b.view());
+    // Transposes
+    check(b.view().transpose(), a_col.view().transpose());
+    check(a.view().transpose(), a_col.view());
+    // Submatrices with a leading dimension larger than the shape
+    check(
+        a.view().submatrix(1..4, 1..3),
+        b_col.view().submatrix(2..4, 0..2),
+    );
+    check(
+        a_col.view().submatrix(0..5, 2..4).transpose(),
+        a.view().transpose().submatrix(1..3, 0..5).transpose(),
+    );
+    // Strides BLAS can't describe are copied
+    let strided = DenseView::new(&a.data, (2, 2), (8, 2));
+    assert!(strided.blas_layout().is_none());
+    check(strided, b.view().submatrix(1..3, 0..3));
+}
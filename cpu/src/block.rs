@@ -0,0 +1,154 @@
+use rayon::prelude::*;
+
+use matrix_base::{Scalar, BSR};
+
+pub trait BlockProd<T: Scalar> {
+    fn product_block(&self, other: &BSR<T>) -> BSR<T>;
+    fn product_block_par(&self, other: &BSR<T>) -> BSR<T>;
+}
+
+// Small dense GEMM for the blocks, c += a*b
+// a is (r x s), b is (s x t), c is (r x t), all row-major.
+// computed marks the entries of c that get a product of two non-zero entries,
+// like the marker of the scalar SpGEMM. Padding is zero, so it never marks anything.
+fn gemm_block<T: Scalar>(
+    a: &[T],
+    b: &[T],
+    c: &mut [T],
+    computed: &mut [bool],
+    (r, s, t): (usize, usize, usize),
+) {
+    for i in 0..r {
+        for k in 0..s {
+            let a_ik = a[i * s + k];
+            let a_nonzero = a_ik != T::zero();
+            for j in 0..t {
+                let b_kj = b[k * t + j];
+                c[i * t + j] += a_ik * b_kj;
+                computed[i * t + j] |= a_nonzero && b_kj != T::zero();
+            }
+        }
+    }
+}
+
+// Block row bi of C = A*B, same scheme as the scalar SpGEMM (Gustavson)
+// with blocks instead of numbers. Returns the sorted block columns,
+// their values and the number of computed entries (see BSR::nnz).
+// block_idx maps a block column to its index in the result row (or usize::MAX)
+// and must be reset when done, so it can be reused for the next row.
+fn product_block_row<T: Scalar>(
+    a: &BSR<T>,
+    b: &BSR<T>,
+    bi: usize,
+    block_idx: &mut [usize],
+) -> (Vec<usize>, Vec<T>, usize) {
+    let (r, s) = a.block_shape;
+    let t = b.block_shape.1;
+    let block_len = r * t;
+
+    let mut cols: Vec<usize> = vec![];
+    let mut values: Vec<T> = vec![];
+    let mut computed: Vec<bool> = vec![];
+
+    let (a_cols, a_values) = a.get_block_row(bi);
+    for (ka, bk) in a_cols.iter().enumerate() {
+        let a_block = &a_values[ka * r * s..(ka + 1) * r * s];
+
+        let (b_cols, b_values) = b.get_block_row(*bk);
+        for (kb, bj) in b_cols.iter().enumerate() {
+            let b_block = &b_values[kb * s * t..(kb + 1) * s * t];
+
+            if block_idx[*bj] == usize::MAX {
+                block_idx[*bj] = cols.len();
+                cols.push(*bj);
+                values.extend(std::iter::repeat_n(T::zero(), block_len));
+                computed.extend(std::iter::repeat_n(false, block_len));
+            }
+            let idx = block_idx[*bj];
+            gemm_block(
+                a_block,
+                b_block,
+                &mut values[idx * block_len..(idx + 1) * block_len],
+                &mut computed[idx * block_len..(idx + 1) * block_len],
+                (r, s, t),
+            );
+        }
+    }
+
+    // Sort the blocks by block column
+    let mut order: Vec<usize> = (0..cols.len()).collect();
+    order.sort_by_key(|&k| cols[k]);
+    let mut sorted_values = Vec::with_capacity(values.len());
+    for &k in &order {
+        sorted_values.extend_from_slice(&values[k * block_len..(k + 1) * block_len]);
+    }
+    let sorted_cols: Vec<usize> = order.iter().map(|&k| cols[k]).collect();
+
+    for bj in &cols {
+        block_idx[*bj] = usize::MAX;
+    }
+
+    let nnz = computed.iter().filter(|x| **x).count();
+
+    (sorted_cols, sorted_values, nnz)
+}
+
+// Stitch the block rows together
+fn assemble<T: Scalar>(a: &BSR<T>, b: &BSR<T>, rows: Vec<(Vec<usize>, Vec<T>, usize)>) -> BSR<T> {
+    let mut row_pos = vec![0];
+    let mut col_pos = vec![];
+    let mut values = vec![];
+    let mut nnz = 0;
+
+    for (cols, vals, row_nnz) in rows {
+        col_pos.extend(cols);
+        values.extend(vals);
+        nnz += row_nnz;
+        row_pos.push(col_pos.len());
+    }
+
+    BSR {
+        row_pos,
+        col_pos,
+        values,
+        block_shape: (a.block_shape.0, b.block_shape.1),
+        shape: (a.shape.0, b.shape.1),
+        nnz,
+    }
+}
+
+fn check_shapes<T: Scalar>(a: &BSR<T>, b: &BSR<T>) {
+    assert_eq!(a.shape.1, b.shape.0, "Matrix shapes don't match");
+    assert_eq!(a.block_shape.1, b.block_shape.0, "Block shapes don't match");
+}
+
+impl<T: Scalar> BlockProd<T> for BSR<T> {
+    // Block SpGEMM C = A*B. The column block size of A has to be the
+    // row block size of B, C gets the blocks (rows of A's blocks x columns of B's blocks).
+    // The nnz of the result counts the computed entries inside the blocks, see BSR::nnz.
+    fn product_block(&self, other: &BSR<T>) -> BSR<T> {
+        check_shapes(self, other);
+
+        let mut block_idx = vec![usize::MAX; other.shape.1.div_ceil(other.block_shape.1)];
+        let rows = (0..self.row_pos.len() - 1)
+            .map(|bi| product_block_row(self, other, bi, &mut block_idx))
+            .collect();
+
+        assemble(self, other, rows)
+    }
+
+    fn product_block_par(&self, other: &BSR<T>) -> BSR<T> {
+        check_shapes(self, other);
+
+        let nb = other.shape.1.div_ceil(other.block_shape.1);
+        let rows = (0..self.row_pos.len() - 1)
+            .into_par_iter()
+            .map_init(
+                || vec![usize::MAX; nb],
+                |block_idx, bi| product_block_row(self, other, bi, block_idx),
+            )
+            .collect();
+
+        assemble(self, other, rows)
+    }
+}
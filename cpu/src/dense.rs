@@ -1,12 +1,12 @@
-use matrix_base::Dense;
+use matrix_base::{Dense, Scalar};
 use rayon::prelude::*;
 
-pub trait DenseProd {
-    fn product_dense_par(&self, other: &Dense) -> Dense;
+pub trait DenseProd<T: Scalar> {
+    fn product_dense_par(&self, other: &Dense<T>) -> Dense<T>;
 }
 
-impl DenseProd for Dense {
-    fn product_dense_par(&self, other: &Dense) -> Dense {
+impl<T: Scalar> DenseProd<T> for Dense<T> {
+    fn product_dense_par(&self, other: &Dense<T>) -> Dense<T> {
         let m = self.shape.0;
         let n = other.shape.1;
         let p = self.shape.1;
@@ -15,12 +15,12 @@ impl DenseProd for Dense {
             "Matrix dimensions do not match for multiplication"
         );
 
-        let result: Vec<Vec<f64>> = (0..m)
+        let result: Vec<Vec<T>> = (0..m)
             .into_par_iter()
             .map(|i| {
-                let mut row = vec![0.0; n];
+                let mut row = vec![T::zero(); n];
                 for j in 0..n {
-                    let mut sum = 0.0;
+                    let mut sum = T::zero();
                     for k in 0..p {
                         sum += self.get(i, k) * other.get(k, j);
                     }
@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use matrix_base::{Scalar, DIA};
+
+pub trait DiaProd<T: Scalar> {
+    fn product_dia(&self, other: &DIA<T>) -> DIA<T>;
+    fn product_dia_par(&self, other: &DIA<T>) -> DIA<T>;
+}
+
+// A pair of diagonals (index in A, index in B) contributing to a diagonal of C,
+// together with the rows i of C where both have entries
+type Contribution = (usize, usize, Range<usize>);
+
+// The diagonal with offset a of A times the diagonal with offset b of B
+// gives the diagonal a+b of C: C[i, i+a+b] += A[i, i+a] * B[i+a, i+a+b].
+// Returns the offsets of C and for every offset its contributions.
+fn plan<T: Scalar>(a: &DIA<T>, b: &DIA<T>) -> Vec<(isize, Vec<Contribution>)> {
+    let mut plan: Vec<(isize, Vec<Contribution>)> = vec![];
+
+    for (da, off_a) in a.offsets.iter().enumerate() {
+        let rows_a = a.diagonal_rows(*off_a);
+        for (db, off_b) in b.offsets.iter().enumerate() {
+            // Row i of C uses row i + off_a of B
+            let rows_b = b.diagonal_rows(*off_b);
+            let start = rows_a
+                .start
+                .max((rows_b.start as isize - off_a).max(0) as usize);
+            let end = rows_a
+                .end
+                .min((rows_b.end as isize - off_a).max(0) as usize);
+            if start >= end {
+                continue;
+            }
+
+            let off_c = off_a + off_b;
+            match plan.binary_search_by_key(&off_c, |(off, _)| *off) {
+                Ok(k) => plan[k].1.push((da, db, start..end)),
+                Err(k) => plan.insert(k, (off_c, vec![(da, db, start..end)])),
+            }
+        }
+    }
+
+    plan
+}
+
+// Rows first..first+out.len() of one diagonal of C, one value per row of C
+fn product_diagonal<T: Scalar>(
+    a: &DIA<T>,
+    b: &DIA<T>,
+    contributions: &[Contribution],
+    first: usize,
+    out: &mut [T],
+) {
+    let (m, k) = (a.shape.0, b.shape.0);
+    let last = first + out.len();
+
+    for (da, db, rows) in contributions {
+        let a_diag = &a.values[da * m..(da + 1) * m];
+        let b_diag = &b.values[db * k..(db + 1) * k];
+        let shift = a.offsets[*da];
+        for i in rows.start.max(first)..rows.end.min(last) {
+            out[i - first] += a_diag[i] * b_diag[(i as isize + shift) as usize];
+        }
+    }
+}
+
+fn assemble<T: Scalar>(a: &DIA<T>, b: &DIA<T>, offsets: Vec<isize>, diags: Vec<Vec<T>>) -> DIA<T> {
+    let mut res = DIA {
+        offsets,
+        values: diags.concat(),
+        shape: (a.shape.0, b.shape.1),
+        nnz: 0,
+    };
+
+    // nnz counts the non-zero entries inside the matrix
+    let m = res.shape.0;
+    res.nnz = res
+        .offsets
+        .iter()
+        .enumerate()
+        .map(|(d, off)| {
+            res.diagonal_rows(*off)
+                .filter(|i| res.values[d * m + i] != T::zero())
+                .count()
+        })
+        .sum();
+
+    res
+}
+
+// Rows per task in the parallel product
+const CHUNK_ROWS: usize = 4096;
+
+impl<T: Scalar> DiaProd<T> for DIA<T> {
+    // Product of two DIA matrices, the result is DIA again.
+    // The work is proportional to the number of diagonal pairs times the rows,
+    // independent of how the entries are distributed.
+    fn product_dia(&self, other: &DIA<T>) -> DIA<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+
+        let plan = plan(self, other);
+        let diags = plan
+            .iter()
+            .map(|(_, contributions)| {
+                let mut diag = vec![T::zero(); self.shape.0];
+                product_diagonal(self, other, contributions, 0, &mut diag);
+                diag
+            })
+            .collect();
+        let offsets = plan.into_iter().map(|(off, _)| off).collect();
+
+        assemble(self, other, offsets, diags)
+    }
+
+    // Band matrices have only a few diagonals, so the rows
+    // of each diagonal are split into chunks for the threads
+    fn product_dia_par(&self, other: &DIA<T>) -> DIA<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+
+        let plan = plan(self, other);
+        let diags = plan
+            .iter()
+            .map(|(_, contributions)| {
+                let mut diag = vec![T::zero(); self.shape.0];
+                diag.par_chunks_mut(CHUNK_ROWS)
+                    .enumerate()
+                    .for_each(|(c, out)| {
+                        product_diagonal(self, other, contributions, c * CHUNK_ROWS, out)
+                    });
+                diag
+            })
+            .collect();
+        let offsets = plan.into_iter().map(|(off, _)| off).collect();
+
+        assemble(self, other, offsets, diags)
+    }
+}
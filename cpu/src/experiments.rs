@@ -18,10 +18,10 @@ fn main() {
         println!("Loading k={}", k);
         let fname =
             Path::new(DATA_PATH).join(&Path::new(&format!("generated/large_{:04}_A.mtx", k)));
-        let a = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+        let a: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
         let fname =
             Path::new(DATA_PATH).join(&Path::new(&format!("generated/large_{:04}_B.mtx", k)));
-        let b = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+        let b: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
         // let fname = Path::new(DATA_PATH).join(&Path::new(&format!("generated/case_{:04}_C.mtx", k)));
         // let C = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
 
@@ -1,8 +1,13 @@
 // pub mod csr;
 // pub use csr::CSR;
 
+pub mod block;
 pub mod dense;
+pub mod diagonal;
+pub mod sliced;
 pub mod sparse;
+pub mod spmm;
+pub mod transpose;
 
 // #[cfg(test)]
 // mod tests {
@@ -69,13 +69,13 @@ fn main() {
 
     // let fname = Path::new("../matrix_instances/generated/case_0000_A.mtx");
     let fname = Path::new("matrix_instances/generated/sparse/sparse_30_B.mtx");
-    let a = COO::read_mtx(fname, true).expect(":(");
+    let a: COO = COO::read_mtx(fname, true).expect(":(");
 
     // a.to_dense().print();
 
     // let fname = Path::new("../matrix_instances/generated/case_0000_B.mtx");
     let fname = Path::new("matrix_instances/generated/sparse/sparse_30_A.mtx");
-    let b = COO::read_mtx(fname, true).expect(":(");
+    let b: COO = COO::read_mtx(fname, true).expect(":(");
 
     let a = CSR::from_coo(&a);
     let b = CSR::from_coo(&b);
@@ -0,0 +1,177 @@
+use rayon::prelude::*;
+
+use matrix_base::{Scalar, CSR, ELL, SELL};
+
+// CPU reference routines for the padded GPU formats ELL and SELL-C-sigma.
+// They compute the same as SparseProd on CSR, so the layouts can be compared
+// (and later checked against the WGSL kernels).
+pub trait SlicedProd<T: Scalar> {
+    // y = A*x
+    fn spmv(&self, x: &[T]) -> Vec<T>;
+    fn spmv_par(&self, x: &[T]) -> Vec<T>;
+    // C = A*B with B in CSR format
+    fn product_sparse(&self, other: &CSR<T>) -> CSR<T>;
+    fn product_sparse_par(&self, other: &CSR<T>) -> CSR<T>;
+}
+
+// Access to the stored rows, which may be a permutation of the original ones
+trait StoredRows<T: Scalar>: Sync {
+    fn shape(&self) -> (usize, usize);
+    // Original row of the k-th stored row
+    fn row_index(&self, k: usize) -> usize;
+    fn for_each_in_row<F: FnMut(usize, T)>(&self, k: usize, f: F);
+}
+
+impl<T: Scalar> StoredRows<T> for ELL<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+    fn row_index(&self, k: usize) -> usize {
+        k
+    }
+    fn for_each_in_row<F: FnMut(usize, T)>(&self, k: usize, mut f: F) {
+        self.get_row(k).for_each(|(j, x)| f(j, x));
+    }
+}
+
+impl<T: Scalar> StoredRows<T> for SELL<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+    fn row_index(&self, k: usize) -> usize {
+        self.perm[k]
+    }
+    fn for_each_in_row<F: FnMut(usize, T)>(&self, k: usize, mut f: F) {
+        self.get_sorted_row(k).for_each(|(j, x)| f(j, x));
+    }
+}
+
+fn row_dot<T: Scalar, A: StoredRows<T>>(a: &A, k: usize, x: &[T]) -> T {
+    let mut sum = T::zero();
+    a.for_each_in_row(k, |j, v| sum += v * x[j]);
+    sum
+}
+
+fn spmv<T: Scalar, A: StoredRows<T>>(a: &A, x: &[T], par: bool) -> Vec<T> {
+    let (m, n) = a.shape();
+    assert_eq!(n, x.len(), "Matrix and vector shapes don't match");
+
+    let sums: Vec<T> = if par {
+        (0..m).into_par_iter().map(|k| row_dot(a, k, x)).collect()
+    } else {
+        (0..m).map(|k| row_dot(a, k, x)).collect()
+    };
+
+    let mut y = vec![T::zero(); m];
+    for (k, sum) in sums.into_iter().enumerate() {
+        y[a.row_index(k)] = sum;
+    }
+    y
+}
+
+// Row k of C = A*B (Gustavson), returns sorted column indices and values.
+// acc and marker have length n and are left zeroed / false for the next row.
+fn product_row<T: Scalar, A: StoredRows<T>>(
+    a: &A,
+    b: &CSR<T>,
+    k: usize,
+    acc: &mut [T],
+    marker: &mut [bool],
+) -> (Vec<usize>, Vec<T>) {
+    let mut cols = vec![];
+
+    a.for_each_in_row(k, |l, a_kl| {
+        for pos in b.row_pos[l]..b.row_pos[l + 1] {
+            let j = b.col_pos[pos];
+            if !marker[j] {
+                marker[j] = true;
+                cols.push(j);
+            }
+            acc[j] += a_kl * b.values[pos];
+        }
+    });
+
+    cols.sort_unstable();
+    let values = cols
+        .iter()
+        .map(|&j| {
+            marker[j] = false;
+            std::mem::replace(&mut acc[j], T::zero())
+        })
+        .collect();
+
+    (cols, values)
+}
+
+fn product_sparse<T: Scalar, A: StoredRows<T>>(a: &A, b: &CSR<T>, par: bool) -> CSR<T> {
+    let (m, n_a) = a.shape();
+    assert_eq!(n_a, b.shape.0, "Matrix shapes don't match");
+    let n = b.shape.1;
+
+    let rows: Vec<(Vec<usize>, Vec<T>)> = if par {
+        (0..m)
+            .into_par_iter()
+            .map_init(
+                || (vec![T::zero(); n], vec![false; n]),
+                |(acc, marker), k| product_row(a, b, k, acc, marker),
+            )
+            .collect()
+    } else {
+        let (mut acc, mut marker) = (vec![T::zero(); n], vec![false; n]);
+        (0..m)
+            .map(|k| product_row(a, b, k, &mut acc, &mut marker))
+            .collect()
+    };
+
+    // Undo the row permutation
+    let mut sorted_rows: Vec<(Vec<usize>, Vec<T>)> = vec![(vec![], vec![]); m];
+    for (k, row) in rows.into_iter().enumerate() {
+        sorted_rows[a.row_index(k)] = row;
+    }
+
+    let mut row_pos = vec![0];
+    let mut col_pos = vec![];
+    let mut values = vec![];
+    for (cols, vals) in sorted_rows {
+        col_pos.extend(cols);
+        values.extend(vals);
+        row_pos.push(values.len());
+    }
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape: (m, n),
+    }
+}
+
+impl<T: Scalar> SlicedProd<T> for ELL<T> {
+    fn spmv(&self, x: &[T]) -> Vec<T> {
+        spmv(self, x, false)
+    }
+    fn spmv_par(&self, x: &[T]) -> Vec<T> {
+        spmv(self, x, true)
+    }
+    fn product_sparse(&self, other: &CSR<T>) -> CSR<T> {
+        product_sparse(self, other, false)
+    }
+    fn product_sparse_par(&self, other: &CSR<T>) -> CSR<T> {
+        product_sparse(self, other, true)
+    }
+}
+
+impl<T: Scalar> SlicedProd<T> for SELL<T> {
+    fn spmv(&self, x: &[T]) -> Vec<T> {
+        spmv(self, x, false)
+    }
+    fn spmv_par(&self, x: &[T]) -> Vec<T> {
+        spmv(self, x, true)
+    }
+    fn product_sparse(&self, other: &CSR<T>) -> CSR<T> {
+        product_sparse(self, other, false)
+    }
+    fn product_sparse_par(&self, other: &CSR<T>) -> CSR<T> {
+        product_sparse(self, other, true)
+    }
+}
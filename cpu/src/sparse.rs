@@ -2,23 +2,62 @@ use std::sync::{Arc, Mutex};
 
 use rayon::prelude::*;
 
-use matrix_base::{Dense, COO, CSR};
+use matrix_base::{AsCsrView, CsrView, Dense, Scalar, COO, CSR};
+
+// Implemented for CSR and CsrView, the other factor can be anything usable as a view,
+// e.g. a memory-mapped matrix: a.view().product_sparse_par(&MappedCsr::open(path)?)
+pub trait SparseProd<T: Scalar> {
+    fn product<B: AsCsrView<T>>(&self, other: &B) -> Dense<T>;
+    fn product_sparse<B: AsCsrView<T>>(&self, other: &B) -> CSR<T>;
+    fn product_sparse_par<B: AsCsrView<T>>(&self, other: &B) -> CSR<T>;
+    fn product_sparse_to_coo_par<B: AsCsrView<T>>(&self, other: &B) -> COO<T>;
+    // y = alpha*A*x + beta*y
+    fn spmv(&self, alpha: T, x: &[T], beta: T, y: &mut [T]);
+    fn spmv_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]);
+    // y = alpha*A^T*x + beta*y, without building the transpose
+    fn spmv_transpose(&self, alpha: T, x: &[T], beta: T, y: &mut [T]);
+    fn spmv_transpose_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]);
+}
+
+// Rows per task in the parallel SpMV
+const SPMV_CHUNK_ROWS: usize = 1024;
+
+fn row_dot<T: Scalar>(a: &CsrView<T>, i: usize, x: &[T]) -> T {
+    let mut sum = T::zero();
+    for pos in a.row_pos[i]..a.row_pos[i + 1] {
+        sum += a.values[pos] * x[a.col_pos[pos]];
+    }
+    sum
+}
 
-pub trait SparseProd {
-    fn product(&self, other: &CSR) -> Dense;
-    fn product_sparse(&self, other: &CSR) -> CSR;
-    fn product_sparse_par(&self, other: &CSR) -> CSR;
-    fn product_sparse_to_coo_par(&self, other: &CSR) -> COO;
+// alpha*ax + beta*y, like in BLAS y is not read for beta = 0 (it may contain NaN)
+fn update<T: Scalar>(alpha: T, ax: T, beta: T, y: T) -> T {
+    if beta == T::zero() {
+        alpha * ax
+    } else {
+        alpha * ax + beta * y
+    }
+}
+
+fn check_spmv_shapes<T: Scalar>(a: &CsrView<T>, x: &[T], y: &[T], transpose: bool) {
+    let (m, n) = if transpose {
+        (a.shape.1, a.shape.0)
+    } else {
+        a.shape
+    };
+    assert_eq!(x.len(), n, "Matrix and vector x shapes don't match");
+    assert_eq!(y.len(), m, "Matrix and vector y shapes don't match");
 }
 
-impl SparseProd for CSR {
+impl<T: Scalar> SparseProd<T> for CsrView<'_, T> {
     // Matrix/Matrix product, see seciton 3.2 from "A Systematic Survey of General Sparse Matrix-Matrix Multiplication", Gao et al.
     // https://doi.org/10.1145/3571157
     // Notation also from Paper
     // C = A*B
     // I_i(A) is the set of column indices of all non-zero entries of the i-th row of A
     // Returns dense matrix
-    fn product(&self, other: &CSR) -> Dense {
+    fn product<B: AsCsrView<T>>(&self, other: &B) -> Dense<T> {
+        let other = other.view();
         // let data = vec![];
         let m = self.shape.0;
         let n = other.shape.1;
@@ -54,12 +93,13 @@ impl SparseProd for CSR {
     // This is a modification for directly saving CSR
     // via dense intermediate results, suited for
     // parallel execution
-    fn product_sparse(&self, other: &CSR) -> CSR {
+    fn product_sparse<B: AsCsrView<T>>(&self, other: &B) -> CSR<T> {
+        let other = other.view();
         let m = self.shape.0;
         let n = other.shape.1;
         // let mut mat = Dense::new_zeros((m,n));
 
-        let mut res_rows: Vec<Vec<f64>> = vec![];
+        let mut res_rows: Vec<Vec<T>> = vec![];
         let mut res_col_idxs: Vec<Vec<usize>> = vec![];
 
         for i in 0..m {
@@ -69,7 +109,7 @@ impl SparseProd for CSR {
             // and also a bool array that flags if some non-zero
             // entry for the k-th (i.e. for C_{ik}) is calculated.
             let mut nz_row_marker = vec![false; n];
-            let mut res_curr_row = vec![0.; n];
+            let mut res_curr_row = vec![T::zero(); n];
 
             for col_pos_pos in self.row_pos[i]..self.row_pos[i + 1] {
                 let k = self.col_pos[col_pos_pos];
@@ -121,8 +161,6 @@ impl SparseProd for CSR {
         let col_pos = res_col_idxs.concat();
         let values = res_rows.concat();
 
-        row_pos.push(values.len());
-
         CSR {
             row_pos,
             col_pos,
@@ -131,11 +169,12 @@ impl SparseProd for CSR {
         }
     }
 
-    fn product_sparse_par(&self, other: &CSR) -> CSR {
+    fn product_sparse_par<B: AsCsrView<T>>(&self, other: &B) -> CSR<T> {
+        let other = other.view();
         let m = self.shape.0;
         let n = other.shape.1;
 
-        let res_rows: Arc<Mutex<Vec<(usize, Vec<f64>)>>> = Arc::new(Mutex::new(vec![]));
+        let res_rows: Arc<Mutex<Vec<(usize, Vec<T>)>>> = Arc::new(Mutex::new(vec![]));
         let res_col_idxs: Arc<Mutex<Vec<(usize, Vec<usize>)>>> = Arc::new(Mutex::new(vec![]));
 
         (0..m).into_par_iter().for_each(|i| {
@@ -145,7 +184,7 @@ impl SparseProd for CSR {
             // and also a bool array that flags if some non-zero
             // entry for the k-th (i.e. for C_{ik}) is calculated.
             let mut nz_row_marker = vec![false; n];
-            let mut res_curr_row = vec![0.; n];
+            let mut res_curr_row = vec![T::zero(); n];
 
             for col_pos_pos in self.row_pos[i]..self.row_pos[i + 1] {
                 let k = self.col_pos[col_pos_pos];
@@ -213,8 +252,6 @@ impl SparseProd for CSR {
             .flat_map(|(_, inner_vec)| inner_vec)
             .collect();
 
-        row_pos.push(values.len());
-
         CSR {
             row_pos,
             col_pos,
@@ -223,14 +260,15 @@ impl SparseProd for CSR {
         }
     }
 
-    fn product_sparse_to_coo_par(&self, other: &CSR) -> COO {
+    fn product_sparse_to_coo_par<B: AsCsrView<T>>(&self, other: &B) -> COO<T> {
+        let other = other.view();
         let m = self.shape.0;
         let n = other.shape.1;
 
-        let _res_rows: Arc<Mutex<Vec<(usize, Vec<f64>)>>> = Arc::new(Mutex::new(vec![]));
+        let _res_rows: Arc<Mutex<Vec<(usize, Vec<T>)>>> = Arc::new(Mutex::new(vec![]));
         let _res_col_idxs: Arc<Mutex<Vec<(usize, Vec<usize>)>>> = Arc::new(Mutex::new(vec![]));
 
-        let res_data: Arc<Mutex<Vec<(usize, usize, f64)>>> = Arc::new(Mutex::new(vec![]));
+        let res_data: Arc<Mutex<Vec<(usize, usize, T)>>> = Arc::new(Mutex::new(vec![]));
 
         (0..m).into_par_iter().for_each(|i| {
             // Iterate over all non-zero cols of A_{i*}
@@ -239,7 +277,7 @@ impl SparseProd for CSR {
             // and also a bool array that flags if some non-zero
             // entry for the k-th (i.e. for C_{ik}) is calculated.
             let mut nz_row_marker = vec![false; n];
-            let mut res_curr_row = vec![0.; n];
+            let mut res_curr_row = vec![T::zero(); n];
 
             for col_pos_pos in self.row_pos[i]..self.row_pos[i + 1] {
                 let k = self.col_pos[col_pos_pos];
@@ -262,7 +300,7 @@ impl SparseProd for CSR {
             // Save only non-zero entries and their index
             // let mut res_curr_row_final_val = vec![];
             // let mut res_curr_row_final_col_idx = vec![];
-            let mut data_thread: Vec<(usize, usize, f64)> = vec![];
+            let mut data_thread: Vec<(usize, usize, T)> = vec![];
             for ((k, x), marker) in res_curr_row
                 .iter()
                 .enumerate()
@@ -317,4 +355,97 @@ impl SparseProd for CSR {
             shape: (m, n),
         }
     }
+
+    fn spmv(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        check_spmv_shapes(self, x, y, false);
+        for (i, y_i) in y.iter_mut().enumerate() {
+            *y_i = update(alpha, row_dot(self, i, x), beta, *y_i);
+        }
+    }
+
+    fn spmv_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        check_spmv_shapes(self, x, y, false);
+        y.par_iter_mut()
+            .with_min_len(SPMV_CHUNK_ROWS)
+            .enumerate()
+            .for_each(|(i, y_i)| *y_i = update(alpha, row_dot(self, i, x), beta, *y_i));
+    }
+
+    // Row i of A scatters x_i * A_{i*} into y
+    fn spmv_transpose(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        check_spmv_shapes(self, x, y, true);
+        let mut ax = vec![T::zero(); y.len()];
+        for (i, x_i) in x.iter().enumerate() {
+            for pos in self.row_pos[i]..self.row_pos[i + 1] {
+                ax[self.col_pos[pos]] += self.values[pos] * *x_i;
+            }
+        }
+        for (y_j, ax_j) in y.iter_mut().zip(ax) {
+            *y_j = update(alpha, ax_j, beta, *y_j);
+        }
+    }
+
+    // The scattered rows would collide, so every task sums into its own
+    // vector and the vectors are added up at the end
+    fn spmv_transpose_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        check_spmv_shapes(self, x, y, true);
+        let n = y.len();
+        let ax = x
+            .par_iter()
+            .with_min_len(SPMV_CHUNK_ROWS)
+            .enumerate()
+            .fold(
+                || vec![T::zero(); n],
+                |mut ax, (i, x_i)| {
+                    for pos in self.row_pos[i]..self.row_pos[i + 1] {
+                        ax[self.col_pos[pos]] += self.values[pos] * *x_i;
+                    }
+                    ax
+                },
+            )
+            .reduce(
+                || vec![T::zero(); n],
+                |mut a, b| {
+                    a.iter_mut().zip(b).for_each(|(a_j, b_j)| *a_j += b_j);
+                    a
+                },
+            );
+        y.par_iter_mut()
+            .zip(ax)
+            .for_each(|(y_j, ax_j)| *y_j = update(alpha, ax_j, beta, *y_j));
+    }
+}
+
+impl<T: Scalar> SparseProd<T> for CSR<T> {
+    fn product<B: AsCsrView<T>>(&self, other: &B) -> Dense<T> {
+        self.view().product(other)
+    }
+
+    fn product_sparse<B: AsCsrView<T>>(&self, other: &B) -> CSR<T> {
+        self.view().product_sparse(other)
+    }
+
+    fn product_sparse_par<B: AsCsrView<T>>(&self, other: &B) -> CSR<T> {
+        self.view().product_sparse_par(other)
+    }
+
+    fn product_sparse_to_coo_par<B: AsCsrView<T>>(&self, other: &B) -> COO<T> {
+        self.view().product_sparse_to_coo_par(other)
+    }
+
+    fn spmv(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        self.view().spmv(alpha, x, beta, y)
+    }
+
+    fn spmv_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        self.view().spmv_par(alpha, x, beta, y)
+    }
+
+    fn spmv_transpose(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        self.view().spmv_transpose(alpha, x, beta, y)
+    }
+
+    fn spmv_transpose_par(&self, alpha: T, x: &[T], beta: T, y: &mut [T]) {
+        self.view().spmv_transpose_par(alpha, x, beta, y)
+    }
 }
@@ -0,0 +1,148 @@
+use rayon::prelude::*;
+
+use matrix_base::{Dense, Scalar, CSR};
+
+// Sparse x dense products (SpMM). The dense operand is used as it is,
+// instead of converting it to CSR with 100% fill and using SpGEMM.
+// The kernels work on rows, column-major operands are copied once (Dense::row_major_data).
+pub trait SparseDenseProd<T: Scalar> {
+    // C = A*B with A in CSR format, B dense
+    fn product_dense(&self, other: &Dense<T>) -> Dense<T>;
+    fn product_dense_par(&self, other: &Dense<T>) -> Dense<T>;
+    // Like product_dense_par, but B is used in blocks of block_cols columns,
+    // so a block stays in the cache for all rows of a task. For many right-hand sides.
+    fn product_dense_blocked_par(&self, other: &Dense<T>, block_cols: usize) -> Dense<T>;
+}
+
+pub trait DenseSparseProd<T: Scalar> {
+    // C = A*B with A dense, B in CSR format
+    fn product_csr(&self, other: &CSR<T>) -> Dense<T>;
+    fn product_csr_par(&self, other: &CSR<T>) -> Dense<T>;
+}
+
+// Rows per task in the blocked product
+const BLOCKED_CHUNK_ROWS: usize = 64;
+
+// Row i of C = sum over the entries a_ik of row i of A times row k of B,
+// only the columns cols of B and C
+// b is row-major with n columns
+fn csr_dense_row<T: Scalar>(
+    a: &CSR<T>,
+    b: &[T],
+    n: usize,
+    i: usize,
+    cols: (usize, usize),
+    c_row: &mut [T],
+) {
+    for pos in a.row_pos[i]..a.row_pos[i + 1] {
+        let a_ik = a.values[pos];
+        let k = a.col_pos[pos];
+        let b_row = &b[k * n + cols.0..k * n + cols.1];
+        for (c_ij, b_kj) in c_row.iter_mut().zip(b_row) {
+            *c_ij += a_ik * *b_kj;
+        }
+    }
+}
+
+// Row i of C = sum over k of a_ik times the sparse row k of B, a is row-major with p columns
+fn dense_csr_row<T: Scalar>(a: &[T], p: usize, b: &CSR<T>, i: usize, c_row: &mut [T]) {
+    for (k, a_ik) in a[i * p..(i + 1) * p].iter().enumerate() {
+        if *a_ik == T::zero() {
+            continue;
+        }
+        for pos in b.row_pos[k]..b.row_pos[k + 1] {
+            c_row[b.col_pos[pos]] += *a_ik * b.values[pos];
+        }
+    }
+}
+
+impl<T: Scalar> SparseDenseProd<T> for CSR<T> {
+    fn product_dense(&self, other: &Dense<T>) -> Dense<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+        let n = other.shape.1;
+
+        let mut res = Dense::new_zeros((self.shape.0, n));
+        if n == 0 {
+            return res;
+        }
+        let b = other.row_major_data();
+        for (i, c_row) in res.data.chunks_mut(n).enumerate() {
+            csr_dense_row(self, &b, n, i, (0, n), c_row);
+        }
+        res
+    }
+
+    fn product_dense_par(&self, other: &Dense<T>) -> Dense<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+        let n = other.shape.1;
+
+        let mut res = Dense::new_zeros((self.shape.0, n));
+        if n == 0 {
+            return res;
+        }
+        let b = other.row_major_data();
+        res.data
+            .par_chunks_mut(n)
+            .enumerate()
+            .for_each(|(i, c_row)| csr_dense_row(self, &b, n, i, (0, n), c_row));
+        res
+    }
+
+    fn product_dense_blocked_par(&self, other: &Dense<T>, block_cols: usize) -> Dense<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+        assert!(block_cols > 0, "Block width must not be zero");
+        let n = other.shape.1;
+
+        let mut res = Dense::new_zeros((self.shape.0, n));
+        if n == 0 {
+            return res;
+        }
+        let b = other.row_major_data();
+        res.data
+            .par_chunks_mut(BLOCKED_CHUNK_ROWS * n)
+            .enumerate()
+            .for_each(|(chunk, c_rows)| {
+                let first = chunk * BLOCKED_CHUNK_ROWS;
+                for start in (0..n).step_by(block_cols) {
+                    let end = (start + block_cols).min(n);
+                    for (r, c_row) in c_rows.chunks_mut(n).enumerate() {
+                        csr_dense_row(self, &b, n, first + r, (start, end), &mut c_row[start..end]);
+                    }
+                }
+            });
+        res
+    }
+}
+
+impl<T: Scalar> DenseSparseProd<T> for Dense<T> {
+    fn product_csr(&self, other: &CSR<T>) -> Dense<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+        let n = other.shape.1;
+
+        let mut res = Dense::new_zeros((self.shape.0, n));
+        if n == 0 {
+            return res;
+        }
+        let a = self.row_major_data();
+        for (i, c_row) in res.data.chunks_mut(n).enumerate() {
+            dense_csr_row(&a, self.shape.1, other, i, c_row);
+        }
+        res
+    }
+
+    fn product_csr_par(&self, other: &CSR<T>) -> Dense<T> {
+        assert_eq!(self.shape.1, other.shape.0, "Matrix shapes don't match");
+        let n = other.shape.1;
+
+        let mut res = Dense::new_zeros((self.shape.0, n));
+        if n == 0 {
+            return res;
+        }
+        let a = self.row_major_data();
+        res.data
+            .par_chunks_mut(n)
+            .enumerate()
+            .for_each(|(i, c_row)| dense_csr_row(&a, self.shape.1, other, i, c_row));
+        res
+    }
+}
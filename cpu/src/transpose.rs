@@ -0,0 +1,147 @@
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use matrix_base::csr::{inverse_permutation, is_permutation};
+use matrix_base::{Scalar, CSR};
+
+// Parallel versions of CSR::transpose and CSR::permuted_transpose,
+// e.g. for A^T A: a.transpose_par().product_sparse_par(&a)
+pub trait SparseTranspose<T: Scalar> {
+    fn transpose_par(&self) -> CSR<T>;
+    fn permuted_transpose_par(&self, row_perm: &[usize], col_perm: &[usize]) -> CSR<T>;
+}
+
+// Rows of the result per task when merging the partial transposes
+const BAND_ROWS: usize = 4096;
+
+// Transpose of the rows k in ks of P A, the columns are the global k
+struct Partial<T> {
+    row_pos: Vec<usize>,
+    col_pos: Vec<usize>,
+    values: Vec<T>,
+}
+
+fn transpose_part<T, F, G>(a: &CSR<T>, ks: Range<usize>, old_row: &F, new_col: &G) -> Partial<T>
+where
+    T: Scalar,
+    F: Fn(usize) -> usize,
+    G: Fn(usize) -> usize,
+{
+    let n = a.shape.1;
+
+    let mut row_pos = vec![0; n + 1];
+    for k in ks.clone() {
+        let i = old_row(k);
+        for pos in a.row_pos[i]..a.row_pos[i + 1] {
+            row_pos[new_col(a.col_pos[pos]) + 1] += 1;
+        }
+    }
+    for j in 0..n {
+        row_pos[j + 1] += row_pos[j];
+    }
+
+    let nnz = row_pos[n];
+    let mut next = row_pos.clone();
+    let mut col_pos = vec![0; nnz];
+    let mut values = vec![T::zero(); nnz];
+    for k in ks {
+        let i = old_row(k);
+        for pos in a.row_pos[i]..a.row_pos[i + 1] {
+            let j = new_col(a.col_pos[pos]);
+            col_pos[next[j]] = k;
+            values[next[j]] = a.values[pos];
+            next[j] += 1;
+        }
+    }
+
+    Partial {
+        row_pos,
+        col_pos,
+        values,
+    }
+}
+
+// Every thread transposes a contiguous part of the rows, then row j of the result
+// is the concatenation of row j of all parts. The parts are in row order,
+// so the columns stay sorted like in the sequential version.
+fn transpose_par_with<T, F, G>(a: &CSR<T>, old_row: F, new_col: G) -> CSR<T>
+where
+    T: Scalar,
+    F: Fn(usize) -> usize + Sync,
+    G: Fn(usize) -> usize + Sync,
+{
+    let (m, n) = a.shape;
+    let part_rows = m.div_ceil(rayon::current_num_threads()).max(1);
+
+    let parts: Vec<Partial<T>> = (0..m.div_ceil(part_rows))
+        .into_par_iter()
+        .map(|p| {
+            let ks = p * part_rows..((p + 1) * part_rows).min(m);
+            transpose_part(a, ks, &old_row, &new_col)
+        })
+        .collect();
+
+    let mut row_pos = vec![0; n + 1];
+    for j in 0..n {
+        let len: usize = parts.iter().map(|p| p.row_pos[j + 1] - p.row_pos[j]).sum();
+        row_pos[j + 1] = row_pos[j] + len;
+    }
+
+    // Split the result into bands of rows, every band is filled by one task
+    let nnz = row_pos[n];
+    let mut col_pos = vec![0; nnz];
+    let mut values = vec![T::zero(); nnz];
+    let mut bands = vec![];
+    let (mut col_rest, mut val_rest) = (&mut col_pos[..], &mut values[..]);
+    for start in (0..n).step_by(BAND_ROWS) {
+        let end = (start + BAND_ROWS).min(n);
+        let len = row_pos[end] - row_pos[start];
+        let (col_band, col_tail) = col_rest.split_at_mut(len);
+        let (val_band, val_tail) = val_rest.split_at_mut(len);
+        bands.push((start..end, col_band, val_band));
+        (col_rest, val_rest) = (col_tail, val_tail);
+    }
+
+    bands
+        .into_par_iter()
+        .for_each(|(rows, col_band, val_band)| {
+            let mut next = 0;
+            for j in rows {
+                for p in &parts {
+                    let range = p.row_pos[j]..p.row_pos[j + 1];
+                    let len = range.len();
+                    col_band[next..next + len].copy_from_slice(&p.col_pos[range.clone()]);
+                    val_band[next..next + len].copy_from_slice(&p.values[range]);
+                    next += len;
+                }
+            }
+        });
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape: (n, m),
+    }
+}
+
+impl<T: Scalar> SparseTranspose<T> for CSR<T> {
+    fn transpose_par(&self) -> CSR<T> {
+        transpose_par_with(self, |k| k, |j| j)
+    }
+
+    // See CSR::permuted_transpose
+    fn permuted_transpose_par(&self, row_perm: &[usize], col_perm: &[usize]) -> CSR<T> {
+        assert!(
+            row_perm.len() == self.shape.0 && is_permutation(row_perm),
+            "Invalid row permutation"
+        );
+        assert!(
+            col_perm.len() == self.shape.1 && is_permutation(col_perm),
+            "Invalid column permutation"
+        );
+        let new_col = inverse_permutation(col_perm);
+        transpose_par_with(self, |k| row_perm[k], |j| new_col[j])
+    }
+}
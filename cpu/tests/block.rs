@@ -0,0 +1,63 @@
+use fakscpu::block::BlockProd;
+use fakscpu::sparse::SparseProd;
+use matrix_base::{Dense, BSR, COO, CSR};
+
+#[cfg(test)]
+fn cmp_dense(a: &Dense, b: &Dense, eps: f64) -> bool {
+    let mut res = true;
+
+    for (x, y) in a.data.iter().zip(b.data.iter()) {
+        res = res && ((x - y).abs() < eps);
+    }
+
+    res
+}
+
+// Sparse test matrix with some pattern, sizes are no multiples of the block sizes
+#[cfg(test)]
+fn pattern_matrix(shape: (usize, usize), seed: usize) -> COO {
+    let mut data = vec![];
+    for i in 0..shape.0 {
+        for j in 0..shape.1 {
+            if (i * 7 + j * 3 + seed).is_multiple_of(5) {
+                data.push((i, j, (i + 2 * j + seed) as f64 / 10.));
+            }
+        }
+    }
+    COO { data, shape }
+}
+
+#[test]
+fn test_product_block() {
+    let eps = 1e-10;
+
+    let a = pattern_matrix((10, 13), 1);
+    let b = pattern_matrix((13, 9), 2);
+    let c = CSR::from_coo(&a).product(&CSR::from_coo(&b));
+    let c_sparse = CSR::from_coo(&a).product_sparse(&CSR::from_coo(&b));
+
+    for (block_a, block_b) in [
+        ((1, 1), (1, 1)),
+        ((2, 3), (3, 2)),
+        ((4, 4), (4, 4)),
+        ((3, 5), (5, 1)),
+    ] {
+        println!("Testing blocks {:?} x {:?}", block_a, block_b);
+
+        let a_bsr = BSR::from_coo(&a, block_a);
+        let b_bsr = BSR::from_coo(&b, block_b);
+
+        let c_test = a_bsr.product_block(&b_bsr);
+        assert_eq!(c_test.block_shape, (block_a.0, block_b.1));
+        assert!(cmp_dense(&c, &c_test.to_dense(), eps));
+
+        let c_test_par = a_bsr.product_block_par(&b_bsr);
+        assert_eq!(c_test.row_pos, c_test_par.row_pos);
+        assert_eq!(c_test.col_pos, c_test_par.col_pos);
+        assert!(cmp_dense(&c, &c_test_par.to_dense(), eps));
+        assert_eq!(c_test.nnz, c_test_par.nnz);
+        // Same entries as the scalar SpGEMM computes, the rest is padding
+        assert_eq!(c_test.nnz, c_sparse.values.len());
+        assert_eq!(c_test.padding(), c_test.values.len() - c_sparse.values.len());
+    }
+}
@@ -0,0 +1,64 @@
+use fakscpu::diagonal::DiaProd;
+use fakscpu::sparse::SparseProd;
+use matrix_base::{Dense, COO, CSR, DIA};
+
+#[cfg(test)]
+fn cmp_dense(a: &Dense, b: &Dense, eps: f64) -> bool {
+    let mut res = true;
+
+    for (x, y) in a.data.iter().zip(b.data.iter()) {
+        res = res && ((x - y).abs() < eps);
+    }
+
+    res
+}
+
+// Band matrix with the given diagonals, like generate_toeplitz.py
+#[cfg(test)]
+fn band_matrix(shape: (usize, usize), offsets: &[isize]) -> COO {
+    let mut data = vec![];
+    for i in 0..shape.0 {
+        for off in offsets {
+            let j = i as isize + off;
+            if j >= 0 && (j as usize) < shape.1 {
+                data.push((i, j as usize, (i as f64 + 2. * *off as f64) / 10. + 1.));
+            }
+        }
+    }
+    COO { data, shape }
+}
+
+#[test]
+fn test_product_dia() {
+    let eps = 1e-10;
+
+    let cases = [
+        ((10, 10), vec![-1, 0, 1], (10, 10), vec![-1, 0, 1]),
+        ((7, 12), vec![-2, 0, 3], (12, 5), vec![-4, 1]),
+        ((12, 5), vec![-6, -1, 2], (5, 9), vec![0, 4, 8]),
+    ];
+
+    for (shape_a, offsets_a, shape_b, offsets_b) in cases {
+        println!("Testing {:?} x {:?}", offsets_a, offsets_b);
+
+        let a = CSR::from_coo(&band_matrix(shape_a, &offsets_a));
+        let b = CSR::from_coo(&band_matrix(shape_b, &offsets_b));
+        let c = a.product(&b);
+
+        let a_dia = DIA::from_csr(&a);
+        let b_dia = DIA::from_csr(&b);
+
+        let c_test = a_dia.product_dia(&b_dia);
+        assert_eq!(c_test.shape, c.shape);
+        assert!(cmp_dense(&c, &c_test.to_dense(), eps));
+
+        let c_test_par = a_dia.product_dia_par(&b_dia);
+        assert_eq!(c_test.offsets, c_test_par.offsets);
+        assert_eq!(c_test.nnz, c_test_par.nnz);
+        assert!(cmp_dense(&c, &c_test_par.to_dense(), eps));
+    }
+
+    // Tridiagonal times tridiagonal is pentadiagonal
+    let a = DIA::from_coo(&band_matrix((20, 20), &[-1, 0, 1]));
+    assert_eq!(a.product_dia(&a).offsets, vec![-2, -1, 0, 1, 2]);
+}
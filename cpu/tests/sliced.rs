@@ -0,0 +1,80 @@
+use fakscpu::sliced::SlicedProd;
+use fakscpu::sparse::SparseProd;
+use matrix_base::{Dense, COO, CSR, ELL, SELL};
+
+#[cfg(test)]
+fn cmp_dense(a: &Dense, b: &Dense, eps: f64) -> bool {
+    let mut res = true;
+
+    for (x, y) in a.data.iter().zip(b.data.iter()) {
+        res = res && ((x - y).abs() < eps);
+    }
+
+    res
+}
+
+// Sparse test matrix with irregular row lengths
+#[cfg(test)]
+fn pattern_matrix(shape: (usize, usize), seed: usize) -> COO {
+    let mut data = vec![];
+    for i in 0..shape.0 {
+        for j in 0..shape.1 {
+            if (i * j + seed).is_multiple_of(3) || (i + seed).is_multiple_of(4) {
+                data.push((i, j, (i + 2 * j + seed) as f64 / 10.));
+            }
+        }
+    }
+    COO { data, shape }
+}
+
+#[test]
+fn test_spmv_sliced() {
+    let eps = 1e-10;
+
+    let a = pattern_matrix((11, 7), 1);
+    let x: Vec<f64> = (0..7).map(|j| j as f64 - 3.).collect();
+    let dense = a.to_dense();
+    let y: Vec<f64> = (0..11)
+        .map(|i| (0..7).map(|j| dense.get(i, j) * x[j]).sum())
+        .collect();
+
+    let csr = CSR::from_coo(&a);
+    let ell = ELL::from_csr(&csr);
+    let sell = SELL::from_csr(&csr, 4, 8);
+
+    for y_test in [
+        ell.spmv(&x),
+        ell.spmv_par(&x),
+        sell.spmv(&x),
+        sell.spmv_par(&x),
+    ] {
+        assert_eq!(y_test.len(), y.len());
+        for (u, v) in y.iter().zip(y_test) {
+            assert!((u - v).abs() < eps);
+        }
+    }
+}
+
+#[test]
+fn test_product_sliced() {
+    let eps = 1e-10;
+
+    let a = CSR::from_coo(&pattern_matrix((11, 7), 1));
+    let b = CSR::from_coo(&pattern_matrix((7, 9), 2));
+    let c = a.product(&b);
+
+    let ell = ELL::from_csr(&a);
+    for (chunk_size, sigma) in [(1, 1), (4, 1), (4, 8), (32, 32)] {
+        println!("Testing SELL-{}-{}", chunk_size, sigma);
+        let sell = SELL::from_csr(&a, chunk_size, sigma);
+
+        for c_test in [
+            ell.product_sparse(&b),
+            ell.product_sparse_par(&b),
+            sell.product_sparse(&b),
+            sell.product_sparse_par(&b),
+        ] {
+            assert!(cmp_dense(&c, &c_test.to_dense(), eps));
+        }
+    }
+}
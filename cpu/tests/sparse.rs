@@ -1,7 +1,7 @@
 use std::path::Path;
 
 use fakscpu::sparse::SparseProd;
-use matrix_base::{Dense, COO, CSR};
+use matrix_base::{AsCsrView, Dense, MappedCsr, COO, CSR};
 
 // Im Endeffekt etwas umständlich über Path joinen.
 // Kann man auch mit String-Concat machen, aber
@@ -157,3 +157,101 @@ fn test_product_csr_sparse_to_coo_par() {
         // assert!(false);
     }
 }
+
+#[test]
+fn test_product_csr_sparse_valid() {
+    // The sparse products must give valid CSR structures,
+    // including zero rows at the end
+    let fname = Path::new(DATA_PATH).join(&Path::new("a001.mtx"));
+    let a: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let a = CSR::from_coo(&a);
+    let zero_rows = CSR {
+        row_pos: vec![0, 1, 1, 1],
+        col_pos: vec![2],
+        values: vec![1.],
+        shape: (3, 3),
+    };
+
+    for (x, y) in [(&a, &a), (&zero_rows, &a), (&a, &zero_rows)] {
+        assert_eq!(x.product_sparse(y).validate(), Ok(()));
+        assert_eq!(x.product_sparse_par(y).validate(), Ok(()));
+    }
+}
+
+#[test]
+fn test_spmv() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let a: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let a = CSR::from_coo(&a);
+    let a_dense = a.to_dense();
+    let (m, n) = a.shape;
+
+    let x: Vec<f64> = (0..n).map(|j| 1. + (j % 7) as f64 / 7.).collect();
+    let x_t: Vec<f64> = (0..m).map(|i| 1. - (i % 5) as f64 / 5.).collect();
+    let y0: Vec<f64> = (0..m).map(|i| i as f64).collect();
+    let y0_t: Vec<f64> = (0..n).map(|j| -(j as f64)).collect();
+
+    // Dense reference for y = 2*A*x + 0.5*y and y = 2*A^T*x + 0.5*y
+    let expected: Vec<f64> = (0..m)
+        .map(|i| 2. * (0..n).map(|j| a_dense.get(i, j) * x[j]).sum::<f64>() + 0.5 * y0[i])
+        .collect();
+    let expected_t: Vec<f64> = (0..n)
+        .map(|j| 2. * (0..m).map(|i| a_dense.get(i, j) * x_t[i]).sum::<f64>() + 0.5 * y0_t[j])
+        .collect();
+
+    let cmp =
+        |y: &[f64], expected: &[f64]| y.iter().zip(expected).all(|(a, b)| (a - b).abs() < eps);
+
+    let mut y = y0.clone();
+    a.spmv(2., &x, 0.5, &mut y);
+    assert!(cmp(&y, &expected));
+    let mut y = y0.clone();
+    a.spmv_par(2., &x, 0.5, &mut y);
+    assert!(cmp(&y, &expected));
+
+    let mut y = y0_t.clone();
+    a.spmv_transpose(2., &x_t, 0.5, &mut y);
+    assert!(cmp(&y, &expected_t));
+    let mut y = y0_t.clone();
+    a.spmv_transpose_par(2., &x_t, 0.5, &mut y);
+    assert!(cmp(&y, &expected_t));
+
+    // For beta = 0 the old content of y is ignored, even NaN
+    let mut y = vec![f64::NAN; m];
+    a.spmv_par(1., &x, 0., &mut y);
+    let mut y_ref = vec![0.; m];
+    a.spmv(1., &x, 1., &mut y_ref);
+    assert!(cmp(&y, &y_ref));
+}
+
+#[test]
+fn test_product_mapped() {
+    // Products on a memory-mapped file must match the ones on the loaded matrix
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let b: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let b = CSR::from_coo(&b);
+    let bt = b.transpose();
+
+    let bin = std::env::temp_dir().join("fakscpu_test_mapped.bin");
+    b.save_bin_mappable(&bin)
+        .expect("Failed writing matrix during test");
+    let mapped: MappedCsr = MappedCsr::open(&bin).expect("Failed mapping matrix during test");
+    let view = mapped.view();
+
+    let c = bt.product_sparse_par(&b);
+    let c_mapped = bt.product_sparse_par(&mapped);
+    assert_eq!(c_mapped.row_pos, c.row_pos);
+    assert_eq!(c_mapped.col_pos, c.col_pos);
+    assert_eq!(c_mapped.values, c.values);
+
+    let c_view = view.product_sparse(&bt);
+    assert_eq!(c_view.values, b.product_sparse(&bt).values);
+
+    let x: Vec<f64> = (0..b.shape.1).map(|j| j as f64).collect();
+    let (mut y, mut y_mapped) = (vec![0.; b.shape.0], vec![0.; b.shape.0]);
+    b.spmv_par(1., &x, 0., &mut y);
+    view.spmv_par(1., &x, 0., &mut y_mapped);
+    assert_eq!(y, y_mapped);
+}
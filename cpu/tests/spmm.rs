@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use fakscpu::dense::DenseProd;
+use fakscpu::spmm::{DenseSparseProd, SparseDenseProd};
+use matrix_base::{Dense, COO, CSR};
+
+const DATA_PATH: &str = "../matrix_instances";
+
+#[cfg(test)]
+fn cmp_dense(a: &Dense, b: &Dense, eps: f64) -> bool {
+    let mut res = a.shape == b.shape;
+
+    for (x, y) in a.data.iter().zip(b.data.iter()) {
+        res = res && ((x - y).abs() < eps);
+    }
+
+    res
+}
+
+// Dense (m,n)-matrix with a few zeros
+#[cfg(test)]
+fn dense_matrix(shape: (usize, usize)) -> Dense {
+    let mut mat = Dense::new_zeros(shape);
+    for i in 0..shape.0 {
+        for j in 0..shape.1 {
+            if (i + 2 * j) % 5 != 0 {
+                mat.set(i, j, ((i * 7 + j * 3) % 11) as f64 / 10. - 0.4);
+            }
+        }
+    }
+    mat
+}
+
+#[test]
+fn test_product_csr_dense() {
+    let eps = 1e-10;
+
+    // bigB001 is 839x121
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let a: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let a_csr = CSR::from_coo(&a);
+    let a_dense = a.to_dense();
+
+    for n in [1, 5, 130] {
+        println!("Testing n={}", n);
+        let b = dense_matrix((a_csr.shape.1, n));
+        let c = a_dense.product_dense_par(&b);
+
+        assert!(cmp_dense(&a_csr.product_dense(&b), &c, eps));
+        assert!(cmp_dense(&a_csr.product_dense_par(&b), &c, eps));
+        for block_cols in [1, 16, 200] {
+            assert!(cmp_dense(
+                &a_csr.product_dense_blocked_par(&b, block_cols),
+                &c,
+                eps
+            ));
+        }
+    }
+}
+
+#[test]
+fn test_product_dense_csr() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let b: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let b_csr = CSR::from_coo(&b);
+    let b_dense = b.to_dense();
+
+    for m in [1, 7, 100] {
+        println!("Testing m={}", m);
+        let a = dense_matrix((m, b_csr.shape.0));
+        let c = a.product_dense_par(&b_dense);
+
+        assert!(cmp_dense(&a.product_csr(&b_csr), &c, eps));
+        assert!(cmp_dense(&a.product_csr_par(&b_csr), &c, eps));
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use fakscpu::transpose::SparseTranspose;
+use matrix_base::{COO, CSR};
+
+const DATA_PATH: &str = "../matrix_instances";
+
+#[cfg(test)]
+fn assert_csr_eq(a: &CSR, b: &CSR) {
+    assert_eq!(a.shape, b.shape);
+    assert_eq!(a.row_pos, b.row_pos);
+    assert_eq!(a.col_pos, b.col_pos);
+    assert_eq!(a.values, b.values);
+}
+
+#[test]
+fn test_transpose_par() {
+    for name in ["a001.mtx", "a002.mtx", "bigB001.mtx"] {
+        println!("Testing {}", name);
+        let fname = Path::new(DATA_PATH).join(name);
+        let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+        let a = CSR::from_coo(&coo);
+        let (m, n) = a.shape;
+
+        let t = a.transpose_par();
+        assert_eq!(t.validate(), Ok(()));
+        assert_csr_eq(&t, &a.transpose());
+
+        // Reverse rows, rotate columns
+        let row_perm: Vec<usize> = (0..m).rev().collect();
+        let col_perm: Vec<usize> = (0..n).map(|j| (j + 3) % n).collect();
+        let t = a.permuted_transpose_par(&row_perm, &col_perm);
+        assert_eq!(t.validate(), Ok(()));
+        assert_csr_eq(&t, &a.permuted_transpose(&row_perm, &col_perm));
+    }
+}
@@ -4,7 +4,7 @@ use cublas_sys::{cublasCreate_v2, cublasDestroy_v2, cublasGetStream_v2, cublasHa
 use cust::error::CudaResult;
 use cust::memory::*;
 use cust::sys::cuStreamSynchronize;
-use matrix_base::Dense;
+use matrix_base::{Dense, Layout};
 use std::ptr;
 
 
@@ -26,15 +26,17 @@ pub fn multiply(matrix1: &Dense, matrix2: &Dense) -> CudaResult<(Dense, u128, u1
     let mut handle: cublasHandle_t = ptr::null_mut();
     unsafe { cublasCreate_v2(&mut handle) };
 
-    // Convert matrices for cuBLAS (f64 to f32, and RowMajor to ColumnMajor)
+    // Convert matrices for cuBLAS (f64 to f32), the layout is kept:
+    // cuBLAS erwartet ColumnMajor, eine RowMajor-Matrix ist die ColumnMajor-Speicherung
+    // ihrer Transponierten und wird daher ohne Umsortieren mit CUBLAS_OP_T übergeben.
     // Diese Konvertierungen auf der CPU sind Teil des Overheads, aber nicht H2D/D2H.
     // Wir können sie zum `initialization_us` in main.rs hinzufügen.
-    let matrix1_col_major = matrix1.as_column_major();
-    let matrix2_col_major = matrix2.as_column_major();
+    let (op_a, lda) = cublas_operand(matrix1);
+    let (op_b, ldb) = cublas_operand(matrix2);
 
-    let a: Vec<f32> = matrix1_col_major.data.iter().map(|&x| x as f32).collect();
-    let b: Vec<f32> = matrix2_col_major.data.iter().map(|&x| x as f32).collect();
-    let mut c: Vec<f32> = vec![0.0; matrix1_col_major.shape.0 * matrix2_col_major.shape.1];
+    let a: Vec<f32> = matrix1.data.iter().map(|&x| x as f32).collect();
+    let b: Vec<f32> = matrix2.data.iter().map(|&x| x as f32).collect();
+    let mut c: Vec<f32> = vec![0.0; matrix1.shape.0 * matrix2.shape.1];
 
     // NEU: H2D Zeitmessung beginnt
     let start_h2d_measure = std::time::Instant::now();
@@ -47,9 +49,9 @@ pub fn multiply(matrix1: &Dense, matrix2: &Dense) -> CudaResult<(Dense, u128, u1
     // Set cuBLAS parameters
     let alpha: f32 = 1.0;
     let beta: f32 = 0.0;
-    let m = matrix1_col_major.shape.0 as i32;
-    let k = matrix1_col_major.shape.1 as i32;
-    let n = matrix2_col_major.shape.1 as i32;
+    let m = matrix1.shape.0 as i32;
+    let k = matrix1.shape.1 as i32;
+    let n = matrix2.shape.1 as i32;
 
     // Get cuBLAS stream (optional, but good practice if using async operations)
     let mut stream: *mut Struct_CUstream_st = ptr::null_mut();
@@ -60,16 +62,16 @@ pub fn multiply(matrix1: &Dense, matrix2: &Dense) -> CudaResult<(Dense, u128, u1
     unsafe {
         cublasSgemm_v2(
             handle,
-            cublas_sys::cublasOperation_t::CUBLAS_OP_N, // No transpose A
-            cublas_sys::cublasOperation_t::CUBLAS_OP_N, // No transpose B
+            op_a, // transpose A if it is RowMajor
+            op_b, // transpose B if it is RowMajor
             m, // rows of A and C
             n, // cols of B and C
             k, // cols of A and rows of B
             &alpha,
             d_a.as_device_ptr().as_ptr(),
-            m, // leading dimension of A (rows of A)
+            lda, // leading dimension of A (rows of A, columns if RowMajor)
             d_b.as_device_ptr().as_ptr(),
-            k, // leading dimension of B (rows of B - since B is ColMajor, it's k)
+            ldb, // leading dimension of B (rows of B, columns if RowMajor)
             &beta,
             d_c.as_device_ptr().as_mut_ptr(),
             m, // leading dimension of C (rows of C)
@@ -94,10 +96,19 @@ pub fn multiply(matrix1: &Dense, matrix2: &Dense) -> CudaResult<(Dense, u128, u1
     let result_data_f64: Vec<f64> = c.iter().map(|&x| x as f64).collect();
     let result_dense_col_major = Dense {
         data: result_data_f64,
-        shape: (matrix1_col_major.shape.0, matrix2_col_major.shape.1),
+        shape: (matrix1.shape.0, matrix2.shape.1),
+        layout: Layout::ColumnMajor,
     };
     let result_dense_row_major = result_dense_col_major.as_row_major();
 
     // Return the result matrix, raw multiply time, total time, H2D time, D2H time
     Ok((result_dense_row_major, time_raw_multiply, time_total, time_h2d, time_d2h))
-}
\ No newline at end of file
+}
+
+// Operation and leading dimension for a matrix in its own layout
+fn cublas_operand(matrix: &Dense) -> (cublas_sys::cublasOperation_t, i32) {
+    match matrix.layout {
+        Layout::ColumnMajor => (cublas_sys::cublasOperation_t::CUBLAS_OP_N, matrix.shape.0.max(1) as i32),
+        Layout::RowMajor => (cublas_sys::cublasOperation_t::CUBLAS_OP_T, matrix.shape.1.max(1) as i32),
+    }
+}
@@ -52,3 +52,35 @@ fn test_product_blas() {
         assert!(cmp_dense(&c, &c_test, eps));
     }
 }
+
+#[test]
+fn test_product_layouts() {
+    let eps = 1e-4;
+
+    let mut a = Dense::new_zeros((5, 4));
+    for i in 0..5 {
+        for j in 0..4 {
+            a.set(i, j, (i * 7 + j * 3) as f64 - 5.0);
+        }
+    }
+    let mut b = Dense::new_zeros((4, 3));
+    for i in 0..4 {
+        for j in 0..3 {
+            b.set(i, j, 0.5 * (i + 2 * j) as f64 - 1.0);
+        }
+    }
+    let c = &a * &b;
+
+    // RowMajor und ColumnMajor werden ohne Umsortieren übergeben
+    for a in [a.clone(), a.as_column_major()] {
+        for b in [b.clone(), b.as_column_major()] {
+            let (c_test, _, _, _, _) = multiply(&a, &b).unwrap();
+            assert_eq!(c_test.shape, c.shape);
+            for i in 0..c.shape.0 {
+                for j in 0..c.shape.1 {
+                    assert!((c_test.get(i, j) - c.get(i, j)).abs() < eps);
+                }
+            }
+        }
+    }
+}
@@ -21,6 +21,12 @@ pub fn multiply(matrix1: &CSR, matrix2: &CSR) -> CudaResult<(CSR, u128, u128, u1
     // Ensure the matrices can be multiplied
     assert_eq!(matrix1.shape.1, matrix2.shape.0);
 
+    // Broken CSR structures give garbage on the GPU, so fail early in debug builds
+    if cfg!(debug_assertions) {
+        matrix1.validate().unwrap_or_else(|e| panic!("matrix1 is no valid CSR matrix: {}", e));
+        matrix2.validate().unwrap_or_else(|e| panic!("matrix2 is no valid CSR matrix: {}", e));
+    }
+
     // Matrix dimensions and non-zero counts
     let rows1 = matrix1.shape.0 as i64;
     let cols1 = matrix1.shape.1 as i64;
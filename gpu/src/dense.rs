@@ -114,8 +114,8 @@ pub fn multiply_for_benchmark(
     let size_of_result = (std::mem::size_of::<f32>() * matrix1.shape.0 * matrix2.shape.1) as u64;
     let size_of_dims = (std::mem::size_of::<u32>() * 3) as u64;
 
-    let matrix1_data_f32: Vec<f32> = matrix1.data.iter().map(|&x| x as f32).collect();
-    let matrix2_data_f32: Vec<f32> = matrix2.data.iter().map(|&x| x as f32).collect();
+    let matrix1_data_f32: Vec<f32> = matrix1.row_major_data().iter().map(|&x| x as f32).collect();
+    let matrix2_data_f32: Vec<f32> = matrix2.row_major_data().iter().map(|&x| x as f32).collect();
 
     let buffer_a = device.create_buffer(&wgpu::BufferDescriptor {
         label: Some("Matrix A Buffer"), // Add labels for easier debugging
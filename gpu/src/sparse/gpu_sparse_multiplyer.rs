@@ -32,6 +32,12 @@ impl<'a> GPUSparseMultiplyer {
         batch_size: usize,
         wgpu_task_in: WgpuTask,
     ) -> (Self, TimingResult) {
+        // Broken CSR structures give garbage on the GPU, so fail early in debug builds
+        if cfg!(debug_assertions) {
+            a.validate().unwrap_or_else(|e| panic!("a is no valid CSR matrix: {}", e));
+            b.validate().unwrap_or_else(|e| panic!("b is no valid CSR matrix: {}", e));
+        }
+
         let start_total_new = std::time::Instant::now();
         let start_init_resources = std::time::Instant::now();
 
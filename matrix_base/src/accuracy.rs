@@ -0,0 +1,254 @@
+use std::fmt;
+
+use crate::{Dense, RealScalar, Scalar, COO, CSC, CSR};
+
+// Comparison of a computed result with a reference, independent of the formats.
+// Entries missing in one of the matrices count as 0, so a CSR result can be compared
+// with a Dense reference or a COO result without explicit zeros with one that has them.
+
+// Entries of a matrix sorted by (row, column), duplicates summed up
+pub trait MatrixEntries<T> {
+    fn shape(&self) -> (usize, usize);
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)>;
+}
+
+// Sort and sum up duplicates
+fn canonical<T: Scalar>(mut data: Vec<(usize, usize, T)>) -> Vec<(usize, usize, T)> {
+    data.sort_by_key(|a| (a.0, a.1));
+    let mut merged: Vec<(usize, usize, T)> = Vec::with_capacity(data.len());
+    for (i, j, x) in data {
+        match merged.last_mut() {
+            Some(last) if (last.0, last.1) == (i, j) => last.2 += x,
+            _ => merged.push((i, j, x)),
+        }
+    }
+    merged
+}
+
+impl<T: Scalar> MatrixEntries<T> for Dense<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)> {
+        let (m, n) = self.shape;
+        (0..m)
+            .flat_map(|i| (0..n).map(move |j| (i, j)))
+            .map(|(i, j)| (i, j, self.get(i, j)))
+            .filter(|(_, _, x)| *x != T::zero())
+            .collect()
+    }
+}
+
+impl<T: Scalar> MatrixEntries<T> for COO<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)> {
+        canonical(self.data.clone())
+    }
+}
+
+impl<T: Scalar> MatrixEntries<T> for CSR<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)> {
+        canonical(self.to_coo().data)
+    }
+}
+
+impl<T: Scalar> MatrixEntries<T> for CSC<T> {
+    fn shape(&self) -> (usize, usize) {
+        self.shape
+    }
+
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)> {
+        canonical(self.to_coo().data)
+    }
+}
+
+// Vectors, e.g. results of SpMV, are compared as (n, 1)-matrices
+impl<T: Scalar> MatrixEntries<T> for [T] {
+    fn shape(&self) -> (usize, usize) {
+        (self.len(), 1)
+    }
+
+    fn sorted_entries(&self) -> Vec<(usize, usize, T)> {
+        self.iter()
+            .enumerate()
+            .filter(|(_, x)| **x != T::zero())
+            .map(|(i, x)| (i, 0, *x))
+            .collect()
+    }
+}
+
+// Number of the worst entries kept in AccuracyReport::worst
+const WORST_ENTRIES: usize = 5;
+
+// Errors of a result compared with a reference, see compare.
+// Absolute errors are measured in the precision of the scalar type, relative ones as f64.
+#[derive(Debug, Clone)]
+pub struct AccuracyReport<T = f64> {
+    // ||result - reference||_F / ||reference||_F (0 if both are 0, infinite if only the reference is)
+    pub relative_error: f64,
+    // max |result_ij - reference_ij| / max |reference_ij|
+    pub relative_error_max: f64,
+    // max |result_ij - reference_ij|
+    pub max_abs_error: f64,
+    // max |result_ij - reference_ij| / |reference_ij|, infinite if the result has an entry where the reference is 0
+    pub max_componentwise_error: f64,
+    // Largest distance in units in the last place, see Scalar::ulp_distance
+    pub max_ulp: u64,
+    // Positions where only one of both matrices has a non-zero entry
+    pub pattern_mismatches: usize,
+    // Entries with the largest absolute errors, (row, column, result, reference)
+    pub worst: Vec<(usize, usize, T, T)>,
+}
+
+impl<T: Scalar> AccuracyReport<T> {
+    // Result is as accurate as tolerance, relative to the Frobenius norm of the reference
+    pub fn is_within(&self, tolerance: f64) -> bool {
+        self.relative_error <= tolerance
+    }
+}
+
+impl<T: Scalar> fmt::Display for AccuracyReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "relative error {:.2e} (max entry {:.2e}), max abs. error {:.2e}, max {} ULP, {} pattern mismatches",
+            self.relative_error,
+            self.relative_error_max,
+            self.max_abs_error,
+            self.max_ulp,
+            self.pattern_mismatches
+        )?;
+        for (i, j, x, y) in &self.worst {
+            write!(f, "\n  ({}, {}): {} instead of {}", i, j, x, y)?;
+        }
+        Ok(())
+    }
+}
+
+// Compare result with reference entry by entry, None if the shapes differ
+pub fn compare<T, A, B>(result: &A, reference: &B) -> Option<AccuracyReport<T>>
+where
+    T: Scalar,
+    A: MatrixEntries<T> + ?Sized,
+    B: MatrixEntries<T> + ?Sized,
+{
+    if result.shape() != reference.shape() {
+        return None;
+    }
+    let a = result.sorted_entries();
+    let b = reference.sorted_entries();
+
+    let abs = |x: T| x.abs().to_f64();
+    let mut diff_squares = 0.;
+    let mut reference_squares = 0.;
+    let mut reference_max: f64 = 0.;
+    let mut report = AccuracyReport {
+        relative_error: 0.,
+        relative_error_max: 0.,
+        max_abs_error: 0.,
+        max_componentwise_error: 0.,
+        max_ulp: 0,
+        pattern_mismatches: 0,
+        worst: vec![],
+    };
+    let mut errors: Vec<(f64, usize, usize, T, T)> = vec![];
+    // NaN counts as the largest error, once it appears the maximum stays NaN
+    let max = |max: f64, x: f64| if x > max || x.is_nan() { x } else { max };
+
+    // Merge both sorted lists, missing entries are 0
+    let (mut p, mut q) = (0, 0);
+    while p < a.len() || q < b.len() {
+        let (i, j, x, y) = match (a.get(p), b.get(q)) {
+            (Some(&(i, j, x)), Some(&(k, l, y))) if (i, j) == (k, l) => {
+                p += 1;
+                q += 1;
+                (i, j, x, y)
+            }
+            (Some(&(i, j, x)), Some(&(k, l, _))) if (i, j) < (k, l) => {
+                p += 1;
+                (i, j, x, T::zero())
+            }
+            (Some(&(i, j, x)), None) => {
+                p += 1;
+                (i, j, x, T::zero())
+            }
+            (_, Some(&(k, l, y))) => {
+                q += 1;
+                (k, l, T::zero(), y)
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let error = abs(x - y);
+        if (x == T::zero()) != (y == T::zero()) {
+            report.pattern_mismatches += 1;
+        }
+        diff_squares += error * error;
+        reference_squares += abs(y) * abs(y);
+        reference_max = max(reference_max, abs(y));
+        report.max_abs_error = max(report.max_abs_error, error);
+        report.max_ulp = report.max_ulp.max(x.ulp_distance(y));
+        if error > 0. || error.is_nan() {
+            let componentwise = error / abs(y);
+            report.max_componentwise_error = max(report.max_componentwise_error, componentwise);
+            errors.push((error, i, j, x, y));
+        }
+    }
+
+    let relative = |error: f64, norm: f64| {
+        if error == 0. {
+            0.
+        } else if norm == 0. {
+            f64::INFINITY
+        } else {
+            error / norm
+        }
+    };
+    report.relative_error = relative(diff_squares.sqrt(), reference_squares.sqrt());
+    report.relative_error_max = relative(report.max_abs_error, reference_max);
+
+    // Largest errors first (NaN before all others), only the first few are sorted
+    let larger = |e1: &(f64, usize, usize, T, T), e2: &(f64, usize, usize, T, T)| {
+        e2.0.is_nan().cmp(&e1.0.is_nan()).then(e2.0.total_cmp(&e1.0))
+    };
+    if errors.len() > WORST_ENTRIES {
+        errors.select_nth_unstable_by(WORST_ENTRIES, larger);
+        errors.truncate(WORST_ENTRIES);
+    }
+    errors.sort_by(larger);
+    report.worst = errors
+        .into_iter()
+        .take(WORST_ENTRIES)
+        .map(|(_, i, j, x, y)| (i, j, x, y))
+        .collect();
+    Some(report)
+}
+
+// ||result - reference||_F / ||reference||_F, infinite if the shapes differ
+pub fn relative_error<T, A, B>(result: &A, reference: &B) -> f64
+where
+    T: Scalar,
+    A: MatrixEntries<T> + ?Sized,
+    B: MatrixEntries<T> + ?Sized,
+{
+    compare(result, reference).map_or(f64::INFINITY, |report| report.relative_error)
+}
+
+// Largest distance of two entries in units in the last place, u64::MAX if the shapes differ.
+// Only meaningful in the precision the result was computed in, e.g. compare f32 results as f32.
+pub fn max_ulp_distance<T, A, B>(result: &A, reference: &B) -> u64
+where
+    T: Scalar,
+    A: MatrixEntries<T> + ?Sized,
+    B: MatrixEntries<T> + ?Sized,
+{
+    compare(result, reference).map_or(u64::MAX, |report| report.max_ulp)
+}
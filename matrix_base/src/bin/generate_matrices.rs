@@ -0,0 +1,95 @@
+use std::env;
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use matrix_base::generate::ExperimentSet;
+
+const USAGE: &str = "Usage: generate_matrices [options] [folder]
+Writes seeded random matrix pairs into the subfolders dense, sparse and sparse-vs-dense of folder
+(default matrix_instances/generated), named like the ones of generate_matrices_experiments.py.
+  --seed N            seed, the same seed gives the same matrices (default 0)
+  --examples N        number of pairs per size or density (default 1)
+  --dense N,...       sizes of the n x n dense pairs dense_<n>_A<k>.mtx
+  --sparse N,...      sizes of the n x n sparse pairs sparse_<n>_A<k>.mtx
+  --sparsity X        density of the sparse pairs (default 0.001)
+  --s-vs-d X,...      densities of the sparse-vs-dense pairs s-vs-d_<x>_A<k>.mtx
+  --s-vs-d-size N     size of the sparse-vs-dense pairs (default 500)
+  --structured S,...  structured pairs sparse_<name>_A<k>.mtx:
+                      lap5:N, lap9:N (2D Laplacian, N x N grid), lap7:N, lap27:N (3D, N^3 grid),
+                      band:N:W (N x N, diagonals -W..=W), rmat:S (R-MAT graph, 2^S vertices),
+                      powerlaw:N (N x N, power-law row lengths)";
+
+// Generates the matrices of the benchmarks without a Python environment, e.g.
+// generate_matrices --seed 1 --dense 100,200,500 --s-vs-d 0.05,0.1,0.2
+fn main() -> ExitCode {
+    let mut set = ExperimentSet::default();
+    let mut seed = 0;
+    let mut folder = String::from("matrix_instances/generated");
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            opt if opt.starts_with("--") => args.next(),
+            _ => {
+                folder = arg;
+                continue;
+            }
+        };
+        let parsed = match (arg.as_str(), value.as_deref()) {
+            (_, None) => None,
+            ("--seed", Some(v)) => v.parse().ok().map(|v| seed = v),
+            ("--examples", Some(v)) => v.parse().ok().map(|v| set.examples = v),
+            ("--dense", Some(v)) => parse_list(v).map(|v| set.dense_sizes = v),
+            ("--sparse", Some(v)) => parse_list(v).map(|v| set.sparse_sizes = v),
+            ("--sparsity", Some(v)) => v.parse().ok().map(|v| set.sparse_density = v),
+            ("--s-vs-d", Some(v)) => parse_list(v).map(|v| set.sparse_vs_dense_densities = v),
+            ("--s-vs-d-size", Some(v)) => v.parse().ok().map(|v| set.size_sparse_vs_dense = v),
+            ("--structured", Some(v)) => parse_list(v).map(|v| set.structured = v),
+            _ => None,
+        };
+        if parsed.is_none() {
+            eprintln!(
+                "Invalid option {} {}\n\n{}",
+                arg,
+                value.unwrap_or_default(),
+                USAGE
+            );
+            return ExitCode::FAILURE;
+        }
+    }
+
+    if set.dense_sizes.is_empty()
+        && set.sparse_sizes.is_empty()
+        && set.sparse_vs_dense_densities.is_empty()
+        && set.structured.is_empty()
+    {
+        eprintln!(
+            "Nothing to generate, give at least one of --dense, --sparse, --s-vs-d, --structured\n\n{}",
+            USAGE
+        );
+        return ExitCode::FAILURE;
+    }
+
+    match set.write(Path::new(&folder), seed) {
+        Ok(written) => {
+            for fname in written {
+                println!("{}", fname.display());
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Comma separated list like 100,200,500
+fn parse_list<V: FromStr>(s: &str) -> Option<Vec<V>> {
+    s.split(',').map(|v| v.trim().parse().ok()).collect()
+}
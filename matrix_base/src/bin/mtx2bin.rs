@@ -0,0 +1,57 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use matrix_base::{binary, mtx, COO};
+
+// Fills the binary cache for mtx-files, so the first benchmark run doesn't have to parse them.
+// Usage: mtx2bin <file or folder>...
+// Folders are searched (not recursively) for .mtx files (also compressed ones), the cache is written next to each file.
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("Usage: mtx2bin <file or folder>...");
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    for path in args.iter().flat_map(|arg| mtx_files(Path::new(arg))) {
+        match COO::<f64>::read_mtx_cached(&path) {
+            Ok(_) if binary::cache_path(&path).exists() => {
+                println!("{}", binary::cache_path(&path).display())
+            }
+            Ok(_) => {
+                eprintln!("{}: could not write the cache", path.display());
+                failed = true;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn mtx_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|file| file.is_file() && mtx::is_mtx_path(file))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
@@ -0,0 +1,353 @@
+use std::any::TypeId;
+use std::fs::{self, File};
+use std::marker::PhantomData;
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::slice;
+
+use memmap2::Mmap;
+
+use crate::csr::{AsCsrView, CsrView};
+use crate::{Complex32, Complex64, Error, Scalar};
+
+// Binary file format for Dense, COO and CSR, much faster to load than mtx-files.
+// Everything is little-endian. The header has 48 bytes:
+//   0..8    magic "FAKSMAT\0"
+//   8..12   version (u32)
+//   12      matrix kind: 0 = Dense, 1 = COO, 2 = CSR
+//   13      scalar type: 1 = f32, 2 = f64, 3 = Complex32, 4 = Complex64
+//   14      index width in bytes: 4 (u32) or 8 (u64)
+//   15      reserved, 0
+//   16..24  rows (u64)
+//   24..32  columns (u64)
+//   32..40  number of stored values (u64), rows*columns for Dense
+//   40..48  checksum of everything after the header (FNV-1a over u64 words)
+// The payload are the index arrays followed by the values (complex: real, imaginary part):
+//   Dense: values, row-major
+//   COO:   row indices, column indices, values
+//   CSR:   row_pos, col_pos, values
+// With 8 byte indices all arrays start at multiples of 8 bytes, so a CSR file can be mapped (MappedCsr).
+pub const MAGIC: [u8; 8] = *b"FAKSMAT\0";
+pub const VERSION: u32 = 1;
+const HEADER_LEN: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kind {
+    Dense = 0,
+    Coo = 1,
+    Csr = 2,
+}
+
+// Matrix read from a binary file, before it is put into the matrix struct
+pub(crate) struct Content<T> {
+    pub shape: (usize, usize),
+    pub indices: Vec<Vec<usize>>,
+    pub values: Vec<T>,
+}
+
+// By type, not by size, MappedCsr relies on T being exactly one of these
+fn scalar_code<T: Scalar>() -> u8 {
+    let id = TypeId::of::<T>();
+    if id == TypeId::of::<f32>() {
+        1
+    } else if id == TypeId::of::<f64>() {
+        2
+    } else if id == TypeId::of::<Complex32>() {
+        3
+    } else if id == TypeId::of::<Complex64>() {
+        4
+    } else {
+        unreachable!("Unknown scalar type")
+    }
+}
+
+// Bytes per real component of T
+fn component_width<T: Scalar>() -> usize {
+    std::mem::size_of::<T>() / if T::IS_COMPLEX { 2 } else { 1 }
+}
+
+fn checksum(bytes: &[u8]) -> u64 {
+    const PRIME: u64 = 0x100000001b3;
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut words = bytes.chunks_exact(8);
+    for word in &mut words {
+        hash = (hash ^ u64::from_le_bytes(word.try_into().unwrap())).wrapping_mul(PRIME);
+    }
+    for byte in words.remainder() {
+        hash = (hash ^ *byte as u64).wrapping_mul(PRIME);
+    }
+    hash
+}
+
+// Writes the header and payload, see above.
+// Indices are stored as u32 if all of them (and the number of values) fit, unless wide is set.
+pub(crate) fn write<T: Scalar>(
+    fname: &Path,
+    kind: Kind,
+    shape: (usize, usize),
+    indices: &[&[usize]],
+    values: &[T],
+    wide: bool,
+) -> Result<(), Error> {
+    let max_index = indices
+        .iter()
+        .flat_map(|idx| idx.iter().copied())
+        .chain([shape.0, shape.1, values.len()])
+        .max()
+        .unwrap_or(0);
+    let index_width = if !wide && max_index <= u32::MAX as usize {
+        4
+    } else {
+        8
+    };
+    let width = component_width::<T>();
+
+    let index_count: usize = indices.iter().map(|idx| idx.len()).sum();
+    let value_parts = if T::IS_COMPLEX { 2 } else { 1 };
+    let mut payload =
+        Vec::with_capacity(index_count * index_width + values.len() * value_parts * width);
+    for idx in indices {
+        for i in idx.iter() {
+            if index_width == 4 {
+                payload.extend_from_slice(&(*i as u32).to_le_bytes());
+            } else {
+                payload.extend_from_slice(&(*i as u64).to_le_bytes());
+            }
+        }
+    }
+    for x in values {
+        let (re, im) = x.to_parts();
+        let parts = [re, im];
+        for part in &parts[..value_parts] {
+            if width == 4 {
+                payload.extend_from_slice(&(*part as f32).to_le_bytes());
+            } else {
+                payload.extend_from_slice(&part.to_le_bytes());
+            }
+        }
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&[kind as u8, scalar_code::<T>(), index_width as u8, 0]);
+    bytes.extend_from_slice(&(shape.0 as u64).to_le_bytes());
+    bytes.extend_from_slice(&(shape.1 as u64).to_le_bytes());
+    bytes.extend_from_slice(&(values.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    fs::write(fname, bytes).map_err(|e| Error::io(fname, e))
+}
+
+// Header fields that describe the payload
+struct Header {
+    shape: (usize, usize),
+    nnz: usize,
+    index_width: usize,
+}
+
+fn invalid(fname: &Path, msg: String) -> Error {
+    Error::Binary {
+        path: fname.to_path_buf(),
+        msg,
+    }
+}
+
+// Checks the header and that the payload has the size given by it and the right checksum.
+// index_lens gives the lengths of the index arrays from the shape and the number of values.
+fn check<T: Scalar>(
+    fname: &Path,
+    bytes: &[u8],
+    kind: Kind,
+    index_lens: impl Fn((usize, usize), usize) -> Vec<usize>,
+) -> Result<(Header, Vec<usize>), Error> {
+    let invalid = |msg: String| invalid(fname, msg);
+
+    if bytes.len() < HEADER_LEN || bytes[0..8] != MAGIC {
+        return Err(invalid("not a binary matrix file".to_string()));
+    }
+    let u64_at = |pos: usize| u64::from_le_bytes(bytes[pos..pos + 8].try_into().unwrap());
+    let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+    if version != VERSION {
+        return Err(invalid(format!("unsupported version {}", version)));
+    }
+    if bytes[12] != kind as u8 {
+        return Err(invalid(format!(
+            "stores matrix kind {}, expected {} ({:?})",
+            bytes[12], kind as u8, kind
+        )));
+    }
+    if bytes[13] != scalar_code::<T>() {
+        return Err(invalid(format!(
+            "stores scalar type {}, expected {}",
+            bytes[13],
+            scalar_code::<T>()
+        )));
+    }
+    let index_width = bytes[14] as usize;
+    if index_width != 4 && index_width != 8 {
+        return Err(invalid(format!("invalid index width {}", index_width)));
+    }
+    let shape = (u64_at(16) as usize, u64_at(24) as usize);
+    let nnz = u64_at(32) as usize;
+
+    let lens = index_lens(shape, nnz);
+    let value_parts = if T::IS_COMPLEX { 2 } else { 1 };
+    let width = component_width::<T>();
+    // u128, a broken header must not overflow
+    let expected = lens.iter().map(|len| *len as u128).sum::<u128>() * index_width as u128
+        + nnz as u128 * (value_parts * width) as u128;
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() as u128 != expected {
+        return Err(invalid(format!(
+            "expected {} bytes after the header, found {}",
+            expected,
+            payload.len()
+        )));
+    }
+    if checksum(payload) != u64_at(40) {
+        return Err(invalid("checksum mismatch".to_string()));
+    }
+
+    let header = Header {
+        shape,
+        nnz,
+        index_width,
+    };
+    Ok((header, lens))
+}
+
+// Reads a file written by write, see check for index_lens
+pub(crate) fn read<T: Scalar>(
+    fname: &Path,
+    kind: Kind,
+    index_lens: impl Fn((usize, usize), usize) -> Vec<usize>,
+) -> Result<Content<T>, Error> {
+    let bytes = fs::read(fname).map_err(|e| Error::io(fname, e))?;
+    let (header, lens) = check::<T>(fname, &bytes, kind, index_lens)?;
+    let index_width = header.index_width;
+    let value_parts = if T::IS_COMPLEX { 2 } else { 1 };
+    let width = component_width::<T>();
+    let payload = &bytes[HEADER_LEN..];
+
+    let mut pos = 0;
+    let mut indices = Vec::with_capacity(lens.len());
+    for len in lens {
+        let part = &payload[pos..pos + len * index_width];
+        pos += part.len();
+        indices.push(if index_width == 4 {
+            part.chunks_exact(4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+                .collect()
+        } else {
+            part.chunks_exact(8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as usize)
+                .collect()
+        });
+    }
+
+    let component = |b: &[u8]| {
+        if width == 4 {
+            f32::from_le_bytes(b.try_into().unwrap()) as f64
+        } else {
+            f64::from_le_bytes(b.try_into().unwrap())
+        }
+    };
+    let values = payload[pos..]
+        .chunks_exact(value_parts * width)
+        .map(|b| {
+            if T::IS_COMPLEX {
+                T::from_parts(component(&b[..width]), component(&b[width..]))
+            } else {
+                T::from_parts(component(b), 0.)
+            }
+        })
+        .collect();
+
+    Ok(Content {
+        shape: header.shape,
+        indices,
+        values,
+    })
+}
+
+// CSR file mapped into memory, the matrix is used in place through view() without
+// loading it, so it may be larger than the RAM. Only files with 8 byte indices
+// can be mapped (CSR::save_bin_mappable), the indices are then used as usize.
+// As with every mapping, the file must not be changed while it is mapped.
+pub struct MappedCsr<T = f64> {
+    mmap: Mmap,
+    shape: (usize, usize),
+    nnz: usize,
+    _scalar: PhantomData<T>,
+}
+
+impl<T: Scalar> MappedCsr<T> {
+    // Checks the header and the checksum (this reads the file once, but doesn't keep it in memory)
+    pub fn open(fname: &Path) -> Result<Self, Error> {
+        if !cfg!(target_endian = "little") || size_of::<usize>() != 8 {
+            return Err(invalid(
+                fname,
+                "mapping needs a little-endian target with 64 bit usize".to_string(),
+            ));
+        }
+        let file = File::open(fname).map_err(|e| Error::io(fname, e))?;
+        // Safety: the file is only read, changing it while mapped is documented above
+        let mmap = unsafe { Mmap::map(&file) }.map_err(|e| Error::io(fname, e))?;
+        let (header, _) = check::<T>(fname, &mmap, Kind::Csr, |shape, nnz| {
+            vec![shape.0.saturating_add(1), nnz]
+        })?;
+        if header.index_width != 8 {
+            return Err(invalid(
+                fname,
+                "stores 4 byte indices, write it with CSR::save_bin_mappable".to_string(),
+            ));
+        }
+
+        Ok(MappedCsr {
+            mmap,
+            shape: header.shape,
+            nnz: header.nnz,
+            _scalar: PhantomData,
+        })
+    }
+}
+
+impl<T: Scalar> AsCsrView<T> for MappedCsr<T> {
+    fn view(&self) -> CsrView<'_, T> {
+        let m = self.shape.0;
+        let base = self.mmap[HEADER_LEN..].as_ptr();
+        // Safety: open checked the size of the payload. The mapping is page aligned
+        // and all arrays start at multiples of 8 bytes, enough for usize and T.
+        // T is one of the types of scalar_code, which have the layout of the file
+        // (Complex is repr(C) with real and imaginary part) and no invalid bit patterns.
+        unsafe {
+            CsrView {
+                row_pos: slice::from_raw_parts(base as *const usize, m + 1),
+                col_pos: slice::from_raw_parts(base.add((m + 1) * 8) as *const usize, self.nnz),
+                values: slice::from_raw_parts(
+                    base.add((m + 1 + self.nnz) * 8) as *const T,
+                    self.nnz,
+                ),
+                shape: self.shape,
+            }
+        }
+    }
+}
+
+// Path of the binary cache of a mtx-file, next to it: foo.mtx -> foo.mtx.bin
+pub fn cache_path(mtx_fname: &Path) -> PathBuf {
+    let mut name = mtx_fname.as_os_str().to_os_string();
+    name.push(".bin");
+    PathBuf::from(name)
+}
+
+// The cache is used if it is at least as new as the mtx-file
+pub(crate) fn cache_is_fresh(mtx_fname: &Path, bin_fname: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(mtx_fname), modified(bin_fname)) {
+        (Some(mtx), Some(bin)) => bin >= mtx,
+        _ => false,
+    }
+}
@@ -0,0 +1,183 @@
+use std::path::Path;
+
+use crate::mtx::MtxWriteOptions;
+use crate::{Dense, Error, RealScalar, Scalar, COO, CSR};
+
+// Block sparse row format: CSR where every entry is a dense r x c block.
+// row_pos and col_pos work like in CSR, but index block rows and block columns.
+// The k-th block is stored row-major in values[k*r*c..(k+1)*r*c].
+// Blocks at the right and bottom border may stick out of the matrix
+// if the shape is not a multiple of the block shape, these parts are zero.
+pub struct BSR<T = f64> {
+    pub row_pos: Vec<usize>,
+    pub col_pos: Vec<usize>,
+    pub values: Vec<T>,
+    pub block_shape: (usize, usize),
+    pub shape: (usize, usize),
+    // Number of entries that carry data, the rest of values is fill-in (padding()).
+    // From COO/CSR these are the distinct stored positions, explicit zeros included.
+    // For a block product (fakscpu::block) the positions that got at least one product
+    // of two non-zero entries, also if the sum cancels, like the pattern of the scalar SpGEMM.
+    pub nnz: usize,
+}
+
+impl<T: Scalar> BSR<T> {
+    // Duplicate entries are summed up
+    pub fn from_coo(coo: &COO<T>, block_shape: (usize, usize)) -> Self {
+        let (r, c) = block_shape;
+        assert!(r > 0 && c > 0, "Block shape must not be zero");
+
+        // Sort by block, then by position. Equal positions are neighbours then.
+        let mut data = coo.data.clone();
+        data.sort_by_key(|&(i, j, _)| (i / r, j / c, i, j));
+
+        let mb = coo.shape.0.div_ceil(r);
+        let mut row_pos = vec![0; mb + 1];
+        let mut col_pos = vec![];
+        let mut values = vec![];
+        let mut nnz = 0;
+
+        let mut last_block = None;
+        let mut last_entry = None;
+        for (i, j, x) in data {
+            let block = (i / r, j / c);
+            if last_block != Some(block) {
+                // New block, filled with zeros
+                row_pos[block.0 + 1] += 1;
+                col_pos.push(block.1);
+                values.extend(std::iter::repeat_n(T::zero(), r * c));
+                last_block = Some(block);
+            }
+            if last_entry != Some((i, j)) {
+                nnz += 1;
+                last_entry = Some((i, j));
+            }
+            let offset = values.len() - r * c;
+            values[offset + (i % r) * c + j % c] += x;
+        }
+
+        // Number of blocks per block row -> start of block rows
+        for k in 0..mb {
+            row_pos[k + 1] += row_pos[k];
+        }
+
+        BSR {
+            row_pos,
+            col_pos,
+            values,
+            block_shape,
+            shape: coo.shape,
+            nnz,
+        }
+    }
+
+    pub fn from_csr(csr: &CSR<T>, block_shape: (usize, usize)) -> Self {
+        Self::from_coo(&csr.to_coo(), block_shape)
+    }
+
+    // Number of stored blocks
+    pub fn block_count(&self) -> usize {
+        self.col_pos.len()
+    }
+
+    // Number of stored zeros that come from filling up the blocks
+    pub fn padding(&self) -> usize {
+        self.values.len() - self.nnz
+    }
+
+    // Stored values per original entry, 1.0 means no fill-in at all
+    pub fn fill_ratio(&self) -> f64 {
+        if self.nnz == 0 {
+            return 1.;
+        }
+        self.values.len() as f64 / self.nnz as f64
+    }
+
+    // Block column indices and block values of block row k
+    pub fn get_block_row(&self, k: usize) -> (&[usize], &[T]) {
+        let (r, c) = self.block_shape;
+        let range = self.row_pos[k]..self.row_pos[k + 1];
+        (
+            &self.col_pos[range.clone()],
+            &self.values[range.start * r * c..range.end * r * c],
+        )
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Sparse ({},{})-matrix in BSR format with {} ({},{})-blocks, {} entries and {} padding",
+            self.shape.0,
+            self.shape.1,
+            self.block_count(),
+            self.block_shape.0,
+            self.block_shape.1,
+            self.nnz,
+            self.padding()
+        );
+        println!("Row Pos {:?}", self.row_pos);
+        println!("Col Pos {:?}", self.col_pos);
+        println!("Values {:?}", self.values);
+    }
+
+    // Write mtx-file, see MtxWriteOptions for the possible formats
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        self.to_coo().write_mtx(fname, opts)
+    }
+
+    pub fn to_dense(&self) -> Dense<T> {
+        let mut mat = Dense::new_zeros(self.shape);
+
+        for (i, j, x) in self.entries() {
+            mat.set(i, j, x);
+        }
+
+        mat
+    }
+
+    // Zeros inside the blocks are dropped. That includes the padding,
+    // but also zeros that were stored explicitly in the original matrix.
+    pub fn to_coo(&self) -> COO<T> {
+        COO {
+            data: self.entries().filter(|(_, _, x)| *x != T::zero()).collect(),
+            shape: self.shape,
+        }
+    }
+
+    pub fn to_csr(&self) -> CSR<T> {
+        CSR::from_coo(&self.to_coo())
+    }
+
+    // All stored entries inside the matrix shape, row by row
+    fn entries(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        let (r, c) = self.block_shape;
+        let (m, n) = self.shape;
+
+        (0..self.row_pos.len() - 1).flat_map(move |bi| {
+            (bi * r..m.min((bi + 1) * r)).flat_map(move |i| {
+                (self.row_pos[bi]..self.row_pos[bi + 1]).flat_map(move |k| {
+                    let bj = self.col_pos[k];
+                    let row = &self.values[k * r * c + (i % r) * c..k * r * c + (i % r + 1) * c];
+                    (bj * c..n.min((bj + 1) * c)).map(move |j| (i, j, row[j % c]))
+                })
+            })
+        })
+    }
+}
+
+impl<T: RealScalar> BSR<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> BSR<U> {
+        BSR {
+            row_pos: self.row_pos.clone(),
+            col_pos: self.col_pos.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|x| U::from_f64(x.to_f64()))
+                .collect(),
+            block_shape: self.block_shape,
+            shape: self.shape,
+            nnz: self.nnz,
+        }
+    }
+}
@@ -1,83 +1,151 @@
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::Dense;
+use rayon::prelude::*;
 
-pub struct COO {
-    pub data: Vec<(usize, usize, f64)>,
+use crate::binary::{self, Kind};
+use crate::mtx::{self, MtxFormat, MtxReader, MtxWriteOptions};
+use crate::npy;
+use crate::rb;
+use crate::{Dense, Error, RealScalar, Scalar, StructureError};
+
+pub struct COO<T = f64> {
+    pub data: Vec<(usize, usize, T)>,
     pub shape: (usize, usize),
 }
 
-impl COO {
+// What COO::canonicalize should do, the default gives
+// sorted entries without duplicates, like CSR needs them
+pub struct CanonicalizeOptions {
+    // Sort by row, then by column
+    pub sort: bool,
+    // Replace entries with the same index by their sum
+    pub sum_duplicates: bool,
+    // Drop entries with absolute value <= tolerance (after summing).
+    // Some(0.) drops only exact zeros, None keeps all entries.
+    pub drop_zeros: Option<f64>,
+}
+
+impl Default for CanonicalizeOptions {
+    fn default() -> Self {
+        CanonicalizeOptions {
+            sort: true,
+            sum_duplicates: true,
+            drop_zeros: None,
+        }
+    }
+}
+
+impl<T: Scalar> COO<T> {
     // Read mtx-file, its honestly almost the same thing as
     // in the last homeworks, so I didn't comment too much
     // If sort_data is true, the coordiante data is sorted by indices
-    pub fn read_mtx(fname: &Path, sort_data: bool) -> Result<Self, &str> {
+    // The MatrixMarket banner is honoured, see MtxReader::read_entries
+    pub fn read_mtx(fname: &Path, sort_data: bool) -> Result<Self, Error> {
         // println!("Loading {:?}", fname);
 
-        let err_msg = "Error parsing file.";
+        let (shape, mut data) = MtxReader::open(fname)?.read_entries()?;
 
-        let f = File::open(fname).map_err(|_| (err_msg))?;
-        let f = BufReader::new(f);
+        if sort_data {
+            data.sort_by_key(|a| (a.0, a.1));
+        }
 
-        // ** Actual reading **
+        Ok(COO { data, shape })
+    }
 
-        let mut liter = f.lines();
+    // Like read_mtx, but parsed on all threads, see mtx::read_entries_par.
+    // Gives the same matrix as read_mtx, much faster for large files.
+    pub fn read_mtx_par(fname: &Path, sort_data: bool) -> Result<Self, Error> {
+        let (shape, mut data) = mtx::read_entries_par(fname)?;
 
-        // "Header" / comments
-        // Ignore all comments / type codes (assume 'MatrixMarket matrix coordinate real general format')
-        let (mut m, mut n, mut l): (usize, usize, usize) = (0, 0, 0);
-        for line in liter.by_ref() {
-            let line = String::from(line.map_err(|_| (err_msg))?.trim());
-            if line.starts_with("%") {
-                continue;
-            } else {
-                let mut hspl = line.split(' ');
-                m = hspl.next().ok_or(err_msg)?.parse().map_err(|_| (err_msg))?;
-                n = hspl.next().ok_or(err_msg)?.parse().map_err(|_| (err_msg))?;
-                l = hspl.next().ok_or(err_msg)?.parse().map_err(|_| (err_msg))?;
-                break;
-            }
+        if sort_data {
+            data.par_sort_by_key(|a| (a.0, a.1));
         }
 
-        let shape = (m, n);
-
-        // println!("{} {} {}", m, n, l);
-
-        // let mut values = vec![vec![0.;n];m];
-        let mut data: Vec<(usize, usize, f64)> = Vec::with_capacity(l);
-
-        // //let mut i = 0;
-        for _ in 0..l {
-            let line = String::from(liter.next().expect(err_msg).unwrap().trim());
-            let mut split = line.split(' ');
-
-            let i: usize = split
-                .next()
-                .ok_or(err_msg)?
-                .parse::<usize>()
-                .map_err(|_| (err_msg))?
-                - 1; // Start counting with 0
-            let j: usize = split
-                .next()
-                .ok_or(err_msg)?
-                .parse::<usize>()
-                .map_err(|_| (err_msg))?
-                - 1;
-            let v: f64 = split
-                .next()
-                .ok_or(err_msg)?
-                .parse()
-                .map_err(|_| (err_msg))?;
-
-            data.push((i, j, v));
+        Ok(COO { data, shape })
+    }
+
+    // Like read_mtx_par (unsorted), but through a binary cache next to the file (binary::cache_path).
+    // A missing, outdated or unreadable cache (e.g. other scalar type) is (re)written,
+    // failing to write it is no error, the matrix is just parsed again next time.
+    // Rutherford-Boeing files (rb::is_rb_path) are read directly with read_rb, without cache.
+    pub fn read_mtx_cached(fname: &Path) -> Result<Self, Error> {
+        if rb::is_rb_path(fname) {
+            return Self::read_rb(fname);
+        }
+        let bin_fname = binary::cache_path(fname);
+        if binary::cache_is_fresh(fname, &bin_fname) {
+            if let Ok(coo) = Self::load_bin(&bin_fname) {
+                return Ok(coo);
+            }
         }
 
-        if sort_data {
-            data.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        let coo = Self::read_mtx_par(fname, false)?;
+        let _ = coo.save_bin(&bin_fname);
+        Ok(coo)
+    }
+
+    // Write mtx-file, see MtxWriteOptions for the possible formats
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        match opts.format {
+            MtxFormat::Coordinate => mtx::write_coordinate(fname, self.shape, &self.data, opts),
+            MtxFormat::Array => mtx::write_array(fname, &self.to_dense(), opts),
         }
+    }
+
+    // Write binary file, see the binary module
+    pub fn save_bin(&self, fname: &Path) -> Result<(), Error> {
+        let rows: Vec<usize> = self.data.iter().map(|(i, _, _)| *i).collect();
+        let cols: Vec<usize> = self.data.iter().map(|(_, j, _)| *j).collect();
+        let values: Vec<T> = self.data.iter().map(|(_, _, x)| *x).collect();
+        binary::write(
+            fname,
+            Kind::Coo,
+            self.shape,
+            &[&rows, &cols],
+            &values,
+            false,
+        )
+    }
 
+    pub fn load_bin(fname: &Path) -> Result<Self, Error> {
+        let content = binary::read(fname, Kind::Coo, |_, nnz| vec![nnz, nnz])?;
+        let [rows, cols]: [Vec<usize>; 2] = content.indices.try_into().unwrap();
+        let data = rows
+            .into_iter()
+            .zip(cols)
+            .zip(content.values)
+            .map(|((i, j), x)| (i, j, x))
+            .collect();
+        Ok(COO {
+            data,
+            shape: content.shape,
+        })
+    }
+
+    // Read a .npz file of scipy.sparse.save_npz, CSR and CSC files are converted
+    pub fn read_npz(fname: &Path) -> Result<Self, Error> {
+        npy::read_npz(fname)?.into_coo(fname)
+    }
+
+    // Write a .npz file in COO format, scipy.sparse.load_npz gives a coo_matrix
+    pub fn write_npz(&self, fname: &Path) -> Result<(), Error> {
+        let rows: Vec<usize> = self.data.iter().map(|(i, _, _)| *i).collect();
+        let cols: Vec<usize> = self.data.iter().map(|(_, j, _)| *j).collect();
+        let values: Vec<T> = self.data.iter().map(|(_, _, x)| *x).collect();
+        npy::write_npz(
+            fname,
+            "coo",
+            self.shape,
+            &[("row", &rows), ("col", &cols)],
+            &values,
+        )
+    }
+
+    // Read a Rutherford-Boeing or Harwell-Boeing file, see the rb module.
+    // The data is sorted by columns, symmetric matrices are mirrored.
+    pub fn read_rb(fname: &Path) -> Result<Self, Error> {
+        let (shape, data) = rb::read_entries(fname)?;
         Ok(COO { data, shape })
     }
 
@@ -94,11 +162,86 @@ impl COO {
         }
     }
 
-    pub fn to_dense(&self) -> Dense {
+    // Checks that all indices are inside the shape.
+    // Order and duplicates are allowed in COO.
+    pub fn validate(&self) -> Result<(), StructureError> {
+        let (m, n) = self.shape;
+        match self.data.iter().position(|&(i, j, _)| i >= m || j >= n) {
+            Some(entry) => Err(StructureError::IndexOutOfBounds {
+                entry,
+                index: (self.data[entry].0, self.data[entry].1),
+                shape: self.shape,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    // Sorts, sums duplicates and drops zeros, see CanonicalizeOptions.
+    // Without sorting, summed duplicates stay at the place of their first occurrence.
+    pub fn canonicalize(&mut self, opts: &CanonicalizeOptions) {
+        if opts.sort {
+            // Stable, so duplicates keep their order
+            self.data.sort_by_key(|&(i, j, _)| (i, j));
+        }
+
+        if opts.sum_duplicates {
+            if opts.sort {
+                // Duplicates are neighbours now
+                self.data.dedup_by(|(i, j, x), (i0, j0, x0)| {
+                    let duplicate = i == i0 && j == j0;
+                    if duplicate {
+                        *x0 += *x;
+                    }
+                    duplicate
+                });
+            } else {
+                let mut first: HashMap<(usize, usize), usize> = HashMap::new();
+                let mut data: Vec<(usize, usize, T)> = Vec::with_capacity(self.data.len());
+                for &(i, j, x) in &self.data {
+                    match first.get(&(i, j)) {
+                        Some(&k) => data[k].2 += x,
+                        None => {
+                            first.insert((i, j), data.len());
+                            data.push((i, j, x));
+                        }
+                    }
+                }
+                self.data = data;
+            }
+        }
+
+        if let Some(tolerance) = opts.drop_zeros {
+            self.data.retain(|(_, _, x)| x.abs().to_f64() > tolerance);
+        }
+    }
+
+    // Sorted by row, then by column, without duplicates
+    pub fn is_canonical(&self) -> bool {
+        self.data
+            .windows(2)
+            .all(|w| (w[0].0, w[0].1) < (w[1].0, w[1].1))
+    }
+
+    // Duplicates are summed up
+    pub fn to_dense(&self) -> Dense<T> {
         let mut mat = Dense::new_zeros((self.shape.0, self.shape.1));
         for (i, j, x) in &self.data {
-            mat.set(*i, *j, *x);
+            mat.set(*i, *j, mat.get(*i, *j) + *x);
         }
         mat
     }
 }
+
+impl<T: RealScalar> COO<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> COO<U> {
+        COO {
+            data: self
+                .data
+                .iter()
+                .map(|&(i, j, x)| (i, j, U::from_f64(x.to_f64())))
+                .collect(),
+            shape: self.shape,
+        }
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use crate::mtx::MtxWriteOptions;
+use crate::npy;
+use crate::{CanonicalizeOptions, Dense, Error, RealScalar, Scalar, COO, CSR};
+
+// CSC format, the transposed counterpart of CSR.
+// col_pos has n+1 entries, the entries of column k are at col_pos[k]..col_pos[k+1].
+// row_pos holds the row index and values the value of each entry.
+// Within a column the entries are sorted by row.
+pub struct CSC<T = f64> {
+    pub col_pos: Vec<usize>,
+    pub row_pos: Vec<usize>,
+    pub values: Vec<T>,
+    pub shape: (usize, usize),
+}
+
+impl<T: Scalar> CSC<T> {
+    // Build from arbitrary ordered (row, column, value) entries.
+    // Counting sort by column, the columns are then sorted by row.
+    fn from_entries<'a, I>(shape: (usize, usize), nnz: usize, entries: I) -> Self
+    where
+        I: Iterator<Item = (usize, usize, &'a T)> + Clone,
+    {
+        let n = shape.1;
+
+        // Number of entries per column, prefix sum gives the column starts
+        let mut col_pos = vec![0; n + 1];
+        for (_, j, _) in entries.clone() {
+            col_pos[j + 1] += 1;
+        }
+        for k in 0..n {
+            col_pos[k + 1] += col_pos[k];
+        }
+
+        // Next free position in each column
+        let mut next = col_pos.clone();
+        let mut row_pos = vec![0; nnz];
+        let mut values = vec![T::zero(); nnz];
+        let mut sorted = true;
+        for (i, j, x) in entries {
+            let pos = next[j];
+            if pos > col_pos[j] && row_pos[pos - 1] > i {
+                sorted = false;
+            }
+            row_pos[pos] = i;
+            values[pos] = *x;
+            next[j] += 1;
+        }
+
+        // Entries came in row-major order (e.g. from CSR), nothing more to do
+        if !sorted {
+            for k in 0..n {
+                let range = col_pos[k]..col_pos[k + 1];
+                let mut col: Vec<(usize, T)> = row_pos[range.clone()]
+                    .iter()
+                    .copied()
+                    .zip(values[range.clone()].iter().copied())
+                    .collect();
+                col.sort_by_key(|(i, _)| *i);
+                for (pos, (i, x)) in range.zip(col) {
+                    row_pos[pos] = i;
+                    values[pos] = x;
+                }
+            }
+        }
+
+        CSC {
+            col_pos,
+            row_pos,
+            values,
+            shape,
+        }
+    }
+
+    // Duplicates are summed up
+    pub fn from_coo(coo: &COO<T>) -> Self {
+        if !coo.is_canonical() {
+            let mut coo = COO {
+                data: coo.data.clone(),
+                shape: coo.shape,
+            };
+            coo.canonicalize(&CanonicalizeOptions::default());
+            return Self::from_coo(&coo);
+        }
+
+        Self::from_entries(
+            coo.shape,
+            coo.data.len(),
+            coo.data.iter().map(|(i, j, x)| (*i, *j, x)),
+        )
+    }
+
+    pub fn from_csr(csr: &CSR<T>) -> Self {
+        let entries = (0..csr.shape.0).flat_map(|i| {
+            (csr.row_pos[i]..csr.row_pos[i + 1]).map(move |k| (i, csr.col_pos[k], &csr.values[k]))
+        });
+        Self::from_entries(csr.shape, csr.values.len(), entries)
+    }
+
+    pub fn to_csr(&self) -> CSR<T> {
+        // Same counting sort as from_csr, with rows and columns swapped.
+        // Columns are visited in order, so the columns within a row end up sorted.
+        let m = self.shape.0;
+
+        let mut row_pos = vec![0; m + 1];
+        for i in self.row_pos.iter() {
+            row_pos[i + 1] += 1;
+        }
+        for k in 0..m {
+            row_pos[k + 1] += row_pos[k];
+        }
+
+        let mut next = row_pos.clone();
+        let mut col_pos = vec![0; self.values.len()];
+        let mut values = vec![T::zero(); self.values.len()];
+        for j in 0..self.shape.1 {
+            for k in self.col_pos[j]..self.col_pos[j + 1] {
+                let i = self.row_pos[k];
+                col_pos[next[i]] = j;
+                values[next[i]] = self.values[k];
+                next[i] += 1;
+            }
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: self.shape,
+        }
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Sparse ({},{})-matrix in CSC format with {} entries",
+            self.shape.0,
+            self.shape.1,
+            self.values.len()
+        );
+        println!("Col Pos {:?}", self.col_pos);
+        println!("Row Pos {:?}", self.row_pos);
+        println!("Values {:?}", self.values);
+    }
+
+    // Write mtx-file, see MtxWriteOptions for the possible formats
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        self.to_coo().write_mtx(fname, opts)
+    }
+
+    // Read a .npz file of scipy.sparse.save_npz, any format is converted via COO
+    pub fn read_npz(fname: &Path) -> Result<Self, Error> {
+        Ok(CSC::from_coo(&npy::read_npz(fname)?.into_coo(fname)?))
+    }
+
+    // Write a .npz file, scipy.sparse.load_npz gives a csc_matrix
+    pub fn write_npz(&self, fname: &Path) -> Result<(), Error> {
+        npy::write_npz(
+            fname,
+            "csc",
+            self.shape,
+            &[("indices", &self.row_pos), ("indptr", &self.col_pos)],
+            &self.values,
+        )
+    }
+
+    // Read a Rutherford-Boeing or Harwell-Boeing file, see the rb module
+    pub fn read_rb(fname: &Path) -> Result<Self, Error> {
+        Ok(CSC::from_coo(&COO::read_rb(fname)?))
+    }
+
+    pub fn get_col_nnz(&self, k: usize) -> usize {
+        self.col_pos[k + 1] - self.col_pos[k]
+    }
+
+    // Row indices and values of column k
+    pub fn get_col(&self, k: usize) -> (&[usize], &[T]) {
+        let range = self.col_pos[k]..self.col_pos[k + 1];
+        (&self.row_pos[range.clone()], &self.values[range])
+    }
+
+    pub fn to_dense(&self) -> Dense<T> {
+        let mut mat = Dense::new_zeros(self.shape);
+
+        for j in 0..self.shape.1 {
+            let (rows, values) = self.get_col(j);
+            for (i, x) in rows.iter().zip(values) {
+                mat.set(*i, j, *x);
+            }
+        }
+
+        mat
+    }
+
+    // Entries in column-major order
+    pub fn to_coo(&self) -> COO<T> {
+        let mut data = Vec::with_capacity(self.values.len());
+
+        for j in 0..self.shape.1 {
+            let (rows, values) = self.get_col(j);
+            for (i, x) in rows.iter().zip(values) {
+                data.push((*i, j, *x));
+            }
+        }
+
+        COO {
+            data,
+            shape: self.shape,
+        }
+    }
+}
+
+impl<T: RealScalar> CSC<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> CSC<U> {
+        CSC {
+            col_pos: self.col_pos.clone(),
+            row_pos: self.row_pos.clone(),
+            values: self.values.iter().map(|x| U::from_f64(x.to_f64())).collect(),
+            shape: self.shape,
+        }
+    }
+}
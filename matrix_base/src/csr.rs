@@ -1,21 +1,39 @@
-use crate::{Dense, COO};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::binary::{self, Kind};
+use crate::mtx::MtxWriteOptions;
+use crate::npy::{self, SparseNpz};
+use crate::scalar::max_or_zero;
+use crate::{CanonicalizeOptions, Dense, Error, RealScalar, Scalar, StructureError, COO};
 
 // CSR format from "Two Fast Algorithms for Sparse Matrices: Multiplication and Permuted Transposition", Rice, Gustavson
 // https://dl.acm.org/doi/pdf/10.1145/355791.355796
 // Notation relation with paper:
 // row_pos = IA, col_pos = JA, values = A
-pub struct CSR {
+pub struct CSR<T = f64> {
     pub row_pos: Vec<usize>,
     pub col_pos: Vec<usize>,
-    pub values: Vec<f64>,
+    pub values: Vec<T>,
     pub shape: (usize, usize),
 }
 
-impl CSR {
-    pub fn from_coo(coo: &COO) -> Self {
+impl<T: Scalar> CSR<T> {
+    // Unsorted input is sorted and duplicates are summed up first
+    pub fn from_coo(coo: &COO<T>) -> Self {
+        if !coo.is_canonical() {
+            let mut coo = COO {
+                data: coo.data.clone(),
+                shape: coo.shape,
+            };
+            coo.canonicalize(&CanonicalizeOptions::default());
+            return Self::from_coo(&coo);
+        }
+
         let mut row_pos: Vec<usize> = vec![];
         let mut col_pos: Vec<usize> = vec![];
-        let mut values: Vec<f64> = vec![];
+        let mut values: Vec<T> = vec![];
 
         let shape = coo.shape;
 
@@ -75,11 +93,108 @@ impl CSR {
         println!("Values {:?}", self.values);
     }
 
+    // Write mtx-file, see MtxWriteOptions for the possible formats
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        self.to_coo().write_mtx(fname, opts)
+    }
+
+    // Write binary file, see the binary module
+    pub fn save_bin(&self, fname: &Path) -> Result<(), Error> {
+        binary::write(
+            fname,
+            Kind::Csr,
+            self.shape,
+            &[&self.row_pos, &self.col_pos],
+            &self.values,
+            false,
+        )
+    }
+
+    // Like save_bin, but always with 8 byte indices, so the file can be opened as binary::MappedCsr
+    pub fn save_bin_mappable(&self, fname: &Path) -> Result<(), Error> {
+        binary::write(
+            fname,
+            Kind::Csr,
+            self.shape,
+            &[&self.row_pos, &self.col_pos],
+            &self.values,
+            true,
+        )
+    }
+
+    pub fn load_bin(fname: &Path) -> Result<Self, Error> {
+        let content = binary::read(fname, Kind::Csr, |shape, nnz| {
+            vec![shape.0.saturating_add(1), nnz]
+        })?;
+        let [row_pos, col_pos]: [Vec<usize>; 2] = content.indices.try_into().unwrap();
+        Ok(CSR {
+            row_pos,
+            col_pos,
+            values: content.values,
+            shape: content.shape,
+        })
+    }
+
+    // Read a .npz file of scipy.sparse.save_npz. CSR files with sorted and unique
+    // columns are used as they are, everything else is converted via COO.
+    pub fn read_npz(fname: &Path) -> Result<Self, Error> {
+        match npy::read_npz(fname)? {
+            SparseNpz::Compressed {
+                csc: false,
+                shape,
+                indptr,
+                indices,
+                data,
+            } => {
+                let csr = CSR {
+                    row_pos: indptr,
+                    col_pos: indices,
+                    values: data,
+                    shape,
+                };
+                if csr.validate().is_ok() {
+                    return Ok(csr);
+                }
+                // scipy allows unsorted and duplicate columns
+                let sparse = SparseNpz::Compressed {
+                    csc: false,
+                    shape,
+                    indptr: csr.row_pos,
+                    indices: csr.col_pos,
+                    data: csr.values,
+                };
+                Ok(CSR::from_coo(&sparse.into_coo(fname)?))
+            }
+            sparse => Ok(CSR::from_coo(&sparse.into_coo(fname)?)),
+        }
+    }
+
+    // Write a .npz file, scipy.sparse.load_npz gives a csr_matrix
+    pub fn write_npz(&self, fname: &Path) -> Result<(), Error> {
+        npy::write_npz(
+            fname,
+            "csr",
+            self.shape,
+            &[("indices", &self.col_pos), ("indptr", &self.row_pos)],
+            &self.values,
+        )
+    }
+
+    // Read a Rutherford-Boeing or Harwell-Boeing file, see the rb module
+    pub fn read_rb(fname: &Path) -> Result<Self, Error> {
+        Ok(CSR::from_coo(&COO::read_rb(fname)?))
+    }
+
+    // See CsrView::validate
+    pub fn validate(&self) -> Result<(), StructureError> {
+        self.view().validate()
+    }
+
     pub fn get_row_nnz(&self, k: usize) -> usize {
         self.row_pos[k + 1] - self.row_pos[k]
     }
 
-    pub fn to_dense(&self) -> Dense {
+    pub fn to_dense(&self) -> Dense<T> {
         let m = self.shape.0;
         let n = self.shape.1;
         // let mut mat = vec![vec![0.;n];m];
@@ -95,8 +210,8 @@ impl CSR {
         mat
     }
 
-    pub fn to_coo(&self) -> COO {
-        let mut data: Vec<(usize, usize, f64)> = Vec::with_capacity(self.values.len()); // Kapazität ist NNZ
+    pub fn to_coo(&self) -> COO<T> {
+        let mut data: Vec<(usize, usize, T)> = Vec::with_capacity(self.values.len()); // Kapazität ist NNZ
 
         // Gehe jede Zeile der CSR-Matrix durch
         for i in 0..self.shape.0 {
@@ -118,4 +233,417 @@ impl CSR {
             shape: self.shape,
         }
     }
+
+    // Transposition from Gustavson's paper (TRANSP): row j of the result
+    // collects column j of A. The rows of A are visited in order, so the
+    // columns of the result are sorted without an extra sorting step.
+    pub fn transpose(&self) -> CSR<T> {
+        self.transpose_with(|k| k, |j| j)
+    }
+
+    // (P A Q)^T, where row k of P A is row row_perm[k] of A
+    // and column l of A Q is column col_perm[l] of A.
+    // Transposing twice with the permutations swapped gives P A Q with sorted columns.
+    pub fn permuted_transpose(&self, row_perm: &[usize], col_perm: &[usize]) -> CSR<T> {
+        assert!(
+            row_perm.len() == self.shape.0 && is_permutation(row_perm),
+            "Invalid row permutation"
+        );
+        assert!(
+            col_perm.len() == self.shape.1 && is_permutation(col_perm),
+            "Invalid column permutation"
+        );
+        let new_col = inverse_permutation(col_perm);
+        self.transpose_with(|k| row_perm[k], |j| new_col[j])
+    }
+
+    // old_row(k): row of A that becomes column k of the result
+    // new_col(j): row of the result for column j of A
+    fn transpose_with<F, G>(&self, old_row: F, new_col: G) -> CSR<T>
+    where
+        F: Fn(usize) -> usize,
+        G: Fn(usize) -> usize,
+    {
+        let (m, n) = self.shape;
+        let nnz = self.values.len();
+
+        // Entries per column of A, prefix sum gives the row starts of the result
+        let mut row_pos = vec![0; n + 1];
+        for j in &self.col_pos {
+            row_pos[new_col(*j) + 1] += 1;
+        }
+        for j in 0..n {
+            row_pos[j + 1] += row_pos[j];
+        }
+
+        // Next free position in every row of the result
+        let mut next = row_pos.clone();
+        let mut col_pos = vec![0; nnz];
+        let mut values = vec![T::zero(); nnz];
+        for k in 0..m {
+            let i = old_row(k);
+            for pos in self.row_pos[i]..self.row_pos[i + 1] {
+                let j = new_col(self.col_pos[pos]);
+                col_pos[next[j]] = k;
+                values[next[j]] = self.values[pos];
+                next[j] += 1;
+            }
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: (n, m),
+        }
+    }
+}
+
+impl<T: Scalar> CSR<T> {
+    // A + B, see axpby
+    pub fn add(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge(other, true, |x, y| x + y)
+    }
+
+    // A - B, see axpby
+    pub fn sub(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge(other, true, |x, y| x - y)
+    }
+
+    // alpha*A, the pattern stays the same even for alpha = 0
+    pub fn scale(&self, alpha: T) -> CSR<T> {
+        CSR {
+            row_pos: self.row_pos.clone(),
+            col_pos: self.col_pos.clone(),
+            values: self.values.iter().map(|x| alpha * *x).collect(),
+            shape: self.shape,
+        }
+    }
+
+    // alpha*A + beta*B on the union of both patterns.
+    // Both matrices need sorted columns without duplicates (see validate),
+    // the result has them too. Entries that cancel out exactly are dropped.
+    pub fn axpby(&self, alpha: T, other: &CSR<T>, beta: T) -> CSR<T> {
+        self.merge(other, true, |x, y| alpha * x + beta * y)
+    }
+
+    // Entrywise product, only on the intersection of both patterns (without exact zeros)
+    pub fn hadamard(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge(other, false, |x, y| x * y)
+    }
+
+    // Parallel versions of add, sub, scale, axpby and hadamard with the same results,
+    // e.g. for the residual C - A*B of a sparse product
+
+    pub fn add_par(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge_par(other, true, |x, y| x + y)
+    }
+
+    pub fn sub_par(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge_par(other, true, |x, y| x - y)
+    }
+
+    pub fn scale_par(&self, alpha: T) -> CSR<T> {
+        CSR {
+            row_pos: self.row_pos.clone(),
+            col_pos: self.col_pos.clone(),
+            values: self.values.par_iter().map(|x| alpha * *x).collect(),
+            shape: self.shape,
+        }
+    }
+
+    pub fn axpby_par(&self, alpha: T, other: &CSR<T>, beta: T) -> CSR<T> {
+        self.merge_par(other, true, |x, y| alpha * x + beta * y)
+    }
+
+    pub fn hadamard_par(&self, other: &CSR<T>) -> CSR<T> {
+        self.merge_par(other, false, |x, y| x * y)
+    }
+
+    // Norms like for Dense, the stored entries are assumed to be without duplicates
+
+    // Frobenius norm, sqrt of the sum of all |a_ij|^2
+    pub fn norm_fro(&self) -> T::Real {
+        self.values
+            .iter()
+            .map(|x| x.abs() * x.abs())
+            .sum::<T::Real>()
+            .sqrt()
+    }
+
+    // 1-norm, largest absolute column sum
+    pub fn norm_one(&self) -> T::Real {
+        let mut col_sums = vec![<T::Real as Scalar>::zero(); self.shape.1];
+        for (j, x) in self.col_pos.iter().zip(&self.values) {
+            col_sums[*j] += x.abs();
+        }
+        max_or_zero(col_sums)
+    }
+
+    // Infinity norm, largest absolute row sum
+    pub fn norm_inf(&self) -> T::Real {
+        max_or_zero(self.row_pos.windows(2).map(|row| {
+            self.values[row[0]..row[1]]
+                .iter()
+                .map(|x| x.abs())
+                .sum()
+        }))
+    }
+
+    // Max norm, largest absolute entry
+    pub fn norm_max(&self) -> T::Real {
+        max_or_zero(self.values.iter().map(|x| x.abs()))
+    }
+
+    fn merge<F: Fn(T, T) -> T>(&self, other: &CSR<T>, union: bool, f: F) -> CSR<T> {
+        assert_eq!(self.shape, other.shape, "Matrix shapes don't match");
+
+        let capacity = if union {
+            self.values.len() + other.values.len()
+        } else {
+            self.values.len().min(other.values.len())
+        };
+        let mut row_pos = vec![0];
+        let mut col_pos = Vec::with_capacity(capacity);
+        let mut values = Vec::with_capacity(capacity);
+        for i in 0..self.shape.0 {
+            merge_row(self, other, i, union, &f, &mut col_pos, &mut values);
+            row_pos.push(values.len());
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: self.shape,
+        }
+    }
+
+    // Every task merges a chunk of rows into its own buffers, which are concatenated afterwards
+    fn merge_par<F: Fn(T, T) -> T + Sync>(&self, other: &CSR<T>, union: bool, f: F) -> CSR<T> {
+        assert_eq!(self.shape, other.shape, "Matrix shapes don't match");
+        let m = self.shape.0;
+
+        let chunks: Vec<(Vec<usize>, Vec<usize>, Vec<T>)> = (0..m.div_ceil(MERGE_CHUNK_ROWS))
+            .into_par_iter()
+            .map(|c| {
+                let (mut row_len, mut col_pos, mut values) = (vec![], vec![], vec![]);
+                for i in c * MERGE_CHUNK_ROWS..((c + 1) * MERGE_CHUNK_ROWS).min(m) {
+                    let before = values.len();
+                    merge_row(self, other, i, union, &f, &mut col_pos, &mut values);
+                    row_len.push(values.len() - before);
+                }
+                (row_len, col_pos, values)
+            })
+            .collect();
+
+        let nnz = chunks.iter().map(|(_, _, values)| values.len()).sum();
+        let mut row_pos = Vec::with_capacity(m + 1);
+        row_pos.push(0);
+        let mut col_pos = Vec::with_capacity(nnz);
+        let mut values = Vec::with_capacity(nnz);
+        for (row_len, cols, vals) in chunks {
+            for len in row_len {
+                row_pos.push(row_pos.last().unwrap() + len);
+            }
+            col_pos.extend(cols);
+            values.extend(vals);
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: self.shape,
+        }
+    }
+}
+
+// Rows per task in the parallel arithmetic
+const MERGE_CHUNK_ROWS: usize = 1024;
+
+// Borrowed CSR matrix, e.g. of a memory-mapped file (binary::MappedCsr).
+// Same fields as CSR, but slices, so nothing has to be copied.
+#[derive(Debug, Clone, Copy)]
+pub struct CsrView<'a, T = f64> {
+    pub row_pos: &'a [usize],
+    pub col_pos: &'a [usize],
+    pub values: &'a [T],
+    pub shape: (usize, usize),
+}
+
+// Everything that can be used as a CsrView, algorithms taking this
+// work on CSR, views and mapped files alike
+pub trait AsCsrView<T> {
+    fn view(&self) -> CsrView<'_, T>;
+}
+
+impl<T> AsCsrView<T> for CSR<T> {
+    fn view(&self) -> CsrView<'_, T> {
+        CsrView {
+            row_pos: &self.row_pos,
+            col_pos: &self.col_pos,
+            values: &self.values,
+            shape: self.shape,
+        }
+    }
+}
+
+impl<T: Copy> AsCsrView<T> for CsrView<'_, T> {
+    fn view(&self) -> CsrView<'_, T> {
+        *self
+    }
+}
+
+impl<T: Scalar> CsrView<'_, T> {
+    // Checks the CSR invariants: row_pos has m+1 non-decreasing entries from 0 to nnz,
+    // col_pos and values have nnz entries and the columns of each row are
+    // in bounds, sorted and unique.
+    // All fields are pub, so this is the way to find out if a matrix was built correctly.
+    pub fn validate(&self) -> Result<(), StructureError> {
+        let (m, n) = self.shape;
+
+        if self.row_pos.len() != m + 1 {
+            return Err(StructureError::RowPosLength {
+                expected: m + 1,
+                found: self.row_pos.len(),
+            });
+        }
+        if self.row_pos[0] != 0 {
+            return Err(StructureError::RowPosStart {
+                found: self.row_pos[0],
+            });
+        }
+        if self.col_pos.len() != self.values.len() {
+            return Err(StructureError::ValueCount {
+                col_pos: self.col_pos.len(),
+                values: self.values.len(),
+            });
+        }
+        if let Some(row) = (0..m).find(|&i| self.row_pos[i + 1] < self.row_pos[i]) {
+            return Err(StructureError::RowPosDecreasing { row });
+        }
+        if self.row_pos[m] != self.col_pos.len() {
+            return Err(StructureError::RowPosEnd {
+                expected: self.col_pos.len(),
+                found: self.row_pos[m],
+            });
+        }
+
+        for i in 0..m {
+            let cols = &self.col_pos[self.row_pos[i]..self.row_pos[i + 1]];
+            if let Some(&column) = cols.iter().find(|&&j| j >= n) {
+                return Err(StructureError::ColumnOutOfBounds {
+                    row: i,
+                    column,
+                    columns: n,
+                });
+            }
+            for w in cols.windows(2) {
+                if w[0] == w[1] {
+                    return Err(StructureError::DuplicateColumn {
+                        row: i,
+                        column: w[0],
+                    });
+                }
+                if w[0] > w[1] {
+                    return Err(StructureError::ColumnsNotSorted { row: i });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Copy into an owned matrix
+    pub fn to_csr(&self) -> CSR<T> {
+        CSR {
+            row_pos: self.row_pos.to_vec(),
+            col_pos: self.col_pos.to_vec(),
+            values: self.values.to_vec(),
+            shape: self.shape,
+        }
+    }
+}
+
+impl<T: RealScalar> CSR<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> CSR<U> {
+        CSR {
+            row_pos: self.row_pos.clone(),
+            col_pos: self.col_pos.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|x| U::from_f64(x.to_f64()))
+                .collect(),
+            shape: self.shape,
+        }
+    }
+}
+
+// Every index 0..perm.len() appears exactly once
+pub fn is_permutation(perm: &[usize]) -> bool {
+    let mut seen = vec![false; perm.len()];
+    perm.iter()
+        .all(|&i| i < perm.len() && !std::mem::replace(&mut seen[i], true))
+}
+
+// perm[k] is the old index at the new position k, the inverse maps old to new positions
+pub fn inverse_permutation(perm: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; perm.len()];
+    for (k, i) in perm.iter().enumerate() {
+        inverse[*i] = k;
+    }
+    inverse
+}
+
+// Merges row i of A and B (sorted columns) and appends f(a_ij, b_ij) for every column,
+// a missing entry is zero. With union all columns of both rows are used,
+// otherwise only the columns that appear in both. Results that are exactly zero are skipped.
+fn merge_row<T: Scalar, F: Fn(T, T) -> T>(
+    a: &CSR<T>,
+    b: &CSR<T>,
+    i: usize,
+    union: bool,
+    f: &F,
+    col_pos: &mut Vec<usize>,
+    values: &mut Vec<T>,
+) {
+    let (mut p, end_a) = (a.row_pos[i], a.row_pos[i + 1]);
+    let (mut q, end_b) = (b.row_pos[i], b.row_pos[i + 1]);
+    let mut push = |j: usize, x: T| {
+        if x != T::zero() {
+            col_pos.push(j);
+            values.push(x);
+        }
+    };
+
+    while p < end_a && q < end_b {
+        let (j_a, j_b) = (a.col_pos[p], b.col_pos[q]);
+        if j_a == j_b {
+            push(j_a, f(a.values[p], b.values[q]));
+            p += 1;
+            q += 1;
+        } else if j_a < j_b {
+            if union {
+                push(j_a, f(a.values[p], T::zero()));
+            }
+            p += 1;
+        } else {
+            if union {
+                push(j_b, f(T::zero(), b.values[q]));
+            }
+            q += 1;
+        }
+    }
+
+    if union {
+        for p in p..end_a {
+            push(a.col_pos[p], f(a.values[p], T::zero()));
+        }
+        for q in q..end_b {
+            push(b.col_pos[q], f(T::zero(), b.values[q]));
+        }
+    }
 }
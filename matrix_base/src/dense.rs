@@ -1,22 +1,151 @@
-pub struct Dense {
-    pub data: Vec<f64>,
+use std::borrow::Cow;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Range, Sub, SubAssign};
+use std::path::Path;
+
+use rayon::prelude::*;
+
+use crate::binary::{self, Kind};
+use crate::mtx::{self, MtxFormat, MtxWriteOptions};
+use crate::npy;
+use crate::scalar::max_or_zero;
+use crate::{Error, RealScalar, Scalar, COO};
+
+// Order of the entries in Dense::data. Everything is row-major unless
+// asked for otherwise, e.g. with as_column_major for BLAS and cuBLAS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    #[default]
+    RowMajor,
+    ColumnMajor,
+}
+
+#[derive(Debug, Clone)]
+pub struct Dense<T = f64> {
+    pub data: Vec<T>,
     pub shape: (usize, usize),
+    pub layout: Layout,
 }
 
-impl Dense {
+impl<T: Scalar> Dense<T> {
     pub fn new_zeros(shape: (usize, usize)) -> Self {
         Dense {
-            data: vec![0.; shape.0 * shape.1],
+            data: vec![T::zero(); shape.0 * shape.1],
+            shape,
+            layout: Layout::RowMajor,
+        }
+    }
+
+    // Read mtx-file into a dense matrix, works for coordinate
+    // as well as array files (see COO::read_mtx)
+    pub fn read_mtx(fname: &Path) -> Result<Self, Error> {
+        COO::read_mtx(fname, false).map(|coo| coo.to_dense())
+    }
+
+    // Write mtx-file, usually with MtxFormat::Array.
+    // In coordinate format only the non-zero entries are written.
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        match opts.format {
+            MtxFormat::Array => mtx::write_array(fname, self, opts),
+            MtxFormat::Coordinate => {
+                let mut data = vec![];
+                for i in 0..self.shape.0 {
+                    for j in 0..self.shape.1 {
+                        let x = self.get(i, j);
+                        if x != T::zero() {
+                            data.push((i, j, x));
+                        }
+                    }
+                }
+                mtx::write_coordinate(fname, self.shape, &data, opts)
+            }
+        }
+    }
+
+    // Write binary file, see the binary module
+    // The file is always row-major.
+    pub fn save_bin(&self, fname: &Path) -> Result<(), Error> {
+        binary::write(
+            fname,
+            Kind::Dense,
+            self.shape,
+            &[],
+            &self.row_major_data(),
+            false,
+        )
+    }
+
+    pub fn load_bin(fname: &Path) -> Result<Self, Error> {
+        let content = binary::read(fname, Kind::Dense, |_, _| vec![])?;
+        if content.values.len() as u128 != content.shape.0 as u128 * content.shape.1 as u128 {
+            return Err(Error::Binary {
+                path: fname.to_path_buf(),
+                msg: "number of values doesn't match the shape".to_string(),
+            });
+        }
+        Ok(Dense {
+            data: content.values,
+            shape: content.shape,
+            layout: Layout::RowMajor,
+        })
+    }
+
+    // Read a 2-dimensional NumPy array, e.g. from np.save
+    pub fn read_npy(fname: &Path) -> Result<Self, Error> {
+        let (shape, data) = npy::read_dense(fname)?;
+        Ok(Dense {
+            data,
             shape,
+            layout: Layout::RowMajor,
+        })
+    }
+
+    // Write a .npy file, np.load gives a (m, n)-array of float32/float64/complex
+    pub fn write_npy(&self, fname: &Path) -> Result<(), Error> {
+        npy::write_dense(fname, self.shape, &self.row_major_data())
+    }
+
+    // Distance in data between neighbouring rows and columns
+    pub fn strides(&self) -> (usize, usize) {
+        match self.layout {
+            Layout::RowMajor => (self.shape.1, 1),
+            Layout::ColumnMajor => (1, self.shape.0),
         }
     }
 
-    pub fn get(&self, i: usize, j: usize) -> f64 {
-        self.data[self.shape.1 * i + j]
+    pub fn get(&self, i: usize, j: usize) -> T {
+        let (s0, s1) = self.strides();
+        self.data[s0 * i + s1 * j]
     }
 
-    pub fn set(&mut self, i: usize, j: usize, x: f64) {
-        self.data[self.shape.1 * i + j] = x;
+    pub fn set(&mut self, i: usize, j: usize, x: T) {
+        let (s0, s1) = self.strides();
+        self.data[s0 * i + s1 * j] = x;
+    }
+
+    // Borrow the whole matrix, submatrices and transposes are taken from the view
+    pub fn view(&self) -> DenseView<'_, T> {
+        DenseView {
+            data: &self.data,
+            shape: self.shape,
+            strides: self.strides(),
+        }
+    }
+
+    pub fn view_mut(&mut self) -> DenseViewMut<'_, T> {
+        let strides = self.strides();
+        DenseViewMut {
+            data: &mut self.data,
+            shape: self.shape,
+            strides,
+        }
+    }
+
+    // The entries row by row, only copied if the matrix is column-major
+    pub fn row_major_data(&self) -> Cow<'_, [T]> {
+        match self.layout {
+            Layout::RowMajor => Cow::Borrowed(&self.data),
+            Layout::ColumnMajor => Cow::Owned(self.as_row_major().data),
+        }
     }
 
     pub fn print(&self) {
@@ -32,29 +161,490 @@ impl Dense {
         }
     }
 
+    // Copy with column-major data, e.g. for BLAS and cuBLAS
     pub fn as_column_major(&self) -> Self {
-        let mut transposed_data = vec![0.0; self.shape.0 * self.shape.1];
-        for i in 0..self.shape.0 {
-            for j in 0..self.shape.1 {
-                transposed_data[j * self.shape.0 + i] = self.data[i * self.shape.1 + j];
+        let (m, n) = self.shape;
+        let mut data = vec![T::zero(); m * n];
+        for i in 0..m {
+            for j in 0..n {
+                data[j * m + i] = self.get(i, j);
             }
         }
         Dense {
-            data: transposed_data,
-            shape: (self.shape.0, self.shape.1),
+            data,
+            shape: self.shape,
+            layout: Layout::ColumnMajor,
         }
     }
 
+    // Copy with row-major data
     pub fn as_row_major(&self) -> Self {
-        let mut transposed_data = vec![0.0; self.shape.0 * self.shape.1];
-        for i in 0..self.shape.0 {
-            for j in 0..self.shape.1 {
-                transposed_data[i * self.shape.1 + j] = self.data[j * self.shape.0 + i];
+        let (m, n) = self.shape;
+        let mut data = vec![T::zero(); m * n];
+        for i in 0..m {
+            for j in 0..n {
+                data[i * n + j] = self.get(i, j);
             }
         }
         Dense {
-            data: transposed_data,
-            shape: (self.shape.0, self.shape.1),
+            data,
+            shape: self.shape,
+            layout: Layout::RowMajor,
+        }
+    }
+}
+
+impl<T: RealScalar> Dense<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> Dense<U> {
+        Dense {
+            data: self.data.iter().map(|x| U::from_f64(x.to_f64())).collect(),
+            shape: self.shape,
+            layout: self.layout,
+        }
+    }
+}
+
+// Element-wise operations and reductions. Two operands may have different layouts,
+// the result has the layout of the left one.
+impl<T: Scalar> Dense<T> {
+    pub fn map<U: Scalar>(&self, f: impl Fn(T) -> U) -> Dense<U> {
+        Dense {
+            data: self.data.iter().map(|&x| f(x)).collect(),
+            shape: self.shape,
+            layout: self.layout,
+        }
+    }
+
+    // f(a_ij, b_ij) for all entries
+    pub fn zip_map<U: Scalar>(&self, other: &Dense<T>, f: impl Fn(T, T) -> U) -> Dense<U> {
+        assert_eq!(self.shape, other.shape, "Matrix shapes don't match");
+        let other = other.with_layout(self.layout);
+        Dense {
+            data: self
+                .data
+                .iter()
+                .zip(other.data.iter())
+                .map(|(&x, &y)| f(x, y))
+                .collect(),
+            shape: self.shape,
+            layout: self.layout,
+        }
+    }
+
+    // a_ij = f(a_ij, b_ij) in place
+    pub fn zip_apply(&mut self, other: &Dense<T>, f: impl Fn(&mut T, T)) {
+        assert_eq!(self.shape, other.shape, "Matrix shapes don't match");
+        let other = other.with_layout(self.layout);
+        for (x, &y) in self.data.iter_mut().zip(other.data.iter()) {
+            f(x, y);
+        }
+    }
+
+    // Entries in no particular order, e.g. for reductions
+    pub fn fold<A>(&self, init: A, f: impl FnMut(A, T) -> A) -> A {
+        self.data.iter().copied().fold(init, f)
+    }
+
+    pub fn sum(&self) -> T {
+        self.data.iter().copied().sum()
+    }
+
+    // Largest absolute value, 0 for empty matrices.
+    // (&a - &b).max_abs() is the largest deviation of two results.
+    pub fn max_abs(&self) -> T::Real {
+        max_or_zero(self.data.iter().map(|x| x.abs()))
+    }
+
+    // Frobenius norm, sqrt of the sum of all |a_ij|^2
+    pub fn norm_fro(&self) -> T::Real {
+        self.fold(<T::Real as Scalar>::zero(), |sum, x| sum + x.abs() * x.abs())
+            .sqrt()
+    }
+
+    // 1-norm, largest absolute column sum
+    pub fn norm_one(&self) -> T::Real {
+        let (m, n) = self.shape;
+        max_or_zero((0..n).map(|j| (0..m).map(|i| self.get(i, j).abs()).sum()))
+    }
+
+    // Infinity norm, largest absolute row sum
+    pub fn norm_inf(&self) -> T::Real {
+        let (m, n) = self.shape;
+        max_or_zero((0..m).map(|i| (0..n).map(|j| self.get(i, j).abs()).sum()))
+    }
+
+    // Max norm, same as max_abs
+    pub fn norm_max(&self) -> T::Real {
+        self.max_abs()
+    }
+
+    // Same matrix with the given layout, only copied if the layout differs
+    fn with_layout(&self, layout: Layout) -> Cow<'_, Dense<T>> {
+        if self.layout == layout {
+            return Cow::Borrowed(self);
+        }
+        match layout {
+            Layout::RowMajor => Cow::Owned(self.as_row_major()),
+            Layout::ColumnMajor => Cow::Owned(self.as_column_major()),
+        }
+    }
+
+    // C = A*B, rows of C in parallel. Same product as fakscpu::dense::DenseProd,
+    // but matrix_base can't depend on the cpu crate, so Mul has its own loop.
+    fn product(&self, other: &Dense<T>) -> Dense<T> {
+        assert_eq!(
+            self.shape.1, other.shape.0,
+            "Matrix dimensions do not match for multiplication"
+        );
+        let (m, p, n) = (self.shape.0, self.shape.1, other.shape.1);
+        let mut res = Dense::new_zeros((m, n));
+        if n == 0 {
+            return res;
+        }
+        let a = self.row_major_data();
+        let b = other.row_major_data();
+        res.data
+            .par_chunks_mut(n)
+            .enumerate()
+            .for_each(|(i, c_row)| {
+                for k in 0..p {
+                    let a_ik = a[i * p + k];
+                    for (c_ij, &b_kj) in c_row.iter_mut().zip(&b[k * n..(k + 1) * n]) {
+                        *c_ij += a_ik * b_kj;
+                    }
+                }
+            });
+        res
+    }
+}
+
+impl<T: Scalar> Index<(usize, usize)> for Dense<T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        let (s0, s1) = self.strides();
+        &self.data[s0 * i + s1 * j]
+    }
+}
+
+impl<T: Scalar> IndexMut<(usize, usize)> for Dense<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        let (s0, s1) = self.strides();
+        &mut self.data[s0 * i + s1 * j]
+    }
+}
+
+// A + B and A - B for references and owned matrices, the owned left operand is reused
+macro_rules! impl_elementwise_op {
+    ($op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident) => {
+        impl<T: Scalar> $op_assign<&Dense<T>> for Dense<T> {
+            fn $fn_assign(&mut self, other: &Dense<T>) {
+                self.zip_apply(other, |x, y| x.$fn_assign(y));
+            }
+        }
+
+        impl<T: Scalar> $op_assign<Dense<T>> for Dense<T> {
+            fn $fn_assign(&mut self, other: Dense<T>) {
+                self.$fn_assign(&other);
+            }
+        }
+
+        impl<T: Scalar> $op<&Dense<T>> for &Dense<T> {
+            type Output = Dense<T>;
+
+            fn $fn(self, other: &Dense<T>) -> Dense<T> {
+                self.zip_map(other, |x, y| x.$fn(y))
+            }
+        }
+
+        impl<T: Scalar> $op<&Dense<T>> for Dense<T> {
+            type Output = Dense<T>;
+
+            fn $fn(mut self, other: &Dense<T>) -> Dense<T> {
+                self.$fn_assign(other);
+                self
+            }
+        }
+
+        impl<T: Scalar> $op<Dense<T>> for Dense<T> {
+            type Output = Dense<T>;
+
+            fn $fn(mut self, other: Dense<T>) -> Dense<T> {
+                self.$fn_assign(&other);
+                self
+            }
+        }
+    };
+}
+
+impl_elementwise_op!(Add, add, AddAssign, add_assign);
+impl_elementwise_op!(Sub, sub, SubAssign, sub_assign);
+
+impl<T: Scalar> Neg for &Dense<T> {
+    type Output = Dense<T>;
+
+    fn neg(self) -> Dense<T> {
+        self.map(|x| -x)
+    }
+}
+
+impl<T: Scalar> Neg for Dense<T> {
+    type Output = Dense<T>;
+
+    fn neg(mut self) -> Dense<T> {
+        self.data.iter_mut().for_each(|x| *x = -*x);
+        self
+    }
+}
+
+// Matrix product
+impl<T: Scalar> Mul<&Dense<T>> for &Dense<T> {
+    type Output = Dense<T>;
+
+    fn mul(self, other: &Dense<T>) -> Dense<T> {
+        self.product(other)
+    }
+}
+
+impl<T: Scalar> Mul<Dense<T>> for Dense<T> {
+    type Output = Dense<T>;
+
+    fn mul(self, other: Dense<T>) -> Dense<T> {
+        self.product(&other)
+    }
+}
+
+// Multiplication with a scalar, alpha * A for f32 and f64 below
+impl<T: Scalar> MulAssign<T> for Dense<T> {
+    fn mul_assign(&mut self, alpha: T) {
+        self.data.iter_mut().for_each(|x| *x *= alpha);
+    }
+}
+
+impl<T: Scalar> Mul<T> for &Dense<T> {
+    type Output = Dense<T>;
+
+    fn mul(self, alpha: T) -> Dense<T> {
+        self.map(|x| x * alpha)
+    }
+}
+
+impl<T: Scalar> Mul<T> for Dense<T> {
+    type Output = Dense<T>;
+
+    fn mul(mut self, alpha: T) -> Dense<T> {
+        self *= alpha;
+        self
+    }
+}
+
+macro_rules! impl_scalar_left_mul {
+    ($t:ty) => {
+        impl Mul<&Dense<$t>> for $t {
+            type Output = Dense<$t>;
+
+            fn mul(self, mat: &Dense<$t>) -> Dense<$t> {
+                mat * self
+            }
+        }
+
+        impl Mul<Dense<$t>> for $t {
+            type Output = Dense<$t>;
+
+            fn mul(self, mat: Dense<$t>) -> Dense<$t> {
+                mat * self
+            }
+        }
+    };
+}
+
+impl_scalar_left_mul!(f32);
+impl_scalar_left_mul!(f64);
+
+// Borrowed dense matrix with arbitrary strides: entry (i, j) is data[i * strides.0 + j * strides.1].
+// Submatrices and transposes of a Dense (or of another view) don't copy anything.
+#[derive(Debug, Clone, Copy)]
+pub struct DenseView<'a, T = f64> {
+    pub data: &'a [T],
+    pub shape: (usize, usize),
+    pub strides: (usize, usize),
+}
+
+pub struct DenseViewMut<'a, T = f64> {
+    pub data: &'a mut [T],
+    pub shape: (usize, usize),
+    pub strides: (usize, usize),
+}
+
+// All entries of a view have to be inside data
+fn check_strides(len: usize, shape: (usize, usize), strides: (usize, usize)) {
+    if shape.0 > 0 && shape.1 > 0 {
+        let last = (shape.0 - 1) * strides.0 + (shape.1 - 1) * strides.1;
+        assert!(
+            last < len,
+            "View of shape {:?} with strides {:?} doesn't fit into {} entries",
+            shape,
+            strides,
+            len
+        );
+    }
+}
+
+// Start of the submatrix in data and its shape, empty submatrices start at 0
+fn sub_offset(
+    shape: (usize, usize),
+    strides: (usize, usize),
+    rows: &Range<usize>,
+    cols: &Range<usize>,
+) -> (usize, (usize, usize)) {
+    assert!(
+        rows.start <= rows.end
+            && rows.end <= shape.0
+            && cols.start <= cols.end
+            && cols.end <= shape.1,
+        "Submatrix {:?} x {:?} out of bounds of shape {:?}",
+        rows,
+        cols,
+        shape
+    );
+    let sub_shape = (rows.len(), cols.len());
+    if sub_shape.0 == 0 || sub_shape.1 == 0 {
+        return (0, sub_shape);
+    }
+    (rows.start * strides.0 + cols.start * strides.1, sub_shape)
+}
+
+impl<'a, T: Scalar> DenseView<'a, T> {
+    pub fn new(data: &'a [T], shape: (usize, usize), strides: (usize, usize)) -> Self {
+        check_strides(data.len(), shape, strides);
+        DenseView {
+            data,
+            shape,
+            strides,
+        }
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> T {
+        assert!(
+            i < self.shape.0 && j < self.shape.1,
+            "Index ({}, {}) out of bounds",
+            i,
+            j
+        );
+        self.data[i * self.strides.0 + j * self.strides.1]
+    }
+
+    // Rows rows and columns cols of the view
+    pub fn submatrix(&self, rows: Range<usize>, cols: Range<usize>) -> DenseView<'a, T> {
+        let (offset, shape) = sub_offset(self.shape, self.strides, &rows, &cols);
+        DenseView {
+            data: &self.data[offset..],
+            shape,
+            strides: self.strides,
+        }
+    }
+
+    pub fn transpose(&self) -> DenseView<'a, T> {
+        DenseView {
+            data: self.data,
+            shape: (self.shape.1, self.shape.0),
+            strides: (self.strides.1, self.strides.0),
+        }
+    }
+
+    // Layout and leading dimension (distance between rows resp. columns) if the view
+    // can be handed to BLAS as it is, None if it has to be copied (e.g. with to_dense)
+    pub fn blas_layout(&self) -> Option<(Layout, usize)> {
+        let (m, n) = self.shape;
+        let (s0, s1) = self.strides;
+        if s1 == 1 || n <= 1 {
+            let ld = if m <= 1 { n.max(1) } else { s0 };
+            if ld >= n.max(1) {
+                return Some((Layout::RowMajor, ld));
+            }
+        }
+        if s0 == 1 || m <= 1 {
+            let ld = if n <= 1 { m.max(1) } else { s1 };
+            if ld >= m.max(1) {
+                return Some((Layout::ColumnMajor, ld));
+            }
+        }
+        None
+    }
+
+    // The entries row by row
+    pub fn iter(&self) -> impl Iterator<Item = T> + 'a {
+        let view = *self;
+        (0..view.shape.0).flat_map(move |i| (0..view.shape.1).map(move |j| view.get(i, j)))
+    }
+
+    // Row-major copy
+    pub fn to_dense(&self) -> Dense<T> {
+        Dense {
+            data: self.iter().collect(),
+            shape: self.shape,
+            layout: Layout::RowMajor,
+        }
+    }
+}
+
+impl<'a, T: Scalar> DenseViewMut<'a, T> {
+    pub fn new(data: &'a mut [T], shape: (usize, usize), strides: (usize, usize)) -> Self {
+        check_strides(data.len(), shape, strides);
+        DenseViewMut {
+            data,
+            shape,
+            strides,
+        }
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> T {
+        self.view().get(i, j)
+    }
+
+    pub fn set(&mut self, i: usize, j: usize, x: T) {
+        assert!(
+            i < self.shape.0 && j < self.shape.1,
+            "Index ({}, {}) out of bounds",
+            i,
+            j
+        );
+        self.data[i * self.strides.0 + j * self.strides.1] = x;
+    }
+
+    pub fn view(&self) -> DenseView<'_, T> {
+        DenseView {
+            data: self.data,
+            shape: self.shape,
+            strides: self.strides,
+        }
+    }
+
+    pub fn submatrix_mut(&mut self, rows: Range<usize>, cols: Range<usize>) -> DenseViewMut<'_, T> {
+        let (offset, shape) = sub_offset(self.shape, self.strides, &rows, &cols);
+        DenseViewMut {
+            data: &mut self.data[offset..],
+            shape,
+            strides: self.strides,
+        }
+    }
+
+    pub fn transpose(self) -> DenseViewMut<'a, T> {
+        DenseViewMut {
+            data: self.data,
+            shape: (self.shape.1, self.shape.0),
+            strides: (self.strides.1, self.strides.0),
+        }
+    }
+
+    // Overwrite all entries with those of a view of the same shape
+    pub fn copy_from(&mut self, other: &DenseView<'_, T>) {
+        assert_eq!(self.shape, other.shape, "Shapes of the views don't match");
+        for i in 0..self.shape.0 {
+            for j in 0..self.shape.1 {
+                self.set(i, j, other.get(i, j));
+            }
         }
     }
 }
@@ -0,0 +1,201 @@
+use std::path::Path;
+
+use crate::mtx::MtxWriteOptions;
+use crate::{Dense, Error, RealScalar, Scalar, COO, CSR};
+
+// Diagonal format for banded matrices, e.g. Toeplitz or stencil matrices.
+// Only the diagonals with an entry are stored, offset = j - i
+// (0 is the main diagonal, positive offsets are above it).
+// The d-th diagonal is stored with one value per row:
+// values[d*m + i] is the entry (i, i + offsets[d]).
+// Positions outside of the matrix are padding with value zero.
+pub struct DIA<T = f64> {
+    pub offsets: Vec<isize>,
+    pub values: Vec<T>,
+    pub shape: (usize, usize),
+    // Number of entries of the original matrix, the rest of values is padding
+    pub nnz: usize,
+}
+
+impl<T: Scalar> DIA<T> {
+    // Offsets of all diagonals with an entry, sorted
+    pub fn diagonal_offsets(csr: &CSR<T>) -> Vec<isize> {
+        let (m, n) = csr.shape;
+        // Offsets range from -(m-1) to n-1
+        let mut used = vec![false; m + n];
+        for i in 0..m {
+            for pos in csr.row_pos[i]..csr.row_pos[i + 1] {
+                used[csr.col_pos[pos] + m - i] = true;
+            }
+        }
+
+        used.iter()
+            .enumerate()
+            .filter(|(_, used)| **used)
+            .map(|(k, _)| k as isize - m as isize)
+            .collect()
+    }
+
+    // Converts only if the matrix is banded, i.e. the stored diagonals
+    // need at most max_fill_ratio values per entry.
+    // A tridiagonal matrix has a fill ratio close to 1.
+    pub fn try_from_csr(csr: &CSR<T>, max_fill_ratio: f64) -> Option<Self> {
+        let offsets = Self::diagonal_offsets(csr);
+        if !Self::within_fill_ratio(csr, offsets.len(), max_fill_ratio) {
+            return None;
+        }
+        Some(Self::from_csr_with_offsets(csr, offsets))
+    }
+
+    // Same check as try_from_csr, but only the offsets are collected, nothing is converted
+    pub fn is_banded(csr: &CSR<T>, max_fill_ratio: f64) -> bool {
+        Self::within_fill_ratio(csr, Self::diagonal_offsets(csr).len(), max_fill_ratio)
+    }
+
+    fn within_fill_ratio(csr: &CSR<T>, diagonals: usize, max_fill_ratio: f64) -> bool {
+        let stored = diagonals * csr.shape.0;
+        stored as f64 <= max_fill_ratio * csr.values.len().max(1) as f64
+    }
+
+    // Duplicate entries are summed up
+    pub fn from_csr(csr: &CSR<T>) -> Self {
+        Self::from_csr_with_offsets(csr, Self::diagonal_offsets(csr))
+    }
+
+    pub fn from_coo(coo: &COO<T>) -> Self {
+        Self::from_csr(&CSR::from_coo(coo))
+    }
+
+    fn from_csr_with_offsets(csr: &CSR<T>, offsets: Vec<isize>) -> Self {
+        let (m, n) = csr.shape;
+
+        // Offset -> index of the diagonal
+        let mut diag_idx = vec![usize::MAX; m + n];
+        for (d, off) in offsets.iter().enumerate() {
+            diag_idx[(off + m as isize) as usize] = d;
+        }
+
+        let mut values = vec![T::zero(); offsets.len() * m];
+        // Duplicates count only once
+        let mut filled = vec![false; values.len()];
+        let mut nnz = 0;
+        for i in 0..m {
+            for pos in csr.row_pos[i]..csr.row_pos[i + 1] {
+                let k = diag_idx[csr.col_pos[pos] + m - i] * m + i;
+                values[k] += csr.values[pos];
+                if !filled[k] {
+                    filled[k] = true;
+                    nnz += 1;
+                }
+            }
+        }
+
+        DIA {
+            offsets,
+            values,
+            shape: csr.shape,
+            nnz,
+        }
+    }
+
+    // Values of the diagonal with the given offset, one per row
+    pub fn get_diagonal(&self, offset: isize) -> Option<&[T]> {
+        let m = self.shape.0;
+        let d = self.offsets.binary_search(&offset).ok()?;
+        Some(&self.values[d * m..(d + 1) * m])
+    }
+
+    // Rows i for which (i, i + offset) is inside the matrix
+    pub fn diagonal_rows(&self, offset: isize) -> std::ops::Range<usize> {
+        let (m, n) = (self.shape.0 as isize, self.shape.1 as isize);
+        let start = (-offset).clamp(0, m);
+        let end = (n - offset).clamp(start, m);
+        start as usize..end as usize
+    }
+
+    // Number of stored zeros that come from padding the diagonals
+    pub fn padding(&self) -> usize {
+        self.values.len() - self.nnz
+    }
+
+    // Stored values per original entry, 1.0 means no padding at all
+    pub fn fill_ratio(&self) -> f64 {
+        if self.nnz == 0 {
+            return 1.;
+        }
+        self.values.len() as f64 / self.nnz as f64
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Sparse ({},{})-matrix in DIA format with {} diagonals, {} entries and {} padding",
+            self.shape.0,
+            self.shape.1,
+            self.offsets.len(),
+            self.nnz,
+            self.padding()
+        );
+        println!("Offsets {:?}", self.offsets);
+        println!("Values {:?}", self.values);
+    }
+
+    // Write mtx-file, see MtxWriteOptions for the possible formats
+    pub fn write_mtx(&self, fname: &Path, opts: &MtxWriteOptions) -> Result<(), Error> {
+        self.to_coo().write_mtx(fname, opts)
+    }
+
+    // Zeros on the diagonals are dropped. That includes the padding,
+    // but also zeros that were stored explicitly in the original matrix.
+    // Entries are sorted by row.
+    pub fn to_coo(&self) -> COO<T> {
+        let m = self.shape.0;
+        let mut data = Vec::with_capacity(self.nnz);
+
+        for i in 0..m {
+            for (d, off) in self.offsets.iter().enumerate() {
+                let x = self.values[d * m + i];
+                if x != T::zero() && self.diagonal_rows(*off).contains(&i) {
+                    data.push((i, (i as isize + off) as usize, x));
+                }
+            }
+        }
+
+        COO {
+            data,
+            shape: self.shape,
+        }
+    }
+
+    pub fn to_csr(&self) -> CSR<T> {
+        CSR::from_coo(&self.to_coo())
+    }
+
+    pub fn to_dense(&self) -> Dense<T> {
+        let m = self.shape.0;
+        let mut mat = Dense::new_zeros(self.shape);
+
+        for (d, off) in self.offsets.iter().enumerate() {
+            for i in self.diagonal_rows(*off) {
+                mat.set(i, (i as isize + off) as usize, self.values[d * m + i]);
+            }
+        }
+
+        mat
+    }
+}
+
+impl<T: RealScalar> DIA<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> DIA<U> {
+        DIA {
+            offsets: self.offsets.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|x| U::from_f64(x.to_f64()))
+                .collect(),
+            shape: self.shape,
+            nnz: self.nnz,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{Dense, RealScalar, Scalar, COO, CSR};
+
+// ELLPACK format: every row is padded to the length of the longest row (width).
+// The entries are stored column-major, i.e. the k-th entry of row i is at k*m + i.
+// This way neighbouring threads (rows) read neighbouring memory on the GPU.
+// Padding has the value zero and column index 0, row_len tells where it starts.
+pub struct ELL<T = f64> {
+    pub col_pos: Vec<usize>,
+    pub values: Vec<T>,
+    pub row_len: Vec<usize>,
+    pub width: usize,
+    pub shape: (usize, usize),
+}
+
+impl<T: Scalar> ELL<T> {
+    pub fn from_csr(csr: &CSR<T>) -> Self {
+        let m = csr.shape.0;
+        let row_len: Vec<usize> = (0..m).map(|i| csr.get_row_nnz(i)).collect();
+        let width = row_len.iter().copied().max().unwrap_or(0);
+
+        let mut col_pos = vec![0; width * m];
+        let mut values = vec![T::zero(); width * m];
+        for i in 0..m {
+            for (k, pos) in (csr.row_pos[i]..csr.row_pos[i + 1]).enumerate() {
+                col_pos[k * m + i] = csr.col_pos[pos];
+                values[k * m + i] = csr.values[pos];
+            }
+        }
+
+        ELL {
+            col_pos,
+            values,
+            row_len,
+            width,
+            shape: csr.shape,
+        }
+    }
+
+    pub fn from_coo(coo: &COO<T>) -> Self {
+        Self::from_csr(&CSR::from_coo(coo))
+    }
+
+    // Number of stored (non-padding) entries
+    pub fn nnz(&self) -> usize {
+        self.row_len.iter().sum()
+    }
+
+    // Number of stored zeros that come from padding the rows
+    pub fn padding(&self) -> usize {
+        self.values.len() - self.nnz()
+    }
+
+    // Stored values per entry, 1.0 means no padding at all
+    pub fn fill_ratio(&self) -> f64 {
+        let nnz = self.nnz();
+        if nnz == 0 {
+            return 1.;
+        }
+        self.values.len() as f64 / nnz as f64
+    }
+
+    // Column indices and values of row i, without padding
+    pub fn get_row(&self, i: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let m = self.shape.0;
+        (0..self.row_len[i]).map(move |k| (self.col_pos[k * m + i], self.values[k * m + i]))
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Sparse ({},{})-matrix in ELL format with width {}, {} entries and {} padding",
+            self.shape.0,
+            self.shape.1,
+            self.width,
+            self.nnz(),
+            self.padding()
+        );
+        println!("Row Len {:?}", self.row_len);
+        println!("Col Pos {:?}", self.col_pos);
+        println!("Values {:?}", self.values);
+    }
+
+    pub fn to_csr(&self) -> CSR<T> {
+        let mut row_pos = vec![0];
+        let mut col_pos = Vec::with_capacity(self.nnz());
+        let mut values = Vec::with_capacity(self.nnz());
+
+        for i in 0..self.shape.0 {
+            for (j, x) in self.get_row(i) {
+                col_pos.push(j);
+                values.push(x);
+            }
+            row_pos.push(values.len());
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: self.shape,
+        }
+    }
+
+    pub fn to_dense(&self) -> Dense<T> {
+        let mut mat = Dense::new_zeros(self.shape);
+
+        for i in 0..self.shape.0 {
+            for (j, x) in self.get_row(i) {
+                mat.set(i, j, x);
+            }
+        }
+
+        mat
+    }
+}
+
+impl<T: RealScalar> ELL<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> ELL<U> {
+        ELL {
+            col_pos: self.col_pos.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|x| U::from_f64(x.to_f64()))
+                .collect(),
+            row_len: self.row_len.clone(),
+            width: self.width,
+            shape: self.shape,
+        }
+    }
+}
@@ -0,0 +1,308 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Errors of the matrix loaders and writers.
+// Everything carries the file path, parsing errors also
+// the (1-based) line and column where things went wrong.
+#[derive(Debug)]
+pub enum Error {
+    // File could not be opened, read or written
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // Malformed or unknown %%MatrixMarket banner
+    Banner {
+        path: PathBuf,
+        line: usize,
+    },
+    // Banner is valid, but we can't handle it (yet)
+    Unsupported {
+        path: PathBuf,
+        line: usize,
+        msg: String,
+    },
+    // Missing or malformed size line ("m n nnz" or "m n" for array files)
+    SizeLine {
+        path: PathBuf,
+        line: usize,
+    },
+    // The number of entries differs from the one announced in the size line
+    EntryCount {
+        path: PathBuf,
+        expected: usize,
+        found: usize,
+    },
+    // Entry index (1-based, as in the file) outside of the matrix shape
+    IndexOutOfBounds {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        index: (usize, usize),
+        shape: (usize, usize),
+    },
+    // Token that is missing or not a number
+    Value {
+        path: PathBuf,
+        line: usize,
+        column: usize,
+        token: String,
+    },
+    // Symmetric output requested for a non-symmetric matrix
+    NotSymmetric {
+        path: PathBuf,
+    },
+    // Broken binary matrix file or one with another matrix kind,
+    // scalar type or version, see the binary module
+    Binary {
+        path: PathBuf,
+        msg: String,
+    },
+    // Broken or unsupported .npy/.npz file, see the npy module
+    Npy {
+        path: PathBuf,
+        msg: String,
+    },
+    // Malformed or unsupported Rutherford-Boeing/Harwell-Boeing file, see the rb module
+    Rb {
+        path: PathBuf,
+        line: usize,
+        msg: String,
+    },
+}
+
+impl Error {
+    pub(crate) fn io(path: &Path, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    // Path of the file that caused the error
+    pub fn path(&self) -> &Path {
+        match self {
+            Error::Io { path, .. }
+            | Error::Banner { path, .. }
+            | Error::Unsupported { path, .. }
+            | Error::SizeLine { path, .. }
+            | Error::EntryCount { path, .. }
+            | Error::IndexOutOfBounds { path, .. }
+            | Error::Value { path, .. }
+            | Error::NotSymmetric { path }
+            | Error::Binary { path, .. }
+            | Error::Npy { path, .. }
+            | Error::Rb { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            Error::Banner { path, line } => {
+                write!(
+                    f,
+                    "{}:{}: invalid MatrixMarket banner",
+                    path.display(),
+                    line
+                )
+            }
+            Error::Unsupported { path, line, msg } => {
+                write!(
+                    f,
+                    "{}:{}: unsupported matrix: {}",
+                    path.display(),
+                    line,
+                    msg
+                )
+            }
+            Error::SizeLine { path, line } => {
+                write!(
+                    f,
+                    "{}:{}: invalid or missing size line",
+                    path.display(),
+                    line
+                )
+            }
+            Error::EntryCount {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{}: expected {} entries, found {}",
+                path.display(),
+                expected,
+                found
+            ),
+            Error::IndexOutOfBounds {
+                path,
+                line,
+                column,
+                index,
+                shape,
+            } => write!(
+                f,
+                "{}:{}:{}: index ({}, {}) out of bounds for ({}, {})-matrix",
+                path.display(),
+                line,
+                column,
+                index.0,
+                index.1,
+                shape.0,
+                shape.1
+            ),
+            Error::Value {
+                path,
+                line,
+                column,
+                token,
+            } => {
+                if token.is_empty() {
+                    write!(f, "{}:{}:{}: missing value", path.display(), line, column)
+                } else {
+                    write!(
+                        f,
+                        "{}:{}:{}: invalid value '{}'",
+                        path.display(),
+                        line,
+                        column,
+                        token
+                    )
+                }
+            }
+            Error::NotSymmetric { path } => {
+                write!(f, "{}: matrix is not symmetric", path.display())
+            }
+            Error::Binary { path, msg } => {
+                write!(f, "{}: invalid binary matrix file: {}", path.display(), msg)
+            }
+            Error::Npy { path, msg } => {
+                write!(f, "{}: invalid npy/npz file: {}", path.display(), msg)
+            }
+            Error::Rb { path, line, msg } => {
+                write!(
+                    f,
+                    "{}:{}: invalid Rutherford-Boeing file: {}",
+                    path.display(),
+                    line,
+                    msg
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Broken structure of a sparse matrix, see CSR::validate and COO::validate.
+// Rows, columns and entries are 0-based here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructureError {
+    // row_pos must have one entry per row plus one
+    RowPosLength {
+        expected: usize,
+        found: usize,
+    },
+    // row_pos must start with 0
+    RowPosStart {
+        found: usize,
+    },
+    // row_pos[row + 1] < row_pos[row]
+    RowPosDecreasing {
+        row: usize,
+    },
+    // The last entry of row_pos must be the number of entries
+    RowPosEnd {
+        expected: usize,
+        found: usize,
+    },
+    // col_pos and values must have the same length
+    ValueCount {
+        col_pos: usize,
+        values: usize,
+    },
+    ColumnOutOfBounds {
+        row: usize,
+        column: usize,
+        columns: usize,
+    },
+    // Columns inside a row must be strictly increasing
+    ColumnsNotSorted {
+        row: usize,
+    },
+    DuplicateColumn {
+        row: usize,
+        column: usize,
+    },
+    // COO entry with index outside of the shape
+    IndexOutOfBounds {
+        entry: usize,
+        index: (usize, usize),
+        shape: (usize, usize),
+    },
+}
+
+impl fmt::Display for StructureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StructureError::RowPosLength { expected, found } => write!(
+                f,
+                "row_pos has length {}, expected {} (rows + 1)",
+                found, expected
+            ),
+            StructureError::RowPosStart { found } => {
+                write!(f, "row_pos starts with {} instead of 0", found)
+            }
+            StructureError::RowPosDecreasing { row } => {
+                write!(f, "row_pos decreases at row {}", row)
+            }
+            StructureError::RowPosEnd { expected, found } => write!(
+                f,
+                "row_pos ends with {}, but there are {} entries",
+                found, expected
+            ),
+            StructureError::ValueCount { col_pos, values } => write!(
+                f,
+                "col_pos has {} entries, but values has {}",
+                col_pos, values
+            ),
+            StructureError::ColumnOutOfBounds {
+                row,
+                column,
+                columns,
+            } => write!(
+                f,
+                "column {} in row {} out of bounds for {} columns",
+                column, row, columns
+            ),
+            StructureError::ColumnsNotSorted { row } => {
+                write!(f, "columns of row {} are not sorted", row)
+            }
+            StructureError::DuplicateColumn { row, column } => {
+                write!(f, "duplicate column {} in row {}", column, row)
+            }
+            StructureError::IndexOutOfBounds {
+                entry,
+                index,
+                shape,
+            } => write!(
+                f,
+                "entry {}: index ({}, {}) out of bounds for ({}, {})-matrix",
+                entry, index.0, index.1, shape.0, shape.1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StructureError {}
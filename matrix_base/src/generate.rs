@@ -0,0 +1,544 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::{Dense, Error, Layout, MtxWriteOptions, Scalar, COO, CSR};
+
+// Seeded random matrices, replacing generate_matrices_experiments.py.
+// The same seed gives the same matrices on every platform. The generator is implemented here
+// instead of taking StdRng from the rand crate, whose output may change between versions.
+
+// xoshiro256**, seeded through SplitMix64, see https://prng.di.unimi.it/
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: [u64; 4],
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        let mut x = seed;
+        let mut splitmix = || {
+            x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = x;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        Rng {
+            state: [splitmix(), splitmix(), splitmix(), splitmix()],
+        }
+    }
+
+    // Independent generator for one file: the matrix only depends on the seed and its name,
+    // not on which other matrices are generated in the same run
+    pub fn for_name(seed: u64, name: &str) -> Self {
+        // FNV-1a, std's hashers are not guaranteed to be stable
+        let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+            (h ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        Rng::new(seed ^ hash)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let s = &mut self.state;
+        let result = s[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = s[1] << 17;
+        s[2] ^= s[0];
+        s[3] ^= s[1];
+        s[1] ^= s[2];
+        s[0] ^= s[3];
+        s[2] ^= t;
+        s[3] = s[3].rotate_left(45);
+        result
+    }
+
+    // Uniform in [0, 1) with 53 random bits, like np.random.rand
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    // Uniform in 0..n (multiply-shift, the bias is below n / 2^64)
+    pub fn below(&mut self, n: u64) -> u64 {
+        ((self.next_u64() as u128 * n as u128) >> 64) as u64
+    }
+}
+
+// Dense matrix with entries uniform in [0, 1)
+pub fn random_dense<T: Scalar>(shape: (usize, usize), rng: &mut Rng) -> Dense<T> {
+    let data = (0..shape.0 * shape.1)
+        .map(|_| T::from_f64(rng.next_f64()))
+        .collect();
+    Dense {
+        data,
+        shape,
+        layout: Layout::RowMajor,
+    }
+}
+
+// Sparse matrix with round(density * m * n) entries at distinct, uniformly chosen positions
+// (like scipy.sparse.random_array), values uniform in [0, 1). The entries are sorted by row and column.
+pub fn random_sparse<T: Scalar>(shape: (usize, usize), density: f64, rng: &mut Rng) -> COO<T> {
+    let (m, n) = shape;
+    let size = m as u64 * n as u64;
+    let nnz = ((density.clamp(0., 1.) * size as f64).round() as u64).min(size);
+
+    let data = distinct_sorted(nnz, size, rng)
+        .into_iter()
+        .map(|pos| {
+            let (i, j) = ((pos / n as u64) as usize, (pos % n as u64) as usize);
+            (i, j, T::from_f64(rng.next_f64()))
+        })
+        .collect();
+    COO { data, shape }
+}
+
+// k distinct numbers out of 0..size in ascending order.
+// Floyd's algorithm, O(k) no matter how close k is to size.
+fn distinct_sorted(k: u64, size: u64, rng: &mut Rng) -> Vec<u64> {
+    let mut chosen = HashSet::with_capacity(k as usize);
+    for l in size - k..size {
+        let x = rng.below(l + 1);
+        if !chosen.insert(x) {
+            chosen.insert(l);
+        }
+    }
+    let mut chosen: Vec<u64> = chosen.into_iter().collect();
+    chosen.sort_unstable();
+    chosen
+}
+
+// Finite-difference stencils of the Laplacian, 2D (5, 9 points) and 3D (7, 27 points)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stencil {
+    Point5,
+    Point9,
+    Point7,
+    Point27,
+}
+
+impl Stencil {
+    pub fn dims(self) -> usize {
+        match self {
+            Stencil::Point5 | Stencil::Point9 => 2,
+            Stencil::Point7 | Stencil::Point27 => 3,
+        }
+    }
+
+    pub fn points(self) -> usize {
+        match self {
+            Stencil::Point5 => 5,
+            Stencil::Point9 => 9,
+            Stencil::Point7 => 7,
+            Stencil::Point27 => 27,
+        }
+    }
+
+    // Offsets (dz, dy, dx) of the stencil in ascending order of the grid index.
+    // The 5/7-point stencils only go along the axes, the 9/27-point ones also diagonally.
+    fn offsets(self) -> Vec<[isize; 3]> {
+        let dz = if self.dims() == 3 { -1..=1 } else { 0..=0 };
+        dz.flat_map(|z| (-1..=1).flat_map(move |y| (-1..=1).map(move |x| [z, y, x])))
+            .filter(|offset| {
+                let axes = offset.iter().filter(|d| **d != 0).count();
+                matches!(self, Stencil::Point9 | Stencil::Point27) || axes <= 1
+            })
+            .collect()
+    }
+}
+
+// Laplacian on a grid with n points per dimension (n^2 or n^3 rows, numbered x first), Dirichlet boundary.
+// Neighbours get -1, the diagonal the number of neighbours of an inner point (4, 8, 6 or 26, the last like
+// in HPCG), so the matrix is symmetric and diagonally dominant. Rows at the boundary are shorter.
+pub fn laplacian<T: Scalar>(stencil: Stencil, n: usize) -> CSR<T> {
+    let nz = if stencil.dims() == 3 { n } else { 1 };
+    let offsets = stencil.offsets();
+    let diagonal = T::from_f64((offsets.len() - 1) as f64);
+    let rows = nz * n * n;
+
+    let mut row_pos = Vec::with_capacity(rows + 1);
+    let mut col_pos = Vec::with_capacity(rows * offsets.len());
+    let mut values = Vec::with_capacity(rows * offsets.len());
+    row_pos.push(0);
+    for z in 0..nz {
+        for y in 0..n {
+            for x in 0..n {
+                for [dz, dy, dx] in &offsets {
+                    let (k, l, m) = (z as isize + dz, y as isize + dy, x as isize + dx);
+                    if k < 0
+                        || l < 0
+                        || m < 0
+                        || k >= nz as isize
+                        || l >= n as isize
+                        || m >= n as isize
+                    {
+                        continue;
+                    }
+                    col_pos.push((k as usize * n + l as usize) * n + m as usize);
+                    values.push(if [*dz, *dy, *dx] == [0; 3] {
+                        diagonal
+                    } else {
+                        -T::one()
+                    });
+                }
+                row_pos.push(col_pos.len());
+            }
+        }
+    }
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape: (rows, rows),
+    }
+}
+
+// Matrix with the given diagonals (offset k = j - i, distinct), value(d, i) gives the entry of
+// diagonal d in row i
+fn diagonals<T: Scalar>(
+    shape: (usize, usize),
+    offsets: &[isize],
+    mut value: impl FnMut(usize, usize) -> T,
+) -> CSR<T> {
+    let mut order: Vec<usize> = (0..offsets.len()).collect();
+    order.sort_by_key(|d| offsets[*d]);
+    assert!(
+        order.windows(2).all(|w| offsets[w[0]] != offsets[w[1]]),
+        "diagonal offsets must be distinct"
+    );
+
+    let (m, n) = shape;
+    let mut row_pos = vec![0];
+    let mut col_pos = vec![];
+    let mut values = vec![];
+    for i in 0..m {
+        for &d in &order {
+            let j = i as isize + offsets[d];
+            if j >= 0 && j < n as isize {
+                col_pos.push(j as usize);
+                values.push(value(d, i));
+            }
+        }
+        row_pos.push(col_pos.len());
+    }
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape,
+    }
+}
+
+// Toeplitz matrix, constant along the diagonals: (k, x) sets diagonal k = j - i to x
+pub fn toeplitz<T: Scalar>(shape: (usize, usize), diagonals_values: &[(isize, T)]) -> CSR<T> {
+    let offsets: Vec<isize> = diagonals_values.iter().map(|(k, _)| *k).collect();
+    diagonals(shape, &offsets, |d, _| diagonals_values[d].1)
+}
+
+// Banded matrix with the given diagonals (offsets k = j - i), values uniform in [0, 1)
+pub fn banded<T: Scalar>(shape: (usize, usize), offsets: &[isize], rng: &mut Rng) -> CSR<T> {
+    diagonals(shape, offsets, |_, _| T::from_f64(rng.next_f64()))
+}
+
+// Probabilities (a, b, c, d) of the Graph500 benchmark
+pub const RMAT_GRAPH500: [f64; 4] = [0.57, 0.19, 0.19, 0.05];
+
+// R-MAT graph, i.e. a stochastic Kronecker graph with initiator [[a, b], [c, d]]: 2^scale vertices and
+// edge_factor * 2^scale edges, each edge descends scale times into one of the four quadrants of the
+// adjacency matrix with the given probabilities. Low rows and columns get most of the entries
+// (row lengths follow a power law for a > d). Duplicate edges are dropped, values uniform in [0, 1).
+pub fn rmat<T: Scalar>(
+    scale: u32,
+    edge_factor: usize,
+    probabilities: [f64; 4],
+    rng: &mut Rng,
+) -> COO<T> {
+    let n = 1u64 << scale;
+    let [a, b, c, _] = probabilities;
+    let total: f64 = probabilities.iter().sum();
+
+    let mut positions: Vec<u64> = (0..edge_factor as u64 * n)
+        .map(|_| {
+            let (mut i, mut j) = (0, 0);
+            for _ in 0..scale {
+                let r = rng.next_f64() * total;
+                let (down, right) = match r {
+                    r if r < a => (0, 0),
+                    r if r < a + b => (0, 1),
+                    r if r < a + b + c => (1, 0),
+                    _ => (1, 1),
+                };
+                i = 2 * i + down;
+                j = 2 * j + right;
+            }
+            i * n + j
+        })
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+
+    let data = positions
+        .into_iter()
+        .map(|pos| {
+            (
+                (pos / n) as usize,
+                (pos % n) as usize,
+                T::from_f64(rng.next_f64()),
+            )
+        })
+        .collect();
+    COO {
+        data,
+        shape: (n as usize, n as usize),
+    }
+}
+
+// Kronecker product, block (i, j) is a_ij * b. Repeated with a small initiator it gives the
+// deterministic Kronecker graphs, e.g. kronecker(&g, &kronecker(&g, &g)).
+pub fn kronecker<T: Scalar>(a: &CSR<T>, b: &CSR<T>) -> CSR<T> {
+    let (mb, nb) = b.shape;
+    let mut row_pos = vec![0];
+    let mut col_pos = Vec::with_capacity(a.values.len() * b.values.len());
+    let mut values = Vec::with_capacity(a.values.len() * b.values.len());
+    for ia in 0..a.shape.0 {
+        for ib in 0..mb {
+            // Columns stay sorted: block column first, then the column of b
+            for ka in a.row_pos[ia]..a.row_pos[ia + 1] {
+                for kb in b.row_pos[ib]..b.row_pos[ib + 1] {
+                    col_pos.push(a.col_pos[ka] * nb + b.col_pos[kb]);
+                    values.push(a.values[ka] * b.values[kb]);
+                }
+            }
+            row_pos.push(col_pos.len());
+        }
+    }
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape: (a.shape.0 * mb, a.shape.1 * nb),
+    }
+}
+
+// Row lengths from a power law P(length >= d) ~ d^(1 - exponent) (Pareto, exponent > 1), scaled to
+// avg_row_nnz on average and capped at the number of columns: a few very long rows and many short ones,
+// the worst case for splitting the work by rows. Columns are distinct and uniform, values uniform in [0, 1).
+pub fn power_law_rows<T: Scalar>(
+    shape: (usize, usize),
+    avg_row_nnz: f64,
+    exponent: f64,
+    rng: &mut Rng,
+) -> CSR<T> {
+    assert!(exponent > 1., "power law exponent must be larger than 1");
+    let (m, n) = shape;
+    let weights: Vec<f64> = (0..m)
+        .map(|_| (1. - rng.next_f64()).powf(-1. / (exponent - 1.)))
+        .collect();
+    let factor = avg_row_nnz * m as f64 / weights.iter().sum::<f64>();
+
+    let mut row_pos = vec![0];
+    let mut col_pos = vec![];
+    let mut values = vec![];
+    for w in weights {
+        let len = ((w * factor).round() as u64).min(n as u64);
+        for j in distinct_sorted(len, n as u64, rng) {
+            col_pos.push(j as usize);
+            values.push(T::from_f64(rng.next_f64()));
+        }
+        row_pos.push(col_pos.len());
+    }
+
+    CSR {
+        row_pos,
+        col_pos,
+        values,
+        shape,
+    }
+}
+
+// Structured matrices of an ExperimentSet, given on the command line as
+//   lap5:N, lap9:N, lap7:N, lap27:N    Laplacian, N points per dimension
+//   band:N:W                           N x N banded with the 2W+1 diagonals -W..=W
+//   rmat:S                             R-MAT graph with 2^S vertices, Graph500 parameters, edge factor 16
+//   powerlaw:N                         N x N with power-law row lengths, 16 per row on average, exponent 2.1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Structured {
+    Laplacian(Stencil, usize),
+    Banded(usize, usize),
+    Rmat(u32),
+    PowerLaw(usize),
+}
+
+const RMAT_EDGE_FACTOR: usize = 16;
+const POWER_LAW_AVG_ROW_NNZ: f64 = 16.;
+// Roughly the exponent of web and social graphs
+const POWER_LAW_EXPONENT: f64 = 2.1;
+
+impl Structured {
+    // Part of the file name, e.g. lap27-16 or band-1000-5
+    pub fn name(&self) -> String {
+        match self {
+            Structured::Laplacian(stencil, n) => format!("lap{}-{}", stencil.points(), n),
+            Structured::Banded(n, w) => format!("band-{}-{}", n, w),
+            Structured::Rmat(scale) => format!("rmat-{}", scale),
+            Structured::PowerLaw(n) => format!("powerlaw-{}", n),
+        }
+    }
+
+    pub fn generate<T: Scalar>(&self, rng: &mut Rng) -> CSR<T> {
+        match *self {
+            Structured::Laplacian(stencil, n) => laplacian(stencil, n),
+            Structured::Banded(n, w) => {
+                let w = w as isize;
+                banded((n, n), &(-w..=w).collect::<Vec<_>>(), rng)
+            }
+            Structured::Rmat(scale) => {
+                CSR::from_coo(&rmat(scale, RMAT_EDGE_FACTOR, RMAT_GRAPH500, rng))
+            }
+            Structured::PowerLaw(n) => {
+                power_law_rows((n, n), POWER_LAW_AVG_ROW_NNZ, POWER_LAW_EXPONENT, rng)
+            }
+        }
+    }
+}
+
+impl FromStr for Structured {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid structured matrix '{}'", s);
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        let number = |k: usize| {
+            parts
+                .get(k)
+                .and_then(|p| p.parse::<usize>().ok())
+                .ok_or_else(invalid)
+        };
+        let stencil = match parts[0] {
+            "lap5" => Some(Stencil::Point5),
+            "lap9" => Some(Stencil::Point9),
+            "lap7" => Some(Stencil::Point7),
+            "lap27" => Some(Stencil::Point27),
+            _ => None,
+        };
+        let expected_parts = if parts[0] == "band" { 3 } else { 2 };
+        if parts.len() != expected_parts {
+            return Err(invalid());
+        }
+        match (parts[0], stencil) {
+            (_, Some(stencil)) => Ok(Structured::Laplacian(stencil, number(1)?)),
+            ("band", _) => Ok(Structured::Banded(number(1)?, number(2)?)),
+            ("rmat", _) => Ok(Structured::Rmat(
+                number(1)?.try_into().map_err(|_| invalid())?,
+            )),
+            ("powerlaw", _) => Ok(Structured::PowerLaw(number(1)?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// Matrix sets of generate_matrices_experiments.py, one subfolder per kind:
+//   dense/dense_<n>_A<k>.mtx, dense/dense_<n>_B<k>.mtx                   n x n dense
+//   sparse/sparse_<n>_A<k>.mtx, ...                                      n x n with density sparse_density
+//   sparse-vs-dense/s-vs-d_<density>_A<k>.mtx, ...                       size_sparse_vs_dense^2 with the given density
+//   sparse/sparse_<name>_A<k>.mtx, ...                                   structured, see Structured::name
+// k is the example number, padded to the number of digits of examples
+#[derive(Debug, Clone)]
+pub struct ExperimentSet {
+    pub examples: usize,
+    pub dense_sizes: Vec<usize>,
+    pub sparse_sizes: Vec<usize>,
+    pub sparse_density: f64,
+    pub sparse_vs_dense_densities: Vec<f64>,
+    pub size_sparse_vs_dense: usize,
+    pub structured: Vec<Structured>,
+}
+
+impl Default for ExperimentSet {
+    fn default() -> Self {
+        ExperimentSet {
+            examples: 1,
+            dense_sizes: vec![],
+            sparse_sizes: vec![],
+            sparse_density: 0.001,
+            sparse_vs_dense_densities: vec![],
+            size_sparse_vs_dense: 500,
+            structured: vec![],
+        }
+    }
+}
+
+impl ExperimentSet {
+    // Write all matrices below dir, existing files are overwritten. Returns the written paths.
+    pub fn write(&self, dir: &Path, seed: u64) -> Result<Vec<PathBuf>, Error> {
+        let mut written = vec![];
+        for (path, kind) in self.jobs() {
+            let fname = dir.join(&path);
+            let folder = fname.parent().unwrap_or(dir);
+            fs::create_dir_all(folder).map_err(|e| Error::io(folder, e))?;
+
+            // Seeded by the file name only, the separator differs between platforms
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let mut rng = Rng::for_name(seed, &name);
+            let opts = MtxWriteOptions::default();
+            match kind {
+                Kind::Dense(n) => random_dense::<f64>((n, n), &mut rng).write_mtx(&fname, &opts)?,
+                Kind::Sparse(n, density) => {
+                    // At least one entry, empty matrices break the GPU backends
+                    let density = density.max(1. / (n * n).max(1) as f64);
+                    random_sparse::<f64>((n, n), density, &mut rng).write_mtx(&fname, &opts)?
+                }
+                Kind::Structured(structured) => structured
+                    .generate::<f64>(&mut rng)
+                    .write_mtx(&fname, &opts)?,
+            }
+            written.push(fname);
+        }
+        Ok(written)
+    }
+
+    // Relative paths (subfolder and file name) of all matrices, A and B of each pair next to each other
+    fn jobs(&self) -> Vec<(PathBuf, Kind)> {
+        let digits = self.examples.to_string().len();
+        let mut jobs = vec![];
+        for example in 0..self.examples {
+            let example = format!("{:0digits$}", example);
+            let mut pair = |folder: &str, name: String, kind: Kind| {
+                for ab in ["A", "B"] {
+                    let fname = format!("{}_{}{}.mtx", name, ab, example);
+                    jobs.push((Path::new(folder).join(fname), kind));
+                }
+            };
+
+            for &n in &self.dense_sizes {
+                pair("dense", format!("dense_{}", n), Kind::Dense(n));
+            }
+            for &n in &self.sparse_sizes {
+                pair(
+                    "sparse",
+                    format!("sparse_{}", n),
+                    Kind::Sparse(n, self.sparse_density),
+                );
+            }
+            for &density in &self.sparse_vs_dense_densities {
+                let kind = Kind::Sparse(self.size_sparse_vs_dense, density);
+                pair("sparse-vs-dense", format!("s-vs-d_{}", density), kind);
+            }
+            for &structured in &self.structured {
+                let name = format!("sparse_{}", structured.name());
+                pair("sparse", name, Kind::Structured(structured));
+            }
+        }
+        jobs
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Kind {
+    Dense(usize),
+    Sparse(usize, f64),
+    Structured(Structured),
+}
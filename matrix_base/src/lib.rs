@@ -1,8 +1,44 @@
+pub mod scalar;
+pub use scalar::{Complex, Complex32, Complex64, RealScalar, Scalar};
+
+pub mod error;
+pub use error::{Error, StructureError};
+
 pub mod dense;
-pub use dense::Dense;
+pub use dense::{Dense, DenseView, DenseViewMut, Layout};
 
 pub mod coo;
-pub use coo::COO;
+pub use coo::{CanonicalizeOptions, COO};
 
 pub mod csr;
-pub use csr::CSR;
+pub use csr::{AsCsrView, CsrView, CSR};
+
+pub mod csc;
+pub use csc::CSC;
+
+pub mod bsr;
+pub use bsr::BSR;
+
+pub mod ell;
+pub use ell::ELL;
+
+pub mod sell;
+pub use sell::SELL;
+
+pub mod dia;
+pub use dia::DIA;
+
+pub mod mtx;
+pub use mtx::{Compression, MtxField, MtxFormat, MtxHeader, MtxSymmetry, MtxWriteOptions};
+
+pub mod binary;
+pub use binary::MappedCsr;
+
+mod npy;
+
+pub mod rb;
+
+pub mod accuracy;
+pub use accuracy::AccuracyReport;
+
+pub mod generate;
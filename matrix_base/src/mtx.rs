@@ -0,0 +1,763 @@
+// MatrixMarket header ("banner") handling, see
+// https://math.nist.gov/MatrixMarket/formats.html
+// The banner is the first line of the file and looks like
+// %%MatrixMarket matrix coordinate real general
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use xz2::read::XzDecoder;
+
+use crate::{Dense, Error, Scalar};
+
+// Readers allocate at most this many entries ahead of reading them, sizes in the header can be broken
+pub(crate) const MAX_PREALLOCATED_ENTRIES: usize = 1 << 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxFormat {
+    Coordinate,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxField {
+    Real,
+    Integer,
+    Complex,
+    Pattern,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MtxSymmetry {
+    General,
+    Symmetric,
+    SkewSymmetric,
+    Hermitian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MtxHeader {
+    pub format: MtxFormat,
+    pub field: MtxField,
+    pub symmetry: MtxSymmetry,
+}
+
+pub const BANNER: &str = "%%MatrixMarket";
+
+impl Default for MtxHeader {
+    // Files without banner (e.g. the ones written by our Python scripts)
+    // are 'coordinate real general'
+    fn default() -> Self {
+        MtxHeader {
+            format: MtxFormat::Coordinate,
+            field: MtxField::Real,
+            symmetry: MtxSymmetry::General,
+        }
+    }
+}
+
+impl MtxHeader {
+    // Parse the banner line. Keywords are case-insensitive according to the spec.
+    // Returns None if the line is not a valid banner.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.to_lowercase();
+        let mut spl = line.split_whitespace();
+
+        if spl.next()? != BANNER.to_lowercase() || spl.next()? != "matrix" {
+            return None;
+        }
+
+        let format = match spl.next()? {
+            "coordinate" => MtxFormat::Coordinate,
+            "array" => MtxFormat::Array,
+            _ => return None,
+        };
+        let field = match spl.next()? {
+            "real" | "double" => MtxField::Real,
+            "integer" => MtxField::Integer,
+            "complex" => MtxField::Complex,
+            "pattern" => MtxField::Pattern,
+            _ => return None,
+        };
+        let symmetry = match spl.next()? {
+            "general" => MtxSymmetry::General,
+            "symmetric" => MtxSymmetry::Symmetric,
+            "skew-symmetric" => MtxSymmetry::SkewSymmetric,
+            "hermitian" => MtxSymmetry::Hermitian,
+            _ => return None,
+        };
+
+        // Pattern only makes sense for sparse matrices
+        if format == MtxFormat::Array && field == MtxField::Pattern {
+            return None;
+        }
+
+        Some(MtxHeader {
+            format,
+            field,
+            symmetry,
+        })
+    }
+
+    // Number of values stored in the file for a (m,n)-matrix in array format.
+    // For symmetric matrices only the lower triangle is stored,
+    // for skew-symmetric ones without the (zero) diagonal.
+    // None if the number doesn't fit into usize (broken size line).
+    pub fn array_len(&self, shape: (usize, usize)) -> Option<usize> {
+        let (m, n) = shape;
+        match self.symmetry {
+            MtxSymmetry::General => m.checked_mul(n),
+            MtxSymmetry::Symmetric | MtxSymmetry::Hermitian => {
+                Some(n.checked_mul(n.checked_add(1)?)? / 2)
+            }
+            MtxSymmetry::SkewSymmetric => Some(n.checked_mul(n.saturating_sub(1))? / 2),
+        }
+    }
+}
+
+// Line-by-line reader for mtx-files, keeps track
+// of the line number for error messages
+// Coordinate entries (row, column, value) as stored in COO
+pub(crate) type Entries<T> = Vec<(usize, usize, T)>;
+
+pub(crate) struct MtxReader<R: BufRead> {
+    lines: Lines<R>,
+    path: PathBuf,
+    line_no: usize,
+}
+
+// Tokens of a single line, keeps track of the column for error messages.
+// Tokens are separated by any number of spaces and tabs.
+pub(crate) struct Tokens<'a> {
+    line: &'a str,
+    split: std::str::SplitAsciiWhitespace<'a>,
+    line_no: usize,
+    path: &'a Path,
+}
+
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(line: &'a str, line_no: usize, path: &'a Path) -> Self {
+        Tokens {
+            line,
+            split: line.split_ascii_whitespace(),
+            line_no,
+            path,
+        }
+    }
+
+    // 1-based column of the token inside the line
+    fn column(&self, tok: &str) -> usize {
+        tok.as_ptr() as usize - self.line.as_ptr() as usize + 1
+    }
+
+    // Parse the next token, returns the value and its column
+    pub(crate) fn next<T: FromStr>(&mut self) -> Result<(T, usize), Error> {
+        match self.split.next() {
+            Some(tok) => {
+                let column = self.column(tok);
+                tok.parse().map(|x| (x, column)).map_err(|_| Error::Value {
+                    path: self.path.to_path_buf(),
+                    line: self.line_no,
+                    column,
+                    token: String::from(tok),
+                })
+            }
+            None => Err(Error::Value {
+                path: self.path.to_path_buf(),
+                line: self.line_no,
+                column: self.line.len() + 1,
+                token: String::new(),
+            }),
+        }
+    }
+
+    // Parse a line "i j value" of a coordinate file, returns the 0-based indices
+    pub(crate) fn coordinate_entry<T: Scalar>(
+        mut self,
+        shape: (usize, usize),
+        field: MtxField,
+    ) -> Result<(usize, usize, T), Error> {
+        let (m, n) = shape;
+
+        // Indices in the file start counting with 1
+        let (i, column_i): (usize, usize) = self.next()?;
+        let (j, column_j): (usize, usize) = self.next()?;
+
+        let column = if i == 0 || i > m {
+            Some(column_i)
+        } else if j == 0 || j > n {
+            Some(column_j)
+        } else {
+            None
+        };
+        if let Some(column) = column {
+            return Err(Error::IndexOutOfBounds {
+                path: self.path.to_path_buf(),
+                line: self.line_no,
+                column,
+                index: (i, j),
+                shape,
+            });
+        }
+
+        let v = self.next_value(field)?;
+        Ok((i - 1, j - 1, v))
+    }
+
+    // Parse the value of an entry according to the field of the file.
+    // Pattern entries are 1, complex values consist of two tokens.
+    pub(crate) fn next_value<T: Scalar>(&mut self, field: MtxField) -> Result<T, Error> {
+        match field {
+            MtxField::Pattern => Ok(T::one()),
+            MtxField::Complex => {
+                let (re, _) = self.next()?;
+                let (im, _) = self.next()?;
+                Ok(T::from_parts(re, im))
+            }
+            // Integer values parse as f64 as well
+            MtxField::Real | MtxField::Integer => Ok(T::from_f64(self.next()?.0)),
+        }
+    }
+}
+
+// Compression of a mtx-file, SuiteSparse ships them as .mtx.gz
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    // By the magic bytes at the start of the file
+    pub fn detect(start: &[u8]) -> Self {
+        if start.starts_with(&[0x1f, 0x8b]) {
+            Compression::Gzip
+        } else if start.starts_with(b"BZh") {
+            Compression::Bzip2
+        } else if start.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0]) {
+            Compression::Xz
+        } else {
+            Compression::None
+        }
+    }
+
+    // By the file extension, for files that aren't opened yet
+    pub fn from_path(fname: &Path) -> Self {
+        match fname.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("bz2") => Compression::Bzip2,
+            Some("xz") => Compression::Xz,
+            _ => Compression::None,
+        }
+    }
+}
+
+// True for .mtx files, also compressed ones (.mtx.gz, .mtx.bz2, .mtx.xz)
+pub fn is_mtx_path(fname: &Path) -> bool {
+    let inner = match Compression::from_path(fname) {
+        Compression::None => fname.to_path_buf(),
+        _ => fname.with_extension(""),
+    };
+    inner.extension().unwrap_or_default() == "mtx"
+}
+
+impl MtxReader<Box<dyn BufRead>> {
+    // Compressed files are decompressed while reading, the compression
+    // is detected from the content, so the extension doesn't matter
+    pub(crate) fn open(fname: &Path) -> Result<Self, Error> {
+        let f = File::open(fname).map_err(|e| Error::io(fname, e))?;
+        let mut f = BufReader::new(f);
+        let start = f.fill_buf().map_err(|e| Error::io(fname, e))?;
+        // The multi decoders also read files with several concatenated streams (e.g. from pigz)
+        let reader: Box<dyn BufRead> = match Compression::detect(start) {
+            Compression::None => Box::new(f),
+            Compression::Gzip => Box::new(BufReader::new(MultiGzDecoder::new(f))),
+            Compression::Bzip2 => Box::new(BufReader::new(MultiBzDecoder::new(f))),
+            Compression::Xz => Box::new(BufReader::new(XzDecoder::new_multi_decoder(f))),
+        };
+        Ok(MtxReader::new(reader, fname))
+    }
+}
+
+impl<R: BufRead> MtxReader<R> {
+    pub(crate) fn new(reader: R, fname: &Path) -> Self {
+        MtxReader {
+            lines: reader.lines(),
+            path: fname.to_path_buf(),
+            line_no: 0,
+        }
+    }
+
+    // Next line of the file or None at the end
+    fn next_line(&mut self) -> Result<Option<String>, Error> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line_no += 1;
+                line.map(Some).map_err(|e| Error::io(&self.path, e))
+            }
+            None => Ok(None),
+        }
+    }
+
+    // Read banner (if any), comments and size line.
+    // Returns the header, the shape and the number of entries that follow.
+    pub(crate) fn read_header(&mut self) -> Result<(MtxHeader, (usize, usize), usize), Error> {
+        // The first line may be the banner, if there is none
+        // assume 'MatrixMarket matrix coordinate real general format'
+        let mut header = MtxHeader::default();
+        loop {
+            let line = match self.next_line()? {
+                Some(line) => line,
+                None => {
+                    return Err(Error::SizeLine {
+                        path: self.path.clone(),
+                        line: self.line_no,
+                    })
+                }
+            };
+            let trimmed = line.trim();
+
+            if self.line_no == 1 && trimmed.starts_with("%%") {
+                header = MtxHeader::parse(trimmed).ok_or_else(|| Error::Banner {
+                    path: self.path.clone(),
+                    line: self.line_no,
+                })?;
+            } else if trimmed.starts_with('%') || trimmed.is_empty() {
+                continue;
+            } else {
+                let size_err = |_| Error::SizeLine {
+                    path: self.path.clone(),
+                    line: self.line_no,
+                };
+                let mut tokens = Tokens::new(&line, self.line_no, &self.path);
+                let (m, _) = tokens.next().map_err(size_err)?;
+                let (n, _) = tokens.next().map_err(size_err)?;
+                let shape = (m, n);
+
+                if header.symmetry != MtxSymmetry::General && m != n {
+                    return Err(Error::SizeLine {
+                        path: self.path.clone(),
+                        line: self.line_no,
+                    });
+                }
+
+                let l = match header.format {
+                    MtxFormat::Coordinate => tokens.next().map_err(size_err)?.0,
+                    MtxFormat::Array => header.array_len(shape).ok_or(Error::SizeLine {
+                        path: self.path.clone(),
+                        line: self.line_no,
+                    })?,
+                };
+
+                return Ok((header, shape, l));
+            }
+        }
+    }
+
+    // Next line containing an entry. The file must not end before,
+    // found is the number of entries read so far.
+    fn next_entry_line(&mut self, expected: usize, found: usize) -> Result<String, Error> {
+        loop {
+            match self.next_line()? {
+                Some(line) if line.trim().is_empty() => continue,
+                Some(line) => return Ok(line),
+                None => {
+                    return Err(Error::EntryCount {
+                        path: self.path.clone(),
+                        expected,
+                        found,
+                    })
+                }
+            }
+        }
+    }
+
+    // Read the whole file into coordinate data (0-based indices).
+    // The MatrixMarket banner is honoured: symmetric, skew-symmetric and hermitian
+    // matrices are mirrored, pattern entries get the value 1.0 and dense
+    // array files are read with all their (possibly zero) entries.
+    pub(crate) fn read_entries<T: Scalar>(
+        &mut self,
+    ) -> Result<((usize, usize), Entries<T>), Error> {
+        let (header, shape, l) = self.read_header()?;
+        let (m, n) = shape;
+
+        if header.field == MtxField::Complex && !T::IS_COMPLEX {
+            return Err(Error::Unsupported {
+                path: self.path.clone(),
+                line: 1,
+                msg: String::from("complex values can't be read into a real matrix"),
+            });
+        }
+
+        // The size line can't be trusted, the vector grows beyond that if needed
+        let mut data: Vec<(usize, usize, T)> = Vec::with_capacity(l.min(MAX_PREALLOCATED_ENTRIES));
+
+        match header.format {
+            MtxFormat::Coordinate => {
+                for k in 0..l {
+                    let line = self.next_entry_line(l, k)?;
+                    let tokens = Tokens::new(&line, self.line_no, &self.path);
+                    data.push(tokens.coordinate_entry(shape, header.field)?);
+                }
+            }
+            MtxFormat::Array => {
+                // Values are stored column by column, for symmetric
+                // matrices only the lower triangle
+                for j in 0..n {
+                    let i0 = match header.symmetry {
+                        MtxSymmetry::General => 0,
+                        MtxSymmetry::Symmetric | MtxSymmetry::Hermitian => j,
+                        MtxSymmetry::SkewSymmetric => j + 1,
+                    };
+                    for i in i0..m {
+                        let line = self.next_entry_line(l, data.len())?;
+                        let v = Tokens::new(&line, self.line_no, &self.path)
+                            .next_value(header.field)?;
+                        data.push((i, j, v));
+                    }
+                }
+            }
+        }
+
+        // Anything except comments after the last entry means that
+        // the size line announced too few entries
+        let mut found = l;
+        while let Some(line) = self.next_line()? {
+            let trimmed = line.trim();
+            if !trimmed.is_empty() && !trimmed.starts_with('%') {
+                found += 1;
+            }
+        }
+        if found != l {
+            return Err(Error::EntryCount {
+                path: self.path.clone(),
+                expected: l,
+                found,
+            });
+        }
+
+        mirror_entries(header.symmetry, &mut data);
+
+        Ok((shape, data))
+    }
+}
+
+// Only one triangle is stored for symmetric matrices, mirror the rest.
+// For real values hermitian is the same as symmetric.
+pub(crate) fn mirror_entries<T: Scalar>(symmetry: MtxSymmetry, data: &mut Entries<T>) {
+    let mirror: Option<fn(T) -> T> = match symmetry {
+        MtxSymmetry::General => None,
+        MtxSymmetry::Symmetric => Some(|x| x),
+        MtxSymmetry::SkewSymmetric => Some(|x: T| -x),
+        MtxSymmetry::Hermitian => Some(|x: T| x.conj()),
+    };
+    if let Some(mirror) = mirror {
+        let mirrored: Vec<(usize, usize, T)> = data
+            .iter()
+            .filter(|(i, j, _)| i != j)
+            .map(|&(i, j, x)| (j, i, mirror(x)))
+            .collect();
+        data.extend(mirrored);
+    }
+}
+
+// Read only the shape of the matrix stored in a mtx-file
+pub fn read_shape(fname: &Path) -> Result<(usize, usize), Error> {
+    let (_, shape, _) = MtxReader::open(fname)?.read_header()?;
+    Ok(shape)
+}
+
+// Chunks per thread in read_entries_par, more than one evens out the work
+const CHUNKS_PER_THREAD: usize = 4;
+
+// Like MtxReader::read_entries, but the file is memory-mapped and the entries are parsed
+// in parallel: the body after the size line is split into chunks at line breaks and
+// every chunk is parsed on its own, straight from the mapping without a String per line.
+// The entries keep the order of the file. Comment lines are allowed anywhere in the body.
+// Compressed and array files can't be split and are read by read_entries.
+pub(crate) fn read_entries_par<T: Scalar>(
+    fname: &Path,
+) -> Result<((usize, usize), Entries<T>), Error> {
+    let file = File::open(fname).map_err(|e| Error::io(fname, e))?;
+    // Safety: the mapping is only read, the file must not be changed while reading it
+    let mmap = unsafe { Mmap::map(&file) }.map_err(|e| Error::io(fname, e))?;
+    let bytes = &mmap[..];
+    if Compression::detect(bytes) != Compression::None {
+        return MtxReader::open(fname)?.read_entries();
+    }
+
+    let body_start = body_offset(bytes);
+    let mut reader = MtxReader::new(&bytes[..body_start], fname);
+    let (header, shape, l) = reader.read_header()?;
+    if header.format != MtxFormat::Coordinate
+        || (header.field == MtxField::Complex && !T::IS_COMPLEX)
+    {
+        return MtxReader::open(fname)?.read_entries();
+    }
+    let body = &bytes[body_start..];
+
+    let chunks = split_lines(body, rayon::current_num_threads() * CHUNKS_PER_THREAD);
+    // Line number of the first line of each chunk, for the error messages
+    let line_counts: Vec<usize> = chunks
+        .par_iter()
+        .map(|chunk| chunk.iter().filter(|&&b| b == b'\n').count())
+        .collect();
+    let first_lines: Vec<usize> = line_counts
+        .iter()
+        .scan(reader.line_no + 1, |line_no, count| {
+            let first = *line_no;
+            *line_no += count;
+            Some(first)
+        })
+        .collect();
+
+    let parts: Vec<Entries<T>> = chunks
+        .par_iter()
+        .zip(first_lines)
+        .map(|(chunk, first_line)| {
+            let mut data = Vec::new();
+            for (k, line) in chunk.split(|&b| b == b'\n').enumerate() {
+                let line_no = first_line + k;
+                let line = std::str::from_utf8(line).map_err(|_| Error::Value {
+                    path: fname.to_path_buf(),
+                    line: line_no,
+                    column: 1,
+                    token: String::from_utf8_lossy(line).into_owned(),
+                })?;
+                let trimmed = line.trim();
+                if trimmed.is_empty() || trimmed.starts_with('%') {
+                    continue;
+                }
+                let tokens = Tokens::new(line, line_no, fname);
+                data.push(tokens.coordinate_entry(shape, header.field)?);
+            }
+            Ok(data)
+        })
+        .collect::<Result<_, Error>>()?;
+
+    let found = parts.iter().map(|part| part.len()).sum();
+    if found != l {
+        return Err(Error::EntryCount {
+            path: fname.to_path_buf(),
+            expected: l,
+            found,
+        });
+    }
+
+    // found entries were actually read, unlike l this is no number from the file
+    let mut data = Vec::with_capacity(found);
+    for part in parts {
+        data.extend(part);
+    }
+    mirror_entries(header.symmetry, &mut data);
+
+    Ok((shape, data))
+}
+
+// Offset of the first byte after the size line (banner, comments and empty lines come before it).
+// For files without size line this is the end, read_header reports the error.
+fn body_offset(bytes: &[u8]) -> usize {
+    let mut pos = 0;
+    for line in bytes.split_inclusive(|&b| b == b'\n') {
+        pos += line.len();
+        let trimmed = line.trim_ascii();
+        if !trimmed.is_empty() && !trimmed.starts_with(b"%") {
+            break;
+        }
+    }
+    pos
+}
+
+// Splits into about count chunks, every chunk but the last ends with a line break
+fn split_lines(bytes: &[u8], count: usize) -> Vec<&[u8]> {
+    let target = bytes.len().div_ceil(count.max(1)).max(1);
+    let mut chunks = vec![];
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let end = match rest[target.min(rest.len())..]
+            .iter()
+            .position(|&b| b == b'\n')
+        {
+            Some(pos) => target + pos + 1,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    chunks
+}
+
+// Options for the write_mtx functions of COO, CSR and Dense
+#[derive(Debug, Clone)]
+pub struct MtxWriteOptions {
+    // Coordinate (sparse) or array (dense) format
+    pub format: MtxFormat,
+    // Number of digits after the decimal point (scientific notation).
+    // None writes the shortest representation that reads back exactly.
+    pub precision: Option<usize>,
+    // Only write the lower triangle with a 'symmetric' banner.
+    // Writing fails if the matrix is not symmetric.
+    pub symmetric: bool,
+    // Comment lines written after the banner, without leading '%'
+    pub comments: Vec<String>,
+}
+
+impl Default for MtxWriteOptions {
+    fn default() -> Self {
+        MtxWriteOptions {
+            format: MtxFormat::Coordinate,
+            precision: None,
+            symmetric: false,
+            comments: vec![],
+        }
+    }
+}
+
+impl MtxWriteOptions {
+    fn header<T: Scalar>(&self) -> MtxHeader {
+        MtxHeader {
+            format: self.format,
+            field: if T::IS_COMPLEX {
+                MtxField::Complex
+            } else {
+                MtxField::Real
+            },
+            symmetry: if self.symmetric {
+                MtxSymmetry::Symmetric
+            } else {
+                MtxSymmetry::General
+            },
+        }
+    }
+
+    fn fmt_real(&self, x: f64) -> String {
+        match self.precision {
+            Some(p) => format!("{:.*e}", p, x),
+            None => format!("{}", x),
+        }
+    }
+
+    // Complex values are written as "re im"
+    fn fmt_value<T: Scalar>(&self, x: T) -> String {
+        let (re, im) = x.to_parts();
+        if T::IS_COMPLEX {
+            format!("{} {}", self.fmt_real(re), self.fmt_real(im))
+        } else {
+            self.fmt_real(re)
+        }
+    }
+}
+
+impl std::fmt::Display for MtxHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let format = match self.format {
+            MtxFormat::Coordinate => "coordinate",
+            MtxFormat::Array => "array",
+        };
+        let field = match self.field {
+            MtxField::Real => "real",
+            MtxField::Integer => "integer",
+            MtxField::Complex => "complex",
+            MtxField::Pattern => "pattern",
+        };
+        let symmetry = match self.symmetry {
+            MtxSymmetry::General => "general",
+            MtxSymmetry::Symmetric => "symmetric",
+            MtxSymmetry::SkewSymmetric => "skew-symmetric",
+            MtxSymmetry::Hermitian => "hermitian",
+        };
+        write!(f, "{} matrix {} {} {}", BANNER, format, field, symmetry)
+    }
+}
+
+fn write_head<T: Scalar>(f: &mut impl Write, opts: &MtxWriteOptions) -> io::Result<()> {
+    writeln!(f, "{}", opts.header::<T>())?;
+    for c in &opts.comments {
+        writeln!(f, "%{}", c)?;
+    }
+    Ok(())
+}
+
+// Write coordinate entries (0-based indices) in coordinate format
+pub(crate) fn write_coordinate<T: Scalar>(
+    fname: &Path,
+    shape: (usize, usize),
+    data: &[(usize, usize, T)],
+    opts: &MtxWriteOptions,
+) -> Result<(), Error> {
+    let data: Cow<[(usize, usize, T)]> = if opts.symmetric {
+        // A is symmetric if the sorted entries of A and A^T coincide
+        let mut entries = data.to_vec();
+        entries.sort_by_key(|&(i, j, _)| (i, j));
+        let mut entries_t: Vec<(usize, usize, T)> =
+            data.iter().map(|&(i, j, x)| (j, i, x)).collect();
+        entries_t.sort_by_key(|&(i, j, _)| (i, j));
+
+        if shape.0 != shape.1 || entries != entries_t {
+            return Err(Error::NotSymmetric {
+                path: fname.to_path_buf(),
+            });
+        }
+
+        // Only keep the lower triangle
+        entries.retain(|&(i, j, _)| i >= j);
+        Cow::Owned(entries)
+    } else {
+        Cow::Borrowed(data)
+    };
+
+    let write = || -> io::Result<()> {
+        let mut f = BufWriter::new(File::create(fname)?);
+        write_head::<T>(&mut f, opts)?;
+        writeln!(f, "{} {} {}", shape.0, shape.1, data.len())?;
+        for &(i, j, x) in data.iter() {
+            writeln!(f, "{} {} {}", i + 1, j + 1, opts.fmt_value(x))?;
+        }
+        f.flush()
+    };
+    write().map_err(|e| Error::io(fname, e))
+}
+
+// Write dense matrix in array format, i.e. column by column
+pub(crate) fn write_array<T: Scalar>(
+    fname: &Path,
+    mat: &Dense<T>,
+    opts: &MtxWriteOptions,
+) -> Result<(), Error> {
+    let (m, n) = mat.shape;
+
+    if opts.symmetric {
+        let symmetric = m == n && (0..m).all(|i| (0..i).all(|j| mat.get(i, j) == mat.get(j, i)));
+        if !symmetric {
+            return Err(Error::NotSymmetric {
+                path: fname.to_path_buf(),
+            });
+        }
+    }
+
+    let write = || -> io::Result<()> {
+        let mut f = BufWriter::new(File::create(fname)?);
+        write_head::<T>(&mut f, opts)?;
+        writeln!(f, "{} {}", m, n)?;
+        for j in 0..n {
+            // Lower triangle only for symmetric matrices
+            let i0 = if opts.symmetric { j } else { 0 };
+            for i in i0..m {
+                writeln!(f, "{}", opts.fmt_value(mat.get(i, j)))?;
+            }
+        }
+        f.flush()
+    };
+    write().map_err(|e| Error::io(fname, e))
+}
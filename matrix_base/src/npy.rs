@@ -0,0 +1,524 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{Error, Scalar, COO};
+
+// NumPy .npy files and the .npz archives of scipy.sparse.save_npz, see
+// https://numpy.org/doc/stable/reference/generated/numpy.lib.format.html
+// A .npy file starts with "\x93NUMPY", the version and the length of the header.
+// The header is a python dict like {'descr': '<f8', 'fortran_order': False, 'shape': (3, 4), },
+// padded with spaces to a multiple of 64 bytes, then the raw data follows.
+// save_npz writes a zip archive of .npy files: format (b'csr', b'csc' or b'coo'), shape and data,
+// indices and indptr for CSR/CSC, row and col for COO.
+const MAGIC: &[u8] = b"\x93NUMPY";
+
+// Array of a .npy file, the data is kept as raw bytes until the type is known
+pub(crate) struct NpyArray {
+    pub descr: String,
+    pub fortran_order: bool,
+    pub shape: Vec<usize>,
+    data: Vec<u8>,
+}
+
+// Sparse matrix of a .npz file
+pub(crate) enum SparseNpz<T> {
+    // CSR, or CSC with rows and columns swapped (indptr runs over the columns)
+    Compressed {
+        csc: bool,
+        shape: (usize, usize),
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<T>,
+    },
+    Coo {
+        shape: (usize, usize),
+        row: Vec<usize>,
+        col: Vec<usize>,
+        data: Vec<T>,
+    },
+}
+
+impl<T: Scalar> SparseNpz<T> {
+    // Entries in the order of the file, the indices are checked against the shape
+    pub(crate) fn into_coo(self, fname: &Path) -> Result<COO<T>, Error> {
+        let coo = match self {
+            SparseNpz::Coo {
+                shape,
+                row,
+                col,
+                data,
+            } => {
+                let data = row
+                    .into_iter()
+                    .zip(col)
+                    .zip(data)
+                    .map(|((i, j), x)| (i, j, x))
+                    .collect();
+                COO { data, shape }
+            }
+            SparseNpz::Compressed {
+                csc,
+                shape,
+                indptr,
+                indices,
+                data,
+            } => {
+                let mut entries = Vec::with_capacity(data.len());
+                for k in 0..indptr.len() - 1 {
+                    for pos in indptr[k]..indptr[k + 1] {
+                        let (i, j) = if csc {
+                            (indices[pos], k)
+                        } else {
+                            (k, indices[pos])
+                        };
+                        entries.push((i, j, data[pos]));
+                    }
+                }
+                COO {
+                    data: entries,
+                    shape,
+                }
+            }
+        };
+        coo.validate().map_err(|e| invalid(fname, e.to_string()))?;
+        Ok(coo)
+    }
+}
+
+fn invalid(fname: &Path, msg: String) -> Error {
+    Error::Npy {
+        path: fname.to_path_buf(),
+        msg,
+    }
+}
+
+// Text after 'key': in the header dict
+fn dict_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    let start = header
+        .find(&format!("'{}':", key))
+        .or_else(|| header.find(&format!("\"{}\":", key)))?;
+    Some(header[start + key.len() + 3..].trim_start())
+}
+
+fn parse_header(header: &str) -> Option<(String, bool, Vec<usize>)> {
+    let descr = dict_value(header, "descr")?;
+    // The header is decoded lossy, so anything else than a quote is rejected
+    // before slicing behind it
+    let quote = descr.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let descr = descr[1..].split(quote).next()?.to_string();
+
+    let fortran_order = dict_value(header, "fortran_order")?.starts_with("True");
+
+    let shape = dict_value(header, "shape")?.strip_prefix('(')?;
+    let shape = shape[..shape.find(')')?]
+        .split(',')
+        .map(|dim| dim.trim())
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.parse().ok())
+        .collect::<Option<Vec<usize>>>()?;
+
+    Some((descr, fortran_order, shape))
+}
+
+// Errors are messages, the caller knows the file (and the member of the archive)
+pub(crate) fn read_array<R: Read>(r: &mut R) -> Result<NpyArray, String> {
+    let io_err = |e: std::io::Error| e.to_string();
+
+    let mut start = [0; 8];
+    r.read_exact(&mut start).map_err(io_err)?;
+    if &start[..6] != MAGIC {
+        return Err("not a .npy file".to_string());
+    }
+    let header_len = match start[6] {
+        1 => {
+            let mut len = [0; 2];
+            r.read_exact(&mut len).map_err(io_err)?;
+            u16::from_le_bytes(len) as usize
+        }
+        2 | 3 => {
+            let mut len = [0; 4];
+            r.read_exact(&mut len).map_err(io_err)?;
+            u32::from_le_bytes(len) as usize
+        }
+        version => return Err(format!("unsupported version {}", version)),
+    };
+    let mut header = vec![0; header_len];
+    r.read_exact(&mut header).map_err(io_err)?;
+    let header = String::from_utf8_lossy(&header);
+    let (descr, fortran_order, shape) =
+        parse_header(&header).ok_or_else(|| format!("invalid header {}", header.trim()))?;
+
+    let item_size: usize = descr
+        .get(2..)
+        .and_then(|size| size.parse().ok())
+        .ok_or_else(|| format!("unsupported dtype '{}'", descr))?;
+    // Unicode strings have 4 bytes per character
+    let item_size = if descr.get(1..2) == Some("U") {
+        4 * item_size
+    } else {
+        item_size
+    };
+    let len = shape
+        .iter()
+        .try_fold(item_size, |len, dim| len.checked_mul(*dim))
+        .ok_or_else(|| "shape too large".to_string())?;
+
+    let mut data = Vec::new();
+    r.read_to_end(&mut data).map_err(io_err)?;
+    if data.len() != len {
+        return Err(format!(
+            "expected {} bytes of data, found {}",
+            len,
+            data.len()
+        ));
+    }
+
+    Ok(NpyArray {
+        descr,
+        fortran_order,
+        shape,
+        data,
+    })
+}
+
+impl NpyArray {
+    fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    // Values as T, integer and real arrays can be read into every scalar type,
+    // complex ones only into complex types
+    pub(crate) fn scalars<T: Scalar>(&self) -> Result<Vec<T>, String> {
+        let real = |f: fn(&[u8]) -> f64, size: usize| -> Vec<T> {
+            self.data
+                .chunks_exact(size)
+                .map(|b| T::from_parts(f(b), 0.))
+                .collect()
+        };
+        let complex = |f: fn(&[u8]) -> f64, size: usize| -> Vec<T> {
+            self.data
+                .chunks_exact(2 * size)
+                .map(|b| T::from_parts(f(&b[..size]), f(&b[size..])))
+                .collect()
+        };
+        let f32_at = |b: &[u8]| f32::from_le_bytes(b.try_into().unwrap()) as f64;
+        let f64_at = |b: &[u8]| f64::from_le_bytes(b.try_into().unwrap());
+
+        match self.descr.as_str() {
+            "<f4" => Ok(real(f32_at, 4)),
+            "<f8" => Ok(real(f64_at, 8)),
+            "<i4" => Ok(real(
+                |b| i32::from_le_bytes(b.try_into().unwrap()) as f64,
+                4,
+            )),
+            "<i8" => Ok(real(
+                |b| i64::from_le_bytes(b.try_into().unwrap()) as f64,
+                8,
+            )),
+            "<c8" | "<c16" if !T::IS_COMPLEX => {
+                Err("complex values can't be read into a real matrix".to_string())
+            }
+            "<c8" => Ok(complex(f32_at, 4)),
+            "<c16" => Ok(complex(f64_at, 8)),
+            descr => Err(format!("unsupported dtype '{}' for values", descr)),
+        }
+    }
+
+    pub(crate) fn indices(&self) -> Result<Vec<usize>, String> {
+        let values: Vec<i128> = match self.descr.as_str() {
+            "<i4" => self
+                .data
+                .chunks_exact(4)
+                .map(|b| i32::from_le_bytes(b.try_into().unwrap()) as i128)
+                .collect(),
+            "<i8" => self
+                .data
+                .chunks_exact(8)
+                .map(|b| i64::from_le_bytes(b.try_into().unwrap()) as i128)
+                .collect(),
+            "<u4" => self
+                .data
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as i128)
+                .collect(),
+            "<u8" => self
+                .data
+                .chunks_exact(8)
+                .map(|b| u64::from_le_bytes(b.try_into().unwrap()) as i128)
+                .collect(),
+            descr => return Err(format!("unsupported dtype '{}' for indices", descr)),
+        };
+        values
+            .into_iter()
+            .map(|i| usize::try_from(i).map_err(|_| format!("invalid index {}", i)))
+            .collect()
+    }
+
+    // Byte string (b'csr') or unicode string ('csr')
+    fn text(&self) -> Result<String, String> {
+        match &self.descr[..2] {
+            "|S" => Ok(String::from_utf8_lossy(&self.data)
+                .trim_end_matches('\0')
+                .to_string()),
+            "<U" => Ok(self
+                .data
+                .chunks_exact(4)
+                .filter_map(|b| char::from_u32(u32::from_le_bytes(b.try_into().unwrap())))
+                .filter(|c| *c != '\0')
+                .collect()),
+            _ => Err(format!("unsupported dtype '{}' for text", self.descr)),
+        }
+    }
+}
+
+fn scalar_descr<T: Scalar>() -> &'static str {
+    match (T::IS_COMPLEX, std::mem::size_of::<T>()) {
+        (false, 4) => "<f4",
+        (false, _) => "<f8",
+        (true, 8) => "<c8",
+        (true, _) => "<c16",
+    }
+}
+
+pub(crate) fn write_array<W: Write>(
+    w: &mut W,
+    descr: &str,
+    shape: &[usize],
+    data: &[u8],
+) -> std::io::Result<()> {
+    let shape = match shape {
+        [len] => format!("({},)", len),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|dim| dim.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    // Magic, version and length take 10 bytes, the header ends with a newline
+    let padding = (64 - (10 + header.len() + 1) % 64) % 64;
+    header.push_str(&" ".repeat(padding));
+    header.push('\n');
+
+    w.write_all(MAGIC)?;
+    w.write_all(&[1, 0])?;
+    w.write_all(&(header.len() as u16).to_le_bytes())?;
+    w.write_all(header.as_bytes())?;
+    w.write_all(data)
+}
+
+fn write_scalars<T: Scalar, W: Write>(
+    w: &mut W,
+    shape: &[usize],
+    values: &[T],
+) -> std::io::Result<()> {
+    let descr = scalar_descr::<T>();
+    let mut data = Vec::with_capacity(std::mem::size_of_val(values));
+    for x in values {
+        let (re, im) = x.to_parts();
+        let parts = if T::IS_COMPLEX {
+            &[re, im][..]
+        } else {
+            &[re][..]
+        };
+        for part in parts {
+            if descr == "<f4" || descr == "<c8" {
+                data.extend_from_slice(&(*part as f32).to_le_bytes());
+            } else {
+                data.extend_from_slice(&part.to_le_bytes());
+            }
+        }
+    }
+    write_array(w, descr, shape, &data)
+}
+
+// int32 if all indices fit, like scipy does
+fn write_indices<W: Write>(w: &mut W, indices: &[usize], wide: bool) -> std::io::Result<()> {
+    let mut data = Vec::new();
+    if wide {
+        for i in indices {
+            data.extend_from_slice(&(*i as i64).to_le_bytes());
+        }
+        write_array(w, "<i8", &[indices.len()], &data)
+    } else {
+        for i in indices {
+            data.extend_from_slice(&(*i as i32).to_le_bytes());
+        }
+        write_array(w, "<i4", &[indices.len()], &data)
+    }
+}
+
+// Dense array, row-major (C order). Arrays in Fortran order are transposed.
+pub(crate) fn read_dense<T: Scalar>(fname: &Path) -> Result<((usize, usize), Vec<T>), Error> {
+    let f = File::open(fname).map_err(|e| Error::io(fname, e))?;
+    let array = read_array(&mut BufReader::new(f)).map_err(|msg| invalid(fname, msg))?;
+    let shape = match array.shape[..] {
+        [m, n] => (m, n),
+        _ => {
+            return Err(invalid(
+                fname,
+                format!(
+                    "expected a 2-dimensional array, found shape {:?}",
+                    array.shape
+                ),
+            ))
+        }
+    };
+    let values = array.scalars().map_err(|msg| invalid(fname, msg))?;
+    if !array.fortran_order {
+        return Ok((shape, values));
+    }
+
+    let (m, n) = shape;
+    let mut data = vec![T::zero(); m * n];
+    for j in 0..n {
+        for i in 0..m {
+            data[i * n + j] = values[j * m + i];
+        }
+    }
+    Ok((shape, data))
+}
+
+pub(crate) fn write_dense<T: Scalar>(
+    fname: &Path,
+    shape: (usize, usize),
+    data: &[T],
+) -> Result<(), Error> {
+    let f = File::create(fname).map_err(|e| Error::io(fname, e))?;
+    let mut w = BufWriter::new(f);
+    write_scalars(&mut w, &[shape.0, shape.1], data)
+        .and_then(|_| w.flush())
+        .map_err(|e| Error::io(fname, e))
+}
+
+pub(crate) fn read_npz<T: Scalar>(fname: &Path) -> Result<SparseNpz<T>, Error> {
+    let f = File::open(fname).map_err(|e| Error::io(fname, e))?;
+    let mut archive =
+        ZipArchive::new(BufReader::new(f)).map_err(|e| invalid(fname, e.to_string()))?;
+    let mut member = |name: &str| -> Result<NpyArray, Error> {
+        let mut file = archive
+            .by_name(&format!("{}.npy", name))
+            .map_err(|e| invalid(fname, format!("{}.npy: {}", name, e)))?;
+        read_array(&mut file).map_err(|msg| invalid(fname, format!("{}.npy: {}", name, msg)))
+    };
+    let in_member =
+        |name: &'static str| move |msg: String| invalid(fname, format!("{}.npy: {}", name, msg));
+
+    let format = member("format")?.text().map_err(in_member("format"))?;
+    let shape = member("shape")?.indices().map_err(in_member("shape"))?;
+    let shape = match shape[..] {
+        [m, n] => (m, n),
+        _ => return Err(invalid(fname, format!("invalid shape {:?}", shape))),
+    };
+    let data = member("data")?;
+    let nnz = data.len();
+    let data = data.scalars().map_err(in_member("data"))?;
+
+    let sparse = match format.as_str() {
+        "csr" | "csc" => {
+            let csc = format == "csc";
+            let indptr = member("indptr")?.indices().map_err(in_member("indptr"))?;
+            let indices = member("indices")?.indices().map_err(in_member("indices"))?;
+            let major = if csc { shape.1 } else { shape.0 };
+            if indptr.len() != major + 1 || indices.len() != nnz || indptr.last() != Some(&nnz) {
+                return Err(invalid(
+                    fname,
+                    "lengths of indptr, indices and data don't match".to_string(),
+                ));
+            }
+            if indptr.windows(2).any(|w| w[1] < w[0]) {
+                return Err(invalid(fname, "indptr is decreasing".to_string()));
+            }
+            SparseNpz::Compressed {
+                csc,
+                shape,
+                indptr,
+                indices,
+                data,
+            }
+        }
+        "coo" => {
+            let row = member("row")?.indices().map_err(in_member("row"))?;
+            let col = member("col")?.indices().map_err(in_member("col"))?;
+            if row.len() != nnz || col.len() != nnz {
+                return Err(invalid(
+                    fname,
+                    "lengths of row, col and data don't match".to_string(),
+                ));
+            }
+            SparseNpz::Coo {
+                shape,
+                row,
+                col,
+                data,
+            }
+        }
+        format => {
+            return Err(invalid(
+                fname,
+                format!("unsupported sparse format '{}'", format),
+            ))
+        }
+    };
+    Ok(sparse)
+}
+
+// Writes a .npz file like scipy.sparse.save_npz(compressed=False).
+// members are the index arrays, e.g. [("indices", ..), ("indptr", ..)] for CSR.
+pub(crate) fn write_npz<T: Scalar>(
+    fname: &Path,
+    format: &str,
+    shape: (usize, usize),
+    members: &[(&str, &[usize])],
+    data: &[T],
+) -> Result<(), Error> {
+    let f = File::create(fname).map_err(|e| Error::io(fname, e))?;
+    let mut zip = ZipWriter::new(BufWriter::new(f));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let zip_err = |e: zip::result::ZipError| invalid(fname, e.to_string());
+
+    let max_index = members
+        .iter()
+        .flat_map(|(_, indices)| indices.iter().copied())
+        .chain([shape.0, shape.1])
+        .max()
+        .unwrap_or(0);
+    let wide = max_index > i32::MAX as usize;
+
+    let mut write_member = |name: &str, write: &dyn Fn(&mut Vec<u8>) -> std::io::Result<()>| {
+        let mut bytes = Vec::new();
+        write(&mut bytes).map_err(|e| Error::io(fname, e))?;
+        // Members over 4 GiB need zip64
+        let options = options.large_file(bytes.len() >= u32::MAX as usize);
+        zip.start_file(format!("{}.npy", name), options)
+            .map_err(zip_err)?;
+        zip.write_all(&bytes).map_err(|e| Error::io(fname, e))
+    };
+
+    for (name, indices) in members {
+        write_member(name, &|w| write_indices(w, indices, wide))?;
+    }
+    write_member("format", &|w| {
+        write_array(w, &format!("|S{}", format.len()), &[], format.as_bytes())
+    })?;
+    write_member("shape", &|w| {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(shape.0 as i64).to_le_bytes());
+        bytes.extend_from_slice(&(shape.1 as i64).to_le_bytes());
+        write_array(w, "<i8", &[2], &bytes)
+    })?;
+    write_member("data", &|w| write_scalars(w, &[data.len()], data))?;
+
+    zip.finish().map_err(zip_err)?;
+    Ok(())
+}
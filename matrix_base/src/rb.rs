@@ -0,0 +1,306 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::path::{Path, PathBuf};
+
+use crate::mtx::{mirror_entries, Entries, MAX_PREALLOCATED_ENTRIES};
+use crate::{Error, MtxSymmetry, Scalar};
+
+// Rutherford-Boeing format and the older Harwell-Boeing format, see
+// "The Rutherford-Boeing Sparse Matrix Collection", Duff, Grimes, Lewis
+// https://math.nist.gov/MatrixMarket/formats.html#hb
+// The matrix is stored column by column (like CSC) with 1-based indices:
+//   line 1: title and key
+//   line 2: number of lines in total and of the pointer, index and value sections,
+//           Harwell-Boeing has a fifth number for the right-hand side section
+//   line 3: matrix type (e.g. "rua"), rows, columns, entries
+//   line 4: Fortran formats of pointers, indices and values, e.g. (13I6) (16I5) (3E26.18)
+//   line 5: only Harwell-Boeing with right-hand sides, their format
+// The column pointers, row indices and values follow, every line holds a fixed number of
+// fixed-width fields. The type letters are value type ('r'eal, 'i'nteger, 'p'attern, 'c'omplex),
+// structure ('u'nsymmetric, 'r'ectangular, 's'ymmetric, 'h'ermitian, skew-symmetric 'z')
+// and 'a'ssembled or 'e'lemental. Only assembled matrices are supported.
+// Symmetric matrices store the lower triangle, the rest is mirrored like for mtx-files.
+
+// Fixed-width Fortran format like (13I6), (1P,5D16.8) or (4E20.12)
+#[derive(Debug, Clone, Copy)]
+struct FortranFormat {
+    per_line: usize,
+    width: usize,
+}
+
+impl FortranFormat {
+    fn parse(desc: &str) -> Option<Self> {
+        let desc = desc.trim().to_ascii_uppercase();
+        let desc = desc.strip_prefix('(')?.strip_suffix(')')?;
+        // Scale factor like 1P, it doesn't change how numbers with exponent are read
+        let desc = match desc.find('P') {
+            Some(pos) => desc[pos + 1..].trim_start_matches(','),
+            None => desc,
+        }
+        .trim();
+
+        let type_pos = desc.find(|c: char| c.is_ascii_alphabetic())?;
+        if !matches!(&desc[type_pos..type_pos + 1], "I" | "E" | "D" | "F" | "G") {
+            return None;
+        }
+        let per_line = match type_pos {
+            0 => 1,
+            _ => desc[..type_pos].parse().ok()?,
+        };
+        let width = desc[type_pos + 1..].split('.').next()?.parse().ok()?;
+        (per_line > 0 && width > 0).then_some(FortranFormat { per_line, width })
+    }
+}
+
+// Fortran writes exponents also as D (1.5D+03) or without letter (1.5+003)
+fn parse_float(tok: &str) -> Option<f64> {
+    let tok = tok.replace(['D', 'd'], "E");
+    if let Ok(x) = tok.parse() {
+        return Some(x);
+    }
+    let sign = tok.rfind(['+', '-']).filter(|&pos| pos > 0)?;
+    format!("{}E{}", &tok[..sign], &tok[sign..]).parse().ok()
+}
+
+struct RbReader {
+    lines: Lines<BufReader<File>>,
+    path: PathBuf,
+    line_no: usize,
+}
+
+struct RbHeader {
+    value_type: char,
+    symmetry: MtxSymmetry,
+    shape: (usize, usize),
+    nnz: usize,
+    formats: [Option<FortranFormat>; 3],
+}
+
+impl RbReader {
+    fn open(fname: &Path) -> Result<Self, Error> {
+        let f = File::open(fname).map_err(|e| Error::io(fname, e))?;
+        Ok(RbReader {
+            lines: BufReader::new(f).lines(),
+            path: fname.to_path_buf(),
+            line_no: 0,
+        })
+    }
+
+    fn invalid(&self, msg: String) -> Error {
+        Error::Rb {
+            path: self.path.clone(),
+            line: self.line_no,
+            msg,
+        }
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>, Error> {
+        match self.lines.next() {
+            Some(line) => {
+                self.line_no += 1;
+                line.map(Some).map_err(|e| Error::io(&self.path, e))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn header_line(&mut self) -> Result<String, Error> {
+        match self.next_line()? {
+            Some(line) => Ok(line),
+            None => Err(self.invalid("file ends in the header".to_string())),
+        }
+    }
+
+    fn read_header(&mut self) -> Result<RbHeader, Error> {
+        // Title and key
+        self.header_line()?;
+
+        let counts = self.header_line()?;
+        let counts: Vec<usize> = counts
+            .split_whitespace()
+            .map(|count| count.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| self.invalid(format!("invalid line counts '{}'", counts.trim())))?;
+        if counts.len() < 4 {
+            return Err(self.invalid("expected at least 4 line counts".to_string()));
+        }
+        let rhs_lines = counts.get(4).copied().unwrap_or(0);
+
+        let line = self.header_line()?.to_ascii_lowercase();
+        let mxtype: Vec<char> = line.chars().take(3).collect();
+        let sizes: Vec<usize> = line
+            .get(3..)
+            .unwrap_or_default()
+            .split_whitespace()
+            .take(3)
+            .map(|size| size.parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| self.invalid(format!("invalid sizes '{}'", line.trim())))?;
+        let (value_type, structure, assembled) = match mxtype[..] {
+            [value_type, structure, assembled] => (value_type, structure, assembled),
+            _ => return Err(self.invalid(format!("invalid matrix type '{}'", line.trim()))),
+        };
+        if sizes.len() != 3 {
+            return Err(self.invalid(format!("invalid sizes '{}'", line.trim())));
+        }
+        if !matches!(value_type, 'r' | 'i' | 'p' | 'c') {
+            return Err(self.invalid(format!("unknown value type '{}'", value_type)));
+        }
+        let symmetry = match structure {
+            'u' | 'r' => MtxSymmetry::General,
+            's' => MtxSymmetry::Symmetric,
+            'h' => MtxSymmetry::Hermitian,
+            'z' => MtxSymmetry::SkewSymmetric,
+            _ => return Err(self.invalid(format!("unknown structure '{}'", structure))),
+        };
+        if assembled != 'a' {
+            return Err(self.invalid("only assembled matrices are supported".to_string()));
+        }
+        let shape = (sizes[0], sizes[1]);
+        // n + 1 column pointers and 2 * nnz complex values are read later
+        if sizes[1].checked_add(1).is_none() || sizes[2].checked_mul(2).is_none() {
+            return Err(self.invalid(format!("invalid sizes '{}'", line.trim())));
+        }
+        if symmetry != MtxSymmetry::General && shape.0 != shape.1 {
+            return Err(self.invalid("symmetric matrix is not square".to_string()));
+        }
+
+        // Fixed columns: 16 characters for pointers and indices, 20 for the values
+        let line = self.header_line()?;
+        let column = |range: std::ops::Range<usize>| {
+            line.get(range.start..range.end.min(line.len()))
+                .unwrap_or_default()
+                .to_string()
+        };
+        let descs = [column(0..16), column(16..32), column(32..52)];
+        let mut formats = [None; 3];
+        for (format, desc) in formats.iter_mut().zip(&descs) {
+            *format = FortranFormat::parse(desc);
+        }
+        let values_needed = value_type != 'p';
+        if formats[0].is_none() || formats[1].is_none() || (values_needed && formats[2].is_none()) {
+            return Err(self.invalid(format!("unsupported Fortran formats '{}'", line.trim())));
+        }
+
+        if rhs_lines > 0 {
+            self.header_line()?;
+        }
+
+        Ok(RbHeader {
+            value_type,
+            symmetry,
+            shape,
+            nnz: sizes[2],
+            formats,
+        })
+    }
+
+    // Reads count fixed-width fields, parse returns None for invalid ones
+    fn read_fields<V>(
+        &mut self,
+        format: FortranFormat,
+        count: usize,
+        parse: impl Fn(&str) -> Option<V>,
+    ) -> Result<Vec<V>, Error> {
+        // count comes from the header, the vector grows beyond that if needed
+        let mut fields = Vec::with_capacity(count.min(MAX_PREALLOCATED_ENTRIES));
+        while fields.len() < count {
+            let line = match self.next_line()? {
+                Some(line) => line,
+                None => {
+                    return Err(Error::EntryCount {
+                        path: self.path.clone(),
+                        expected: count,
+                        found: fields.len(),
+                    })
+                }
+            };
+            if !line.is_ascii() {
+                return Err(self.invalid("non-ASCII characters".to_string()));
+            }
+            for k in 0..format.per_line {
+                let start = k * format.width;
+                if start >= line.len() || fields.len() == count {
+                    break;
+                }
+                let tok = line[start..(start + format.width).min(line.len())].trim();
+                if tok.is_empty() {
+                    break;
+                }
+                let value = parse(tok).ok_or_else(|| Error::Value {
+                    path: self.path.clone(),
+                    line: self.line_no,
+                    column: start + 1,
+                    token: tok.to_string(),
+                })?;
+                fields.push(value);
+            }
+        }
+        Ok(fields)
+    }
+}
+
+// Extensions of Rutherford-Boeing and Harwell-Boeing files: .rb, .hb
+// or the matrix type of assembled matrices, e.g. .rua, .rsa, .psa
+pub fn is_rb_path(fname: &Path) -> bool {
+    let ext = match fname.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) => ext.to_ascii_lowercase(),
+        None => return false,
+    };
+    match ext.as_bytes() {
+        b"rb" | b"hb" => true,
+        [value_type, structure, b'a'] => {
+            b"ripc".contains(value_type) && b"urshz".contains(structure)
+        }
+        _ => false,
+    }
+}
+
+// Read only the shape of the matrix
+pub fn read_shape(fname: &Path) -> Result<(usize, usize), Error> {
+    Ok(RbReader::open(fname)?.read_header()?.shape)
+}
+
+// Coordinate data (0-based indices) in column-major order, symmetric matrices are mirrored
+pub(crate) fn read_entries<T: Scalar>(fname: &Path) -> Result<((usize, usize), Entries<T>), Error> {
+    let mut reader = RbReader::open(fname)?;
+    let header = reader.read_header()?;
+    let (m, n) = header.shape;
+    let nnz = header.nnz;
+    let [ptr_format, ind_format, val_format] = header.formats;
+
+    if header.value_type == 'c' && !T::IS_COMPLEX {
+        return Err(reader.invalid("complex values can't be read into a real matrix".to_string()));
+    }
+
+    let in_range = |max: usize| move |tok: &str| tok.parse().ok().filter(|i| (1..=max).contains(i));
+    let col_ptr: Vec<usize> = reader.read_fields(ptr_format.unwrap(), n + 1, in_range(nnz + 1))?;
+    if col_ptr[0] != 1 || col_ptr[n] != nnz + 1 || col_ptr.windows(2).any(|w| w[1] < w[0]) {
+        return Err(reader.invalid("invalid column pointers".to_string()));
+    }
+    let row_ind: Vec<usize> = reader.read_fields(ind_format.unwrap(), nnz, in_range(m))?;
+
+    let values: Vec<T> = match header.value_type {
+        'p' => vec![T::one(); nnz],
+        'c' => reader
+            .read_fields(val_format.unwrap(), 2 * nnz, parse_float)?
+            .chunks_exact(2)
+            .map(|parts| T::from_parts(parts[0], parts[1]))
+            .collect(),
+        _ => reader
+            .read_fields(val_format.unwrap(), nnz, parse_float)?
+            .into_iter()
+            .map(T::from_f64)
+            .collect(),
+    };
+
+    let mut data = Vec::with_capacity(row_ind.len());
+    for j in 0..n {
+        for pos in col_ptr[j] - 1..col_ptr[j + 1] - 1 {
+            data.push((row_ind[pos] - 1, j, values[pos]));
+        }
+    }
+    mirror_entries(header.symmetry, &mut data);
+
+    Ok(((m, n), data))
+}
@@ -0,0 +1,172 @@
+use std::fmt::{Debug, Display};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+pub use num_complex::{Complex, Complex32, Complex64};
+
+// Scalar type of the matrix entries.
+// Implemented for f32, f64 and the corresponding complex types,
+// so single and double precision can be benchmarked against each other.
+pub trait Scalar:
+    Copy
+    + Debug
+    + Display
+    + PartialEq
+    + Send
+    + Sync
+    + 'static
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + SubAssign
+    + MulAssign
+    + Sum
+{
+    // Real type with the same precision, e.g. f64 for Complex64
+    type Real: RealScalar;
+
+    // True for complex types. Complex values have two components in mtx-files.
+    const IS_COMPLEX: bool;
+
+    fn zero() -> Self;
+    fn one() -> Self;
+
+    // Build a value from real and imaginary part.
+    // For real types the imaginary part is ignored.
+    fn from_parts(re: f64, im: f64) -> Self;
+    // Real and imaginary part, the latter is 0 for real types
+    fn to_parts(self) -> (f64, f64);
+
+    fn from_real(x: Self::Real) -> Self;
+    // Absolute value, modulus for complex numbers
+    fn abs(self) -> Self::Real;
+    // Complex conjugate, identity for real numbers
+    fn conj(self) -> Self;
+    // Number of representable values between self and other (in the precision of Self),
+    // the larger one of real and imaginary part. u64::MAX if one of them is NaN.
+    fn ulp_distance(self, other: Self) -> u64;
+
+    fn from_f64(x: f64) -> Self {
+        Self::from_parts(x, 0.)
+    }
+}
+
+// Real scalar types, i.e. f32 and f64
+pub trait RealScalar: Scalar<Real = Self> + PartialOrd {
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn is_nan(self) -> bool;
+}
+
+// Largest of the values, 0 if there are none (e.g. for norms of empty matrices).
+// NaN if one of the values is NaN, like a sum would be.
+pub(crate) fn max_or_zero<R: RealScalar>(values: impl IntoIterator<Item = R>) -> R {
+    values.into_iter().fold(
+        R::zero(),
+        |max, x| {
+            if x > max || x.is_nan() {
+                x
+            } else {
+                max
+            }
+        },
+    )
+}
+
+macro_rules! impl_real_scalar {
+    ($t:ty) => {
+        impl Scalar for $t {
+            type Real = $t;
+            const IS_COMPLEX: bool = false;
+
+            fn zero() -> Self {
+                0.
+            }
+            fn one() -> Self {
+                1.
+            }
+            fn from_parts(re: f64, _im: f64) -> Self {
+                re as $t
+            }
+            fn to_parts(self) -> (f64, f64) {
+                (self as f64, 0.)
+            }
+            fn from_real(x: Self::Real) -> Self {
+                x
+            }
+            fn abs(self) -> Self::Real {
+                <$t>::abs(self)
+            }
+            fn conj(self) -> Self {
+                self
+            }
+            fn ulp_distance(self, other: Self) -> u64 {
+                if self.is_nan() || other.is_nan() {
+                    return u64::MAX;
+                }
+                // Bit patterns of positive floats are ordered like the floats themselves
+                let ordered = |x: $t| {
+                    let magnitude = x.abs().to_bits() as i128;
+                    if x.is_sign_negative() {
+                        -magnitude
+                    } else {
+                        magnitude
+                    }
+                };
+                (ordered(self) - ordered(other))
+                    .unsigned_abs()
+                    .min(u64::MAX as u128) as u64
+            }
+        }
+
+        impl RealScalar for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+            fn is_nan(self) -> bool {
+                <$t>::is_nan(self)
+            }
+        }
+
+        impl Scalar for Complex<$t> {
+            type Real = $t;
+            const IS_COMPLEX: bool = true;
+
+            fn zero() -> Self {
+                Complex::new(0., 0.)
+            }
+            fn one() -> Self {
+                Complex::new(1., 0.)
+            }
+            fn from_parts(re: f64, im: f64) -> Self {
+                Complex::new(re as $t, im as $t)
+            }
+            fn to_parts(self) -> (f64, f64) {
+                (self.re as f64, self.im as f64)
+            }
+            fn from_real(x: Self::Real) -> Self {
+                Complex::new(x, 0.)
+            }
+            fn abs(self) -> Self::Real {
+                self.norm()
+            }
+            fn conj(self) -> Self {
+                Complex::conj(&self)
+            }
+            fn ulp_distance(self, other: Self) -> u64 {
+                self.re
+                    .ulp_distance(other.re)
+                    .max(self.im.ulp_distance(other.im))
+            }
+        }
+    };
+}
+
+impl_real_scalar!(f32);
+impl_real_scalar!(f64);
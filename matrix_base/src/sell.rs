@@ -0,0 +1,180 @@
+use crate::{Dense, RealScalar, Scalar, COO, CSR};
+
+// SELL-C-sigma format from "A unified sparse matrix data format for efficient general
+// sparse matrix-vector multiply on modern processors with wide SIMD units", Kreutzer et al.
+// https://doi.org/10.1137/130930352
+// The rows are cut into chunks of chunk_size (C) rows, every chunk is stored like ELL
+// (column-major, padded to the longest row of the chunk). To reduce the padding,
+// the rows are sorted by length inside windows of sigma rows before.
+// Row k of the sorted matrix is row perm[k] of the original one.
+// Local row r of chunk c has its k-th entry at chunk_pos[c] + k*C + r.
+pub struct SELL<T = f64> {
+    pub chunk_pos: Vec<usize>,
+    pub chunk_width: Vec<usize>,
+    pub col_pos: Vec<usize>,
+    pub values: Vec<T>,
+    // Length of the sorted rows
+    pub row_len: Vec<usize>,
+    pub perm: Vec<usize>,
+    pub chunk_size: usize,
+    pub sigma: usize,
+    pub shape: (usize, usize),
+}
+
+impl<T: Scalar> SELL<T> {
+    // sigma = 1 means no sorting (SELL-C), sigma = m sorts all rows
+    pub fn from_csr(csr: &CSR<T>, chunk_size: usize, sigma: usize) -> Self {
+        assert!(
+            chunk_size > 0 && sigma > 0,
+            "Chunk size and sigma must not be zero"
+        );
+        let m = csr.shape.0;
+
+        // Sort by decreasing length inside the sigma windows.
+        // Stable, so equally long rows keep their order.
+        let mut perm: Vec<usize> = (0..m).collect();
+        for window in perm.chunks_mut(sigma) {
+            window.sort_by_key(|&i| std::cmp::Reverse(csr.get_row_nnz(i)));
+        }
+        let row_len: Vec<usize> = perm.iter().map(|&i| csr.get_row_nnz(i)).collect();
+
+        let n_chunks = m.div_ceil(chunk_size);
+        let mut chunk_pos = vec![0];
+        let mut chunk_width = Vec::with_capacity(n_chunks);
+        for rows in row_len.chunks(chunk_size) {
+            let width = rows.iter().copied().max().unwrap_or(0);
+            chunk_width.push(width);
+            chunk_pos.push(chunk_pos.last().unwrap() + width * chunk_size);
+        }
+
+        let len = *chunk_pos.last().unwrap();
+        let mut col_pos = vec![0; len];
+        let mut values = vec![T::zero(); len];
+        for (k, i) in perm.iter().enumerate() {
+            let (c, r) = (k / chunk_size, k % chunk_size);
+            for (l, pos) in (csr.row_pos[*i]..csr.row_pos[i + 1]).enumerate() {
+                col_pos[chunk_pos[c] + l * chunk_size + r] = csr.col_pos[pos];
+                values[chunk_pos[c] + l * chunk_size + r] = csr.values[pos];
+            }
+        }
+
+        SELL {
+            chunk_pos,
+            chunk_width,
+            col_pos,
+            values,
+            row_len,
+            perm,
+            chunk_size,
+            sigma,
+            shape: csr.shape,
+        }
+    }
+
+    pub fn from_coo(coo: &COO<T>, chunk_size: usize, sigma: usize) -> Self {
+        Self::from_csr(&CSR::from_coo(coo), chunk_size, sigma)
+    }
+
+    // Number of stored (non-padding) entries
+    pub fn nnz(&self) -> usize {
+        self.row_len.iter().sum()
+    }
+
+    // Number of stored zeros that come from padding the chunks
+    pub fn padding(&self) -> usize {
+        self.values.len() - self.nnz()
+    }
+
+    // Stored values per entry, 1.0 means no padding at all
+    pub fn fill_ratio(&self) -> f64 {
+        let nnz = self.nnz();
+        if nnz == 0 {
+            return 1.;
+        }
+        self.values.len() as f64 / nnz as f64
+    }
+
+    // Column indices and values of the k-th sorted row, i.e. of row perm[k]
+    pub fn get_sorted_row(&self, k: usize) -> impl Iterator<Item = (usize, T)> + '_ {
+        let c = self.chunk_size;
+        let start = self.chunk_pos[k / c] + k % c;
+        (0..self.row_len[k]).map(move |l| (self.col_pos[start + l * c], self.values[start + l * c]))
+    }
+
+    pub fn print(&self) {
+        println!(
+            "Sparse ({},{})-matrix in SELL-{}-{} format with {} chunks, {} entries and {} padding",
+            self.shape.0,
+            self.shape.1,
+            self.chunk_size,
+            self.sigma,
+            self.chunk_width.len(),
+            self.nnz(),
+            self.padding()
+        );
+        println!("Perm {:?}", self.perm);
+        println!("Chunk Pos {:?}", self.chunk_pos);
+        println!("Row Len {:?}", self.row_len);
+        println!("Col Pos {:?}", self.col_pos);
+        println!("Values {:?}", self.values);
+    }
+
+    pub fn to_csr(&self) -> CSR<T> {
+        // Undo the permutation
+        let mut rows: Vec<Vec<(usize, T)>> = vec![vec![]; self.shape.0];
+        for (k, i) in self.perm.iter().enumerate() {
+            rows[*i] = self.get_sorted_row(k).collect();
+        }
+
+        let mut row_pos = vec![0];
+        let mut col_pos = Vec::with_capacity(self.nnz());
+        let mut values = Vec::with_capacity(self.nnz());
+        for row in rows {
+            for (j, x) in row {
+                col_pos.push(j);
+                values.push(x);
+            }
+            row_pos.push(values.len());
+        }
+
+        CSR {
+            row_pos,
+            col_pos,
+            values,
+            shape: self.shape,
+        }
+    }
+
+    pub fn to_dense(&self) -> Dense<T> {
+        let mut mat = Dense::new_zeros(self.shape);
+
+        for (k, i) in self.perm.iter().enumerate() {
+            for (j, x) in self.get_sorted_row(k) {
+                mat.set(*i, j, x);
+            }
+        }
+
+        mat
+    }
+}
+
+impl<T: RealScalar> SELL<T> {
+    // Convert to another scalar type, e.g. f64 -> f32
+    pub fn cast<U: Scalar>(&self) -> SELL<U> {
+        SELL {
+            chunk_pos: self.chunk_pos.clone(),
+            chunk_width: self.chunk_width.clone(),
+            col_pos: self.col_pos.clone(),
+            values: self
+                .values
+                .iter()
+                .map(|x| U::from_f64(x.to_f64()))
+                .collect(),
+            row_len: self.row_len.clone(),
+            perm: self.perm.clone(),
+            chunk_size: self.chunk_size,
+            sigma: self.sigma,
+            shape: self.shape,
+        }
+    }
+}
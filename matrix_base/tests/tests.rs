@@ -1,6 +1,10 @@
 use std::path::Path;
 
-use matrix_base::{Dense, COO, CSR};
+use matrix_base::{
+    accuracy, generate, AsCsrView, CanonicalizeOptions, Complex64, Compression, Dense, DenseView,
+    Error, Layout, MappedCsr, MtxFormat, MtxWriteOptions, Scalar, StructureError, BSR, COO, CSC,
+    CSR, DIA, ELL, SELL,
+};
 
 // Im Endeffekt etwas umständlich über Path joinen.
 // Kann man auch mit String-Concat machen, aber
@@ -67,9 +71,1649 @@ fn test_read_coo() {
     }
 }
 
+#[test]
+fn test_read_coo_symmetric() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx"));
+    let coo = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+
+    assert_eq!(coo.shape, (3, 3));
+    assert_eq!(coo.data.len(), 7);
+
+    let dense = coo.to_dense();
+    let expected = [4., 1., 0., 1., 5., 2., 0., 2., 6.];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!(cmp_float(*x, y, eps));
+    }
+
+    // Skew-symmetric: mirrored entries change their sign
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_skew.mtx"));
+    let dense = COO::read_mtx(&fname, true)
+        .expect("Failed reading matrix during test")
+        .to_dense();
+
+    let expected = [0., -3., 1.5, 3., 0., 0., -1.5, 0., 0.];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!(cmp_float(*x, y, eps));
+    }
+}
+
+#[test]
+fn test_read_coo_pattern_integer() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_pattern.mtx"));
+    let coo = COO::read_mtx(&fname, false).expect("Failed reading matrix during test");
+
+    assert_eq!(coo.shape, (2, 3));
+    let data_pattern = [(0, 0, 1.), (0, 2, 1.), (1, 1, 1.)];
+    for ((i, j, x), (a, b, y)) in coo.data.iter().zip(data_pattern) {
+        assert_eq!(*i, a);
+        assert_eq!(*j, b);
+        assert!(cmp_float(*x, y, eps));
+    }
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_integer.mtx"));
+    let coo = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+
+    let data_integer = [(0, 1, 7.), (1, 0, -3.)];
+    for ((i, j, x), (a, b, y)) in coo.data.iter().zip(data_integer) {
+        assert_eq!(*i, a);
+        assert_eq!(*j, b);
+        assert!(cmp_float(*x, y, eps));
+    }
+}
+
+#[test]
+fn test_read_array() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_array.mtx"));
+    let dense = Dense::read_mtx(&fname).expect("Failed reading matrix during test");
+
+    assert_eq!(dense.shape, (2, 3));
+    let expected = [1., 2., 3., 4., 5., 6.];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!(cmp_float(*x, y, eps));
+    }
+
+    // Array files also load into COO, with all entries
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    assert_eq!(coo.data.len(), 6);
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_array_symmetric.mtx"));
+    let dense = Dense::read_mtx(&fname).expect("Failed reading matrix during test");
+
+    let expected = [1., 2., 3., 2., 4., 5., 3., 5., 6.];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!(cmp_float(*x, y, eps));
+    }
+}
+
+#[test]
+fn test_write_mtx() {
+    let eps = 1e-10;
+    let out_dir = std::env::temp_dir();
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("a001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+
+    // Coordinate format with comments, round trip via COO and CSR
+    let opts = MtxWriteOptions {
+        comments: vec![String::from(" written by test_write_mtx")],
+        ..Default::default()
+    };
+    let fname_out = out_dir.join("matrix_base_test_write_coo.mtx");
+    coo.write_mtx(&fname_out, &opts)
+        .expect("Failed writing matrix during test");
+    let coo2 = COO::read_mtx(&fname_out, true).expect("Failed reading matrix during test");
+    assert_eq!(coo.shape, coo2.shape);
+    assert_eq!(coo.data, coo2.data);
+
+    let fname_out = out_dir.join("matrix_base_test_write_csr.mtx");
+    CSR::from_coo(&coo)
+        .write_mtx(&fname_out, &opts)
+        .expect("Failed writing matrix during test");
+    let coo2 = COO::read_mtx(&fname_out, true).expect("Failed reading matrix during test");
+    assert_eq!(coo.data, coo2.data);
+
+    // Array format with limited precision
+    let dense = Dense::read_mtx(&Path::new(DATA_PATH).join(&Path::new("mm_array.mtx")))
+        .expect("Failed reading matrix during test");
+    let opts = MtxWriteOptions {
+        format: MtxFormat::Array,
+        precision: Some(3),
+        ..Default::default()
+    };
+    let fname_out = out_dir.join("matrix_base_test_write_dense.mtx");
+    dense
+        .write_mtx(&fname_out, &opts)
+        .expect("Failed writing matrix during test");
+    let dense2 = Dense::read_mtx(&fname_out).expect("Failed reading matrix during test");
+    assert_eq!(dense.shape, dense2.shape);
+    for (x, y) in dense.data.iter().zip(dense2.data.iter()) {
+        assert!(cmp_float(*x, *y, eps));
+    }
+
+    // Symmetric compression only for symmetric matrices
+    let opts = MtxWriteOptions {
+        symmetric: true,
+        ..Default::default()
+    };
+    let fname_out = out_dir.join("matrix_base_test_write_symmetric.mtx");
+    assert!(coo.write_mtx(&fname_out, &opts).is_err());
+
+    let sym: COO = COO::read_mtx(
+        &Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx")),
+        true,
+    )
+    .expect("Failed reading matrix during test");
+    sym.write_mtx(&fname_out, &opts)
+        .expect("Failed writing matrix during test");
+    let sym2 = COO::read_mtx(&fname_out, true).expect("Failed reading matrix during test");
+    assert_eq!(sym.data, sym2.data);
+}
+
+#[test]
+fn test_read_errors() {
+    let out_dir = std::env::temp_dir();
+
+    // Writes the content to a temporary file and tries to read it
+    let read = |name: &str, content: &str| -> Result<COO, Error> {
+        let fname = out_dir.join(name);
+        std::fs::write(&fname, content).expect("Failed writing file during test");
+        COO::read_mtx(&fname, false)
+    };
+
+    let res = read("matrix_base_test_missing.mtx", "");
+    assert!(matches!(res, Err(Error::SizeLine { line: 0, .. })));
+
+    let res = read(
+        "matrix_base_test_banner.mtx",
+        "%%MatrixMarket matrix coordinate real unknown\n2 2 1\n1 1 1.\n",
+    );
+    assert!(matches!(res, Err(Error::Banner { line: 1, .. })));
+
+    let res = read("matrix_base_test_size.mtx", "% comment\n2 x 1\n1 1 1.\n");
+    assert!(matches!(res, Err(Error::SizeLine { line: 2, .. })));
+
+    // Sizes from the file must neither overflow nor be allocated up front
+    let res = read(
+        "matrix_base_test_array_overflow.mtx",
+        "%%MatrixMarket matrix array real general\n18446744073709551615 2\n1.\n",
+    );
+    assert!(matches!(res, Err(Error::SizeLine { line: 2, .. })));
+    let res = read(
+        "matrix_base_test_huge_count.mtx",
+        "2 2 18446744073709551615\n1 1 1.\n",
+    );
+    assert!(matches!(res, Err(Error::EntryCount { found: 1, .. })));
+    let fname = out_dir.join("matrix_base_test_huge_count.mtx");
+    let res: Result<COO, Error> = COO::read_mtx_par(&fname, false);
+    assert!(matches!(res, Err(Error::EntryCount { found: 1, .. })));
+
+    // Truncated file must not panic
+    let res = read("matrix_base_test_truncated.mtx", "2 2 3\n1 1 1.\n2 2 1.\n");
+    assert!(matches!(
+        res,
+        Err(Error::EntryCount {
+            expected: 3,
+            found: 2,
+            ..
+        })
+    ));
+
+    let res = read("matrix_base_test_too_many.mtx", "2 2 1\n1 1 1.\n2 2 1.\n");
+    assert!(matches!(
+        res,
+        Err(Error::EntryCount {
+            expected: 1,
+            found: 2,
+            ..
+        })
+    ));
+
+    let res = read("matrix_base_test_index.mtx", "2 2 2\n1 1 1.\n2 3 1.\n");
+    match res {
+        Err(Error::IndexOutOfBounds {
+            line,
+            column,
+            index,
+            shape,
+            ..
+        }) => {
+            assert_eq!((line, column), (3, 3));
+            assert_eq!(index, (2, 3));
+            assert_eq!(shape, (2, 2));
+        }
+        _ => panic!("Expected IndexOutOfBounds error"),
+    }
+
+    let res = read("matrix_base_test_value.mtx", "2 2 2\n1 1 1.\n2 2 abc\n");
+    match res {
+        Err(e @ Error::Value { .. }) => {
+            assert!(e.path().ends_with("matrix_base_test_value.mtx"));
+            assert!(e.to_string().contains(":3:5: invalid value 'abc'"));
+        }
+        _ => panic!("Expected Value error"),
+    }
+
+    let res = read("matrix_base_test_no_value.mtx", "2 2 1\n1 1\n");
+    assert!(matches!(res, Err(Error::Value { line: 2, .. })));
+
+    let res: Result<COO, Error> =
+        COO::read_mtx(&out_dir.join("matrix_base_test_does_not_exist.mtx"), false);
+    assert!(matches!(res, Err(Error::Io { .. })));
+}
+
+#[test]
+fn test_read_complex() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_hermitian.mtx"));
+    let dense: Dense<Complex64> =
+        Dense::read_mtx(&fname).expect("Failed reading matrix during test");
+
+    let c = Complex64::new;
+    let expected = [
+        c(2., 0.),
+        c(1., -1.),
+        c(0., 0.),
+        c(1., 1.),
+        c(3., 0.),
+        c(0., -2.),
+        c(0., 0.),
+        c(0., 2.),
+        c(4., 0.),
+    ];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!((x - y).norm() < eps);
+    }
+
+    // Round trip keeps the complex field
+    let coo: COO<Complex64> =
+        COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let fname_out = std::env::temp_dir().join("matrix_base_test_write_complex.mtx");
+    coo.write_mtx(&fname_out, &MtxWriteOptions::default())
+        .expect("Failed writing matrix during test");
+    let coo2: COO<Complex64> =
+        COO::read_mtx(&fname_out, true).expect("Failed reading matrix during test");
+    assert_eq!(coo.data, coo2.data);
+
+    // Complex values don't fit into a real matrix
+    let res: Result<COO, Error> = COO::read_mtx(&fname, true);
+    assert!(matches!(res, Err(Error::Unsupported { line: 1, .. })));
+}
+
+#[test]
+fn test_read_f32() {
+    let eps = 1e-5;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx"));
+    let coo: COO<f32> = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+    assert_eq!(csr.row_pos, vec![0, 2, 5, 7]);
+
+    let dense = csr.to_dense().cast::<f64>();
+    let expected = [4., 1., 0., 1., 5., 2., 0., 2., 6.];
+    for (x, y) in dense.data.iter().zip(expected) {
+        assert!(cmp_float(*x, y, eps));
+    }
+}
+
+#[test]
+fn test_csc() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let csc = CSC::from_coo(&coo);
+
+    // 4 1 0
+    // 1 5 2
+    // 0 2 6
+    assert_eq!(csc.col_pos, vec![0, 2, 5, 7]);
+    assert_eq!(csc.row_pos, vec![0, 1, 0, 1, 2, 1, 2]);
+    assert_eq!(csc.get_col_nnz(1), 3);
+    let (rows, values) = csc.get_col(2);
+    assert_eq!(rows, &[1, 2]);
+    assert!(cmp_float(values[0], 2., eps));
+
+    // Non-symmetric matrix: conversions must agree with the dense matrix
+    let fname = Path::new(DATA_PATH).join(&Path::new("a001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+    let dense = coo.to_dense();
+
+    let csc = CSC::from_coo(&coo);
+    assert!(cmp_dense(&csc.to_dense(), &dense, eps));
+    assert!(cmp_dense(&CSC::from_csr(&csr).to_dense(), &dense, eps));
+
+    let csr2 = csc.to_csr();
+    assert_eq!(csr.row_pos, csr2.row_pos);
+    assert_eq!(csr.col_pos, csr2.col_pos);
+    assert_eq!(csr.values, csr2.values);
+
+    // Unsorted input, columns are still sorted by row
+    let coo = COO {
+        data: vec![(2, 0, 3.), (0, 1, 1.), (0, 0, 1.), (1, 0, 2.)],
+        shape: (3, 2),
+    };
+    let csc = CSC::from_coo(&coo);
+    assert_eq!(csc.col_pos, vec![0, 3, 4]);
+    assert_eq!(csc.row_pos, vec![0, 1, 2, 0]);
+    assert_eq!(csc.values, vec![1., 2., 3., 1.]);
+}
+
+#[test]
+fn test_bsr() {
+    let eps = 1e-10;
+
+    // 4 1 0
+    // 1 5 2
+    // 0 2 6
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+
+    let bsr = BSR::from_coo(&coo, (2, 2));
+    assert_eq!(bsr.row_pos, vec![0, 2, 4]);
+    assert_eq!(bsr.col_pos, vec![0, 1, 0, 1]);
+    assert_eq!(bsr.values.len(), 16);
+    assert_eq!(bsr.nnz, 7);
+    assert_eq!(bsr.padding(), 9);
+    assert!(cmp_float(bsr.fill_ratio(), 16. / 7., eps));
+
+    let (cols, values) = bsr.get_block_row(1);
+    assert_eq!(cols, &[0, 1]);
+    assert_eq!(values, &[0., 2., 0., 0., 6., 0., 0., 0.]);
+
+    // Conversions back drop the padding
+    assert!(cmp_dense(&bsr.to_dense(), &coo.to_dense(), eps));
+    let csr = CSR::from_coo(&coo);
+    let csr2 = BSR::from_csr(&csr, (3, 1)).to_csr();
+    assert_eq!(csr.row_pos, csr2.row_pos);
+    assert_eq!(csr.col_pos, csr2.col_pos);
+    assert_eq!(csr.values, csr2.values);
+
+    // Unsorted input with duplicates
+    let coo = COO {
+        data: vec![(1, 1, 1.), (0, 0, 1.), (1, 1, 2.)],
+        shape: (2, 2),
+    };
+    let bsr = BSR::from_coo(&coo, (2, 2));
+    assert_eq!(bsr.values, vec![1., 0., 0., 3.]);
+    assert_eq!(bsr.nnz, 2);
+}
+
+#[test]
+fn test_ell_sell() {
+    let eps = 1e-10;
+
+    // Row lengths 1, 3, 0, 2, 1
+    let coo = COO {
+        data: vec![
+            (0, 0, 1.),
+            (1, 0, 2.),
+            (1, 2, 3.),
+            (1, 3, 4.),
+            (3, 1, 5.),
+            (3, 3, 6.),
+            (4, 2, 7.),
+        ],
+        shape: (5, 4),
+    };
+    let csr = CSR::from_coo(&coo);
+    let dense = coo.to_dense();
+
+    let ell = ELL::from_csr(&csr);
+    assert_eq!(ell.width, 3);
+    assert_eq!(ell.nnz(), 7);
+    assert_eq!(ell.padding(), 8);
+    // Column-major: first entries of all rows come first
+    assert_eq!(&ell.values[..5], &[1., 2., 0., 5., 7.]);
+    assert_eq!(
+        ell.get_row(1).collect::<Vec<_>>(),
+        vec![(0, 2.), (2, 3.), (3, 4.)]
+    );
+    assert!(cmp_dense(&ell.to_dense(), &dense, eps));
+    assert_eq!(ell.to_csr().row_pos, csr.row_pos);
+
+    // No sorting: chunks {0, 1}, {2, 3}, {4} with widths 3, 2, 1
+    let sell = SELL::from_csr(&csr, 2, 1);
+    assert_eq!(sell.perm, vec![0, 1, 2, 3, 4]);
+    assert_eq!(sell.chunk_width, vec![3, 2, 1]);
+    assert_eq!(sell.chunk_pos, vec![0, 6, 10, 12]);
+    assert_eq!(sell.padding(), 5);
+    assert!(cmp_dense(&sell.to_dense(), &dense, eps));
+
+    // Sorting all rows: chunks {1, 3}, {0, 4}, {2} with widths 3, 1, 0
+    let sell = SELL::from_csr(&csr, 2, 5);
+    assert_eq!(sell.perm, vec![1, 3, 0, 4, 2]);
+    assert_eq!(sell.chunk_width, vec![3, 1, 0]);
+    assert_eq!(sell.padding(), 1);
+    assert!(cmp_float(sell.fill_ratio(), 8. / 7., eps));
+    assert!(cmp_dense(&sell.to_dense(), &dense, eps));
+
+    let csr2 = sell.to_csr();
+    assert_eq!(csr.row_pos, csr2.row_pos);
+    assert_eq!(csr.col_pos, csr2.col_pos);
+    assert_eq!(csr.values, csr2.values);
+}
+
+#[test]
+fn test_dia() {
+    let eps = 1e-10;
+
+    // 4 1 0
+    // 1 5 2
+    // 0 2 6
+    let fname = Path::new(DATA_PATH).join(&Path::new("mm_symmetric.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+
+    let dia = DIA::from_csr(&csr);
+    assert_eq!(dia.offsets, vec![-1, 0, 1]);
+    assert_eq!(dia.nnz, 7);
+    assert_eq!(dia.padding(), 2);
+    assert_eq!(dia.get_diagonal(-1), Some(&[0., 1., 2.][..]));
+    assert_eq!(dia.get_diagonal(1), Some(&[1., 2., 0.][..]));
+    assert_eq!(dia.get_diagonal(2), None);
+    assert_eq!(dia.diagonal_rows(-1), 1..3);
+    assert!(cmp_dense(&dia.to_dense(), &coo.to_dense(), eps));
+
+    let csr2 = dia.to_csr();
+    assert_eq!(csr.row_pos, csr2.row_pos);
+    assert_eq!(csr.col_pos, csr2.col_pos);
+    assert_eq!(csr.values, csr2.values);
+
+    // Banded detection
+    assert!(DIA::try_from_csr(&csr, 1.5).is_some());
+    let coo = COO {
+        data: vec![(0, 0, 1.), (0, 2, 1.), (1, 1, 1.), (2, 0, 1.)],
+        shape: (3, 3),
+    };
+    assert_eq!(DIA::diagonal_offsets(&CSR::from_coo(&coo)), vec![-2, 0, 2]);
+    assert!(DIA::try_from_csr(&CSR::from_coo(&coo), 1.5).is_none());
+    assert!(DIA::try_from_csr(&CSR::from_coo(&coo), 3.).is_some());
+    assert!(DIA::is_banded(&csr, 1.5));
+    assert!(!DIA::is_banded(&CSR::from_coo(&coo), 1.5));
+    assert!(DIA::is_banded(&CSR::from_coo(&coo), 3.));
+}
+
+#[test]
+fn test_validate() {
+    let fname = Path::new(DATA_PATH).join(&Path::new("a001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    assert_eq!(coo.validate(), Ok(()));
+    assert_eq!(CSR::from_coo(&coo).validate(), Ok(()));
+
+    // 1 0 2
+    // 0 0 0
+    // 0 3 0
+    let csr = || CSR {
+        row_pos: vec![0, 2, 2, 3],
+        col_pos: vec![0, 2, 1],
+        values: vec![1., 2., 3.],
+        shape: (3, 3),
+    };
+    assert_eq!(csr().validate(), Ok(()));
+
+    let mut a = csr();
+    a.row_pos.push(3);
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::RowPosLength {
+            expected: 4,
+            found: 5
+        })
+    );
+
+    let mut a = csr();
+    a.row_pos[0] = 1;
+    assert_eq!(a.validate(), Err(StructureError::RowPosStart { found: 1 }));
+
+    let mut a = csr();
+    a.row_pos[2] = 1;
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::RowPosDecreasing { row: 1 })
+    );
+
+    let mut a = csr();
+    a.row_pos[3] = 2;
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::RowPosEnd {
+            expected: 3,
+            found: 2
+        })
+    );
+
+    let mut a = csr();
+    a.values.pop();
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::ValueCount {
+            col_pos: 3,
+            values: 2
+        })
+    );
+
+    let mut a = csr();
+    a.col_pos[2] = 3;
+    let e = a.validate().unwrap_err();
+    assert_eq!(
+        e,
+        StructureError::ColumnOutOfBounds {
+            row: 2,
+            column: 3,
+            columns: 3
+        }
+    );
+    assert_eq!(
+        e.to_string(),
+        "column 3 in row 2 out of bounds for 3 columns"
+    );
+
+    let mut a = csr();
+    a.col_pos.swap(0, 1);
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::ColumnsNotSorted { row: 0 })
+    );
+
+    let mut a = csr();
+    a.col_pos[1] = 0;
+    assert_eq!(
+        a.validate(),
+        Err(StructureError::DuplicateColumn { row: 0, column: 0 })
+    );
+
+    let coo = COO {
+        data: vec![(0, 0, 1.), (3, 1, 1.)],
+        shape: (3, 3),
+    };
+    assert_eq!(
+        coo.validate(),
+        Err(StructureError::IndexOutOfBounds {
+            entry: 1,
+            index: (3, 1),
+            shape: (3, 3)
+        })
+    );
+}
+
 #[test]
 fn test_read_csr() {
     let eps = 1e-10;
 
     // TODO
 }
+
+#[test]
+fn test_canonicalize() {
+    // 1 0 2
+    // 0 0 0
+    // 0 3 0
+    // with (0,2) split into two entries and an explicit zero at (1,1)
+    let coo = || COO {
+        data: vec![(2, 1, 3.), (0, 2, 1.5), (1, 1, 0.), (0, 0, 1.), (0, 2, 0.5)],
+        shape: (3, 3),
+    };
+    assert!(!coo().is_canonical());
+
+    let mut a = coo();
+    a.canonicalize(&CanonicalizeOptions::default());
+    assert!(a.is_canonical());
+    assert_eq!(a.data, vec![(0, 0, 1.), (0, 2, 2.), (1, 1, 0.), (2, 1, 3.)]);
+
+    let mut a = coo();
+    a.canonicalize(&CanonicalizeOptions {
+        drop_zeros: Some(0.),
+        ..Default::default()
+    });
+    assert_eq!(a.data, vec![(0, 0, 1.), (0, 2, 2.), (2, 1, 3.)]);
+
+    // Without sorting the summed entry stays at its first position
+    let mut a = coo();
+    a.canonicalize(&CanonicalizeOptions {
+        sort: false,
+        sum_duplicates: true,
+        drop_zeros: Some(1e-12),
+    });
+    assert_eq!(a.data, vec![(2, 1, 3.), (0, 2, 2.), (0, 0, 1.)]);
+
+    // Conversions sum up the duplicates, like to_dense does
+    let csr = CSR::from_coo(&coo());
+    assert_eq!(csr.validate(), Ok(()));
+    assert_eq!(csr.row_pos, vec![0, 2, 3, 4]);
+    assert_eq!(csr.col_pos, vec![0, 2, 1, 1]);
+    assert_eq!(csr.values, vec![1., 2., 0., 3.]);
+    assert!(cmp_dense(&csr.to_dense(), &coo().to_dense(), 1e-12));
+    assert!(cmp_dense(
+        &CSC::from_coo(&coo()).to_dense(),
+        &coo().to_dense(),
+        1e-12
+    ));
+}
+
+#[test]
+fn test_transpose() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(Path::new("a002.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let a = CSR::from_coo(&coo);
+    let dense = a.to_dense();
+    let (m, n) = a.shape;
+
+    let t = a.transpose();
+    assert_eq!(t.validate(), Ok(()));
+    assert_eq!(t.shape, (n, m));
+    let t_dense = t.to_dense();
+    for i in 0..m {
+        for j in 0..n {
+            assert!((t_dense.get(j, i) - dense.get(i, j)).abs() < eps);
+        }
+    }
+    assert_eq!(t.transpose().col_pos, a.col_pos);
+
+    // (P A Q)^T with row k of P A = row row_perm[k] of A
+    let row_perm: Vec<usize> = (0..m).rev().collect();
+    let col_perm: Vec<usize> = (0..n).map(|j| (j + 1) % n).collect();
+    let t = a.permuted_transpose(&row_perm, &col_perm);
+    assert_eq!(t.validate(), Ok(()));
+    let t_dense = t.to_dense();
+    for (k, &i) in row_perm.iter().enumerate() {
+        for (l, &j) in col_perm.iter().enumerate() {
+            assert!((t_dense.get(l, k) - dense.get(i, j)).abs() < eps);
+        }
+    }
+
+    // Transposing again gives P A Q with sorted columns
+    let paq = t.transpose();
+    assert_eq!(paq.validate(), Ok(()));
+    let paq_dense = paq.to_dense();
+    for (k, &i) in row_perm.iter().enumerate() {
+        for (l, &j) in col_perm.iter().enumerate() {
+            assert!((paq_dense.get(k, l) - dense.get(i, j)).abs() < eps);
+        }
+    }
+}
+
+#[test]
+fn test_csr_arith() {
+    // 1 0 2      0 4 -2
+    // 0 0 0  and 0 0  0
+    // 0 3 0      5 0  0
+    let a = CSR::from_coo(&COO {
+        data: vec![(0, 0, 1.), (0, 2, 2.), (2, 1, 3.)],
+        shape: (3, 3),
+    });
+    let b = CSR::from_coo(&COO {
+        data: vec![(0, 1, 4.), (0, 2, -2.), (2, 0, 5.)],
+        shape: (3, 3),
+    });
+
+    let c = a.add(&b);
+    assert_eq!(c.validate(), Ok(()));
+    // 2 - 2 cancels, no explicit zero is kept
+    assert_eq!(c.row_pos, vec![0, 2, 2, 4]);
+    assert_eq!(c.col_pos, vec![0, 1, 0, 1]);
+    assert_eq!(c.values, vec![1., 4., 5., 3.]);
+
+    let c = a.sub(&b);
+    assert_eq!(c.values, vec![1., -4., 4., -5., 3.]);
+    let c = a.sub(&a);
+    assert_eq!(c.validate(), Ok(()));
+    assert_eq!(c.row_pos, vec![0, 0, 0, 0]);
+    assert!(c.values.is_empty());
+
+    let c = a.axpby(2., &b, 0.5);
+    assert_eq!(c.col_pos, vec![0, 1, 2, 0, 1]);
+    assert_eq!(c.values, vec![2., 2., 3., 2.5, 6.]);
+    let c = a.axpby(1., &b, 1.);
+    assert_eq!(c.col_pos, vec![0, 1, 0, 1]);
+
+    let c = a.hadamard(&b);
+    assert_eq!(c.validate(), Ok(()));
+    assert_eq!(c.row_pos, vec![0, 1, 1, 1]);
+    assert_eq!(c.col_pos, vec![2]);
+    assert_eq!(c.values, vec![-4.]);
+
+    let c = a.scale(-1.);
+    assert_eq!(c.col_pos, a.col_pos);
+    assert_eq!(c.values, vec![-1., -2., -3.]);
+}
+
+#[test]
+fn test_arith_par() {
+    let eps = 1e-10;
+
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let a: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    // Partly overlapping pattern
+    let n = a.shape.1;
+    let b = COO {
+        data: a
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| k % 3 != 0)
+            .map(|(k, &(i, j, x))| (i, (j + k % 2) % n, 0.5 * x - 1.))
+            .collect(),
+        shape: a.shape,
+    };
+    let (a, b) = (CSR::from_coo(&a), CSR::from_coo(&b));
+    let b_dense = b.to_dense();
+
+    let cases = [
+        (a.add(&b), a.add_par(&b), 1., 1.),
+        (a.sub(&b), a.sub_par(&b), 1., -1.),
+        (a.axpby(2., &b, -0.5), a.axpby_par(2., &b, -0.5), 2., -0.5),
+    ];
+    for (c, c_par, alpha, beta) in cases {
+        assert_eq!(c_par.validate(), Ok(()));
+        assert_eq!(c.row_pos, c_par.row_pos);
+        assert_eq!(c.col_pos, c_par.col_pos);
+        assert_eq!(c.values, c_par.values);
+
+        let mut expected = a.to_dense();
+        for (x, y) in expected.data.iter_mut().zip(b_dense.data.iter()) {
+            *x = alpha * *x + beta * y;
+        }
+        assert!((&c_par.to_dense() - &expected).max_abs() < eps);
+    }
+
+    let c = a.hadamard_par(&b);
+    assert_eq!(c.validate(), Ok(()));
+    assert_eq!(c.col_pos, a.hadamard(&b).col_pos);
+    let mut expected = a.to_dense();
+    for (x, y) in expected.data.iter_mut().zip(b_dense.data.iter()) {
+        *x *= y;
+    }
+    assert!((&c.to_dense() - &expected).max_abs() < eps);
+
+    assert_eq!(a.scale_par(3.).values, a.scale(3.).values);
+}
+
+#[test]
+fn test_binary() {
+    let out_dir = std::env::temp_dir();
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, false).expect("Failed reading matrix during test");
+
+    let coo_bin = out_dir.join("matrix_base_test_coo.bin");
+    coo.save_bin(&coo_bin)
+        .expect("Failed writing matrix during test");
+    let loaded: COO = COO::load_bin(&coo_bin).expect("Failed reading matrix during test");
+    assert_eq!(loaded.shape, coo.shape);
+    assert_eq!(loaded.data, coo.data);
+
+    let csr = CSR::from_coo(&coo);
+    let csr_bin = out_dir.join("matrix_base_test_csr.bin");
+    csr.save_bin(&csr_bin)
+        .expect("Failed writing matrix during test");
+    let loaded: CSR = CSR::load_bin(&csr_bin).expect("Failed reading matrix during test");
+    assert_eq!(loaded.validate(), Ok(()));
+    assert_eq!(loaded.row_pos, csr.row_pos);
+    assert_eq!(loaded.col_pos, csr.col_pos);
+    assert_eq!(loaded.values, csr.values);
+
+    let dense = coo.to_dense().cast::<f32>();
+    let dense_bin = out_dir.join("matrix_base_test_dense.bin");
+    dense
+        .save_bin(&dense_bin)
+        .expect("Failed writing matrix during test");
+    let loaded: Dense<f32> =
+        Dense::load_bin(&dense_bin).expect("Failed reading matrix during test");
+    assert_eq!(loaded.shape, dense.shape);
+    assert_eq!(loaded.data, dense.data);
+
+    let fname = Path::new(DATA_PATH).join(Path::new("mm_hermitian.mtx"));
+    let complex: COO<Complex64> =
+        COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let complex_bin = out_dir.join("matrix_base_test_complex.bin");
+    complex
+        .save_bin(&complex_bin)
+        .expect("Failed writing matrix during test");
+    let loaded: COO<Complex64> =
+        COO::load_bin(&complex_bin).expect("Failed reading matrix during test");
+    assert_eq!(loaded.data, complex.data);
+
+    // Other kind or scalar type than stored
+    assert!(matches!(
+        CSR::<f64>::load_bin(&coo_bin),
+        Err(Error::Binary { .. })
+    ));
+    assert!(matches!(
+        COO::<f32>::load_bin(&coo_bin),
+        Err(Error::Binary { .. })
+    ));
+
+    // Flipped bit in the payload and truncated file
+    let mut bytes = std::fs::read(&csr_bin).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 1;
+    std::fs::write(&csr_bin, &bytes).unwrap();
+    assert!(matches!(
+        CSR::<f64>::load_bin(&csr_bin),
+        Err(Error::Binary { .. })
+    ));
+    std::fs::write(&csr_bin, &bytes[..100]).unwrap();
+    assert!(matches!(
+        CSR::<f64>::load_bin(&csr_bin),
+        Err(Error::Binary { .. })
+    ));
+
+    // The cache is written next to the mtx-file on the first read
+    let mtx = out_dir.join("matrix_base_test_cached.mtx");
+    std::fs::copy(Path::new(DATA_PATH).join("a002.mtx"), &mtx).unwrap();
+    let cache = matrix_base::binary::cache_path(&mtx);
+    let _ = std::fs::remove_file(&cache);
+    let first: COO = COO::read_mtx_cached(&mtx).expect("Failed reading matrix during test");
+    assert!(cache.exists());
+    let second: COO = COO::read_mtx_cached(&mtx).expect("Failed reading matrix during test");
+    assert_eq!(first.data, second.data);
+    assert_eq!(second.data, COO::<f64>::read_mtx(&mtx, false).unwrap().data);
+}
+
+#[test]
+fn test_mapped_csr() {
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, false).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+
+    let bin = std::env::temp_dir().join("matrix_base_test_mapped.bin");
+    csr.save_bin_mappable(&bin)
+        .expect("Failed writing matrix during test");
+    let mapped: MappedCsr = MappedCsr::open(&bin).expect("Failed mapping matrix during test");
+    let view = mapped.view();
+    assert_eq!(view.validate(), Ok(()));
+    assert_eq!(view.shape, csr.shape);
+    assert_eq!(view.row_pos, &csr.row_pos[..]);
+    assert_eq!(view.col_pos, &csr.col_pos[..]);
+    assert_eq!(view.values, &csr.values[..]);
+    assert_eq!(view.to_csr().values, csr.values);
+
+    // The mappable file is a normal binary file
+    let loaded: CSR = CSR::load_bin(&bin).expect("Failed reading matrix during test");
+    assert_eq!(loaded.values, csr.values);
+
+    // 4 byte indices and other scalar types can't be mapped
+    csr.save_bin(&bin)
+        .expect("Failed writing matrix during test");
+    assert!(matches!(
+        MappedCsr::<f64>::open(&bin),
+        Err(Error::Binary { .. })
+    ));
+    csr.cast::<f32>()
+        .save_bin_mappable(&bin)
+        .expect("Failed writing matrix during test");
+    assert!(matches!(
+        MappedCsr::<f64>::open(&bin),
+        Err(Error::Binary { .. })
+    ));
+    let mapped: MappedCsr<f32> = MappedCsr::open(&bin).expect("Failed mapping matrix during test");
+    assert_eq!(mapped.view().values, &csr.cast::<f32>().values[..]);
+}
+
+#[test]
+fn test_compressed_mtx() {
+    use std::io::Write;
+
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let plain = std::fs::read(&fname).unwrap();
+    let expected: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+
+    let mut gz = flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+    gz.write_all(&plain).unwrap();
+    let mut bz = bzip2::write::BzEncoder::new(vec![], bzip2::Compression::default());
+    bz.write_all(&plain).unwrap();
+    let mut xz = xz2::write::XzEncoder::new(vec![], 6);
+    xz.write_all(&plain).unwrap();
+
+    let xz = xz.finish().unwrap();
+
+    let out_dir = std::env::temp_dir();
+    for (name, bytes, compression) in [
+        ("gz.mtx.gz", gz.finish().unwrap(), Compression::Gzip),
+        ("bz2.mtx.bz2", bz.finish().unwrap(), Compression::Bzip2),
+        ("xz.mtx.xz", xz.clone(), Compression::Xz),
+        // Detected by content, not by the name
+        ("xz_named.mtx", xz, Compression::Xz),
+    ] {
+        assert_eq!(Compression::detect(&bytes), compression);
+        let path = out_dir.join(format!("matrix_base_test_{}", name));
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(matrix_base::mtx::is_mtx_path(&path));
+        assert_eq!(matrix_base::mtx::read_shape(&path).unwrap(), expected.shape);
+        let coo: COO = COO::read_mtx(&path, true).expect("Failed reading matrix during test");
+        assert_eq!(coo.data, expected.data);
+    }
+
+    assert_eq!(Compression::detect(&plain), Compression::None);
+    assert!(!matrix_base::mtx::is_mtx_path(Path::new("a.txt.gz")));
+    assert!(!matrix_base::mtx::is_mtx_path(Path::new("a.mtx.bin")));
+}
+
+#[test]
+fn test_read_mtx_par() {
+    // Same matrices as the sequential parser, also for symmetric, pattern, complex and array files
+    for name in [
+        "a001.mtx",
+        "bigB001.mtx",
+        "mm_array.mtx",
+        "mm_integer.mtx",
+        "mm_pattern.mtx",
+        "mm_skew.mtx",
+        "mm_symmetric.mtx",
+    ] {
+        let fname = Path::new(DATA_PATH).join(Path::new(name));
+        let seq: COO = COO::read_mtx(&fname, false).expect("Failed reading matrix during test");
+        let par: COO = COO::read_mtx_par(&fname, false).expect("Failed reading matrix during test");
+        assert_eq!(par.shape, seq.shape);
+        assert_eq!(par.data, seq.data);
+    }
+    let fname = Path::new(DATA_PATH).join(Path::new("mm_hermitian.mtx"));
+    let seq: COO<Complex64> = COO::read_mtx(&fname, true).unwrap();
+    let par: COO<Complex64> = COO::read_mtx_par(&fname, true).unwrap();
+    assert_eq!(par.data, seq.data);
+
+    // Tabs, repeated spaces and comments between the entries
+    let out_dir = std::env::temp_dir();
+    let fname = out_dir.join("matrix_base_test_whitespace.mtx");
+    std::fs::write(
+        &fname,
+        "3 3  3\n1\t1   1.5\n  2 3\t\t-2.\n% comment\n3  2 4e1 \r\n",
+    )
+    .unwrap();
+    let coo: COO = COO::read_mtx_par(&fname, false).expect("Failed reading matrix during test");
+    assert_eq!(coo.data, vec![(0, 0, 1.5), (1, 2, -2.), (2, 1, 40.)]);
+
+    // Same errors as the sequential parser, with the right line numbers
+    for content in [
+        "",
+        "% comment\n2 x 1\n1 1 1.\n",
+        "2 2 3\n1 1 1.\n2 2 1.\n",
+        "2 2 1\n1 1 1.\n2 2 1.\n",
+        "2 2 2\n1 1 1.\n2 3 1.\n",
+        "2 2 2\n1 1 1.\n2 2 abc\n",
+        "2 2 1\n1 1\n",
+    ] {
+        let fname = out_dir.join("matrix_base_test_par_error.mtx");
+        std::fs::write(&fname, content).unwrap();
+        let seq = COO::<f64>::read_mtx(&fname, false).err().unwrap();
+        let par = COO::<f64>::read_mtx_par(&fname, false).err().unwrap();
+        assert_eq!(par.to_string(), seq.to_string());
+    }
+
+    // Many chunks, the error must name the line of the whole file
+    let fname = out_dir.join("matrix_base_test_par_large.mtx");
+    let n = 100_000;
+    let mut content = format!(
+        "%%MatrixMarket matrix coordinate real general\n{} {} {}\n",
+        n, n, n
+    );
+    for i in 1..=n {
+        content.push_str(&format!("{} {} {}\n", i, n + 1 - i, i as f64 * 0.5));
+    }
+    std::fs::write(&fname, &content).unwrap();
+    let seq: COO = COO::read_mtx(&fname, true).unwrap();
+    let par: COO = COO::read_mtx_par(&fname, true).unwrap();
+    assert_eq!(par.data, seq.data);
+
+    let content = content.replace("\n77777 ", "\n77777x ");
+    std::fs::write(&fname, &content).unwrap();
+    assert!(matches!(
+        COO::<f64>::read_mtx_par(&fname, false),
+        Err(Error::Value {
+            line: 77779,
+            column: 1,
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_npy() {
+    let out_dir = std::env::temp_dir();
+    let fname = Path::new(DATA_PATH).join(Path::new("bigB001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, true).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+
+    // Round trips
+    let dense = coo.to_dense();
+    let npy = out_dir.join("matrix_base_test_dense.npy");
+    dense
+        .write_npy(&npy)
+        .expect("Failed writing matrix during test");
+    let loaded: Dense = Dense::read_npy(&npy).expect("Failed reading matrix during test");
+    assert_eq!(loaded.shape, dense.shape);
+    assert_eq!(loaded.data, dense.data);
+
+    let npz = out_dir.join("matrix_base_test_csr.npz");
+    csr.write_npz(&npz)
+        .expect("Failed writing matrix during test");
+    let loaded: CSR = CSR::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.row_pos, csr.row_pos);
+    assert_eq!(loaded.col_pos, csr.col_pos);
+    assert_eq!(loaded.values, csr.values);
+    let loaded: COO = COO::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.data, coo.data);
+
+    let csc = CSC::from_coo(&coo);
+    csc.write_npz(&npz)
+        .expect("Failed writing matrix during test");
+    let loaded: CSR = CSR::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.col_pos, csr.col_pos);
+    assert_eq!(loaded.values, csr.values);
+    let loaded: CSC = CSC::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.row_pos, csc.row_pos);
+
+    coo.write_npz(&npz)
+        .expect("Failed writing matrix during test");
+    let loaded: CSR = CSR::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.values, csr.values);
+
+    let fname = Path::new(DATA_PATH).join(Path::new("mm_hermitian.mtx"));
+    let complex: COO<Complex64> = COO::read_mtx(&fname, true).unwrap();
+    complex
+        .write_npz(&npz)
+        .expect("Failed writing matrix during test");
+    let loaded: COO<Complex64> = COO::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.data, complex.data);
+    assert!(matches!(COO::<f64>::read_npz(&npz), Err(Error::Npy { .. })));
+
+    // Files as numpy and scipy write them: Fortran order, int64 indices,
+    // unicode format string, deflate compression and unsorted columns
+    fn npy_bytes(descr: &str, fortran: bool, shape: &str, data: &[u8]) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '{}', 'fortran_order': {}, 'shape': {}, }}",
+            descr,
+            if fortran { "True" } else { "False" },
+            shape
+        );
+        while (10 + header.len() + 1) % 64 != 0 {
+            header.push(' ');
+        }
+        header.push('\n');
+        let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+        bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(header.as_bytes());
+        bytes.extend_from_slice(data);
+        bytes
+    }
+    let f64s = |xs: &[f64]| xs.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+    let i64s = |xs: &[i64]| xs.iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+
+    std::fs::write(
+        &npy,
+        npy_bytes("<f8", true, "(2, 3)", &f64s(&[1., 4., 2., 5., 3., 6.])),
+    )
+    .unwrap();
+    let loaded: Dense = Dense::read_npy(&npy).expect("Failed reading matrix during test");
+    assert_eq!(loaded.shape, (2, 3));
+    assert_eq!(loaded.data, vec![1., 2., 3., 4., 5., 6.]);
+
+    let members = [
+        (
+            "indices.npy",
+            npy_bytes("<i8", false, "(3,)", &i64s(&[2, 0, 1])),
+        ),
+        (
+            "indptr.npy",
+            npy_bytes("<i8", false, "(3,)", &i64s(&[0, 2, 3])),
+        ),
+        (
+            "format.npy",
+            npy_bytes(
+                "<U3",
+                false,
+                "()",
+                &"csr"
+                    .chars()
+                    .flat_map(|c| (c as u32).to_le_bytes())
+                    .collect::<Vec<u8>>(),
+            ),
+        ),
+        ("shape.npy", npy_bytes("<i8", false, "(2,)", &i64s(&[2, 3]))),
+        (
+            "data.npy",
+            npy_bytes("<f8", false, "(3,)", &f64s(&[1., 2., 3.])),
+        ),
+    ];
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&npz).unwrap());
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, bytes) in &members {
+        zip.start_file(*name, options).unwrap();
+        std::io::Write::write_all(&mut zip, bytes).unwrap();
+    }
+    zip.finish().unwrap();
+    let loaded: CSR = CSR::read_npz(&npz).expect("Failed reading matrix during test");
+    assert_eq!(loaded.validate(), Ok(()));
+    assert_eq!(loaded.row_pos, vec![0, 2, 3]);
+    assert_eq!(loaded.col_pos, vec![0, 2, 1]);
+    assert_eq!(loaded.values, vec![2., 1., 3.]);
+
+    // Broken files
+    std::fs::write(&npy, b"not numpy").unwrap();
+    assert!(matches!(
+        Dense::<f64>::read_npy(&npy),
+        Err(Error::Npy { .. })
+    ));
+    std::fs::write(&npy, npy_bytes("<f8", false, "(2, 3)", &f64s(&[1., 2.]))).unwrap();
+    assert!(matches!(
+        Dense::<f64>::read_npy(&npy),
+        Err(Error::Npy { .. })
+    ));
+    // Invalid UTF-8 instead of the quote around descr
+    let mut bytes = npy_bytes("<f8", false, "(3,)", &f64s(&[1., 2., 3.]));
+    let quote = 10 + "{'descr': ".len();
+    assert_eq!(bytes[quote], b'\'');
+    bytes[quote] = 0xff;
+    std::fs::write(&npy, bytes).unwrap();
+    assert!(matches!(
+        Dense::<f64>::read_npy(&npy),
+        Err(Error::Npy { .. })
+    ));
+}
+
+#[test]
+fn test_read_rb() {
+    let out_dir = std::env::temp_dir();
+    let write = |name: &str, lines: &[&str]| {
+        let path = out_dir.join(format!("matrix_base_test_{}", name));
+        std::fs::write(&path, lines.join("\n") + "\n").unwrap();
+        path
+    };
+
+    // [[1, 0, 2], [0, 3, 0], [4, 0, 5]], values with E, D and without exponent letter
+    let values = format!("{:>20}{:>20}", "2.0D+00", "5.0+000");
+    let rua = write(
+        "rua.rua",
+        &[
+            "Unsymmetric test matrix                                                 RUA_1",
+            "             4             1             1             2",
+            "rua                        3             3             5             0",
+            "(4I5)           (5I5)           (3E20.12)",
+            "    1    3    4    6",
+            "    1    3    2    1    3",
+            "  1.000000000000E+00  4.000000000000E+00  3.000000000000E+00",
+            &values,
+        ],
+    );
+    let expected = vec![
+        (0, 0, 1.0),
+        (0, 2, 2.0),
+        (1, 1, 3.0),
+        (2, 0, 4.0),
+        (2, 2, 5.0),
+    ];
+    assert!(matrix_base::rb::is_rb_path(&rua));
+    assert_eq!(matrix_base::rb::read_shape(&rua).unwrap(), (3, 3));
+    let mut coo: COO = COO::read_rb(&rua).expect("Failed reading matrix during test");
+    coo.data.sort_by_key(|a| (a.0, a.1));
+    assert_eq!(coo.data, expected);
+    let csr: CSR = CSR::read_rb(&rua).expect("Failed reading matrix during test");
+    assert_eq!(csr.to_coo().data, expected);
+    let csc: CSC = CSC::read_rb(&rua).expect("Failed reading matrix during test");
+    let mut csc_data = csc.to_coo().data;
+    csc_data.sort_by_key(|a| (a.0, a.1));
+    assert_eq!(csc_data, expected);
+    let mut cached: COO = COO::read_mtx_cached(&rua).expect("Failed reading matrix during test");
+    cached.data.sort_by_key(|a| (a.0, a.1));
+    assert_eq!(cached.data, expected);
+
+    // Harwell-Boeing with right-hand side line and scale factor, lower triangle of [[2, 1], [1, 3]]
+    let rsa = write(
+        "rsa.hb",
+        &[
+            "Symmetric test matrix                                                   RSA_1",
+            "             4             1             1             1             1",
+            "RSA                        2             2             3             0",
+            "(3I8)           (3I8)           (1P,3D16.8)",
+            "F             1             0",
+            "       1       3       4",
+            "       1       2       2",
+            "  2.00000000D+00  1.00000000D+00  3.00000000D+00",
+        ],
+    );
+    let mut coo: COO = COO::read_rb(&rsa).expect("Failed reading matrix during test");
+    coo.data.sort_by_key(|a| (a.0, a.1));
+    assert_eq!(
+        coo.data,
+        vec![(0, 0, 2.0), (0, 1, 1.0), (1, 0, 1.0), (1, 1, 3.0)]
+    );
+
+    // Pattern without value section, rectangular 2x3
+    let pra = write(
+        "pra.rb",
+        &[
+            "Pattern test matrix                                                     PRA_1",
+            "             2             1             1             0",
+            "pra                        2             3             3             0",
+            "(4I4)           (3I4)",
+            "   1   2   3   4",
+            "   2   1   2",
+        ],
+    );
+    let csr: CSR = CSR::read_rb(&pra).expect("Failed reading matrix during test");
+    assert_eq!(csr.shape, (2, 3));
+    assert_eq!(
+        csr.to_coo().data,
+        vec![(0, 1, 1.0), (1, 0, 1.0), (1, 2, 1.0)]
+    );
+
+    // Row index 4 in a matrix with 3 rows
+    let broken = write(
+        "broken.rb",
+        &[
+            "Broken test matrix                                                      BRK_1",
+            "             3             1             1             1",
+            "rua                        3             3             1             0",
+            "(4I5)           (1I5)           (1E20.12)",
+            "    1    2    2    2",
+            "    4",
+            "  1.000000000000E+00",
+        ],
+    );
+    assert!(matches!(
+        COO::<f64>::read_rb(&broken),
+        Err(Error::Value { line: 6, .. })
+    ));
+    // Elemental matrices are not supported
+    let rue = write(
+        "rue.rb",
+        &[
+            "Elemental test matrix                                                   RUE_1",
+            "             3             1             1             1",
+            "rue                        3             3             1             0",
+            "(4I5)           (1I5)           (1E20.12)",
+        ],
+    );
+    assert!(matches!(COO::<f64>::read_rb(&rue), Err(Error::Rb { .. })));
+
+    // Sizes from the header must neither overflow nor be allocated up front
+    let huge = write(
+        "huge.rb",
+        &[
+            "Huge test matrix                                                        HUG_1",
+            "             3             1             1             1",
+            "rua                        3  18446744073709551615             1             0",
+            "(4I5)           (1I5)           (1E20.12)",
+        ],
+    );
+    assert!(matches!(
+        COO::<f64>::read_rb(&huge),
+        Err(Error::Rb { line: 3, .. })
+    ));
+    let huge_nnz = write(
+        "huge_nnz.rb",
+        &[
+            "Huge test matrix                                                        HUG_2",
+            "             3             1             1             1",
+            "rua                        3             3 1000000000000             0",
+            "(4I5)           (1I5)           (1E20.12)",
+            "    1    2    2    2",
+        ],
+    );
+    assert!(COO::<f64>::read_rb(&huge_nnz).is_err());
+
+    assert!(matrix_base::rb::is_rb_path(Path::new("a.psa")));
+    assert!(!matrix_base::rb::is_rb_path(Path::new("a.rue")));
+    assert!(!matrix_base::rb::is_rb_path(Path::new("a.mtx")));
+}
+
+#[test]
+fn test_dense_views() {
+    // 3x4 with a_ij = 10*i + j
+    let mut a: Dense = Dense::new_zeros((3, 4));
+    for i in 0..3 {
+        for j in 0..4 {
+            a.set(i, j, (10 * i + j) as f64);
+        }
+    }
+    assert_eq!(a.layout, Layout::RowMajor);
+    assert_eq!(a.strides(), (4, 1));
+
+    // Same entries, only the storage differs
+    let col = a.as_column_major();
+    assert_eq!(col.layout, Layout::ColumnMajor);
+    assert_eq!(col.strides(), (1, 3));
+    assert_eq!(&col.data[..4], &[0.0, 10.0, 20.0, 1.0]);
+    assert_eq!(col.view().iter().collect::<Vec<_>>(), a.data);
+    assert_eq!(col.as_row_major().data, a.data);
+    assert_eq!(col.row_major_data().as_ref(), &a.data[..]);
+    assert_eq!(col.as_column_major().data, col.data);
+
+    let sub = a.view().submatrix(1..3, 1..3);
+    assert_eq!(sub.shape, (2, 2));
+    assert_eq!(sub.iter().collect::<Vec<_>>(), vec![11.0, 12.0, 21.0, 22.0]);
+    assert_eq!(sub.blas_layout(), Some((Layout::RowMajor, 4)));
+    let sub_t = col.view().submatrix(1..3, 1..3).transpose();
+    assert_eq!(sub_t.to_dense().data, vec![11.0, 21.0, 12.0, 22.0]);
+    assert_eq!(sub_t.blas_layout(), Some((Layout::RowMajor, 3)));
+    assert_eq!(a.view().submatrix(2..2, 0..4).iter().count(), 0);
+
+    // Every second column, not usable by BLAS without copying
+    let every_second = DenseView::new(&a.data, (3, 2), (4, 2));
+    assert_eq!(every_second.get(2, 1), 22.0);
+    assert_eq!(every_second.blas_layout(), None);
+
+    let mut b: Dense = Dense::new_zeros((3, 4)).as_column_major();
+    b.view_mut().submatrix_mut(0..2, 2..4).copy_from(&sub);
+    b.view_mut().transpose().set(3, 2, 5.0);
+    assert_eq!(b.get(0, 3), 12.0);
+    assert_eq!(b.get(1, 2), 21.0);
+    assert_eq!(b.get(2, 3), 5.0);
+    assert_eq!(b.view().iter().filter(|x| *x != 0.0).count(), 5);
+
+    // Files are written row-major for both layouts
+    let path = std::env::temp_dir().join("matrix_base_test_layout.npy");
+    col.write_npy(&path).unwrap();
+    let read: Dense = Dense::read_npy(&path).expect("Failed reading matrix during test");
+    assert_eq!(read.data, a.data);
+}
+
+#[test]
+#[should_panic]
+fn test_dense_view_out_of_bounds() {
+    let a: Dense = Dense::new_zeros((3, 4));
+    a.view().submatrix(0..2, 0..2).get(0, 2);
+}
+
+#[test]
+fn test_dense_ops() {
+    let fname = Path::new(DATA_PATH).join(Path::new("a001.mtx"));
+    let a: Dense = Dense::read_mtx(&fname).expect("Failed reading matrix during test");
+    let a_col = a.as_column_major();
+
+    // Layouts can be mixed, the result has the layout of the left operand
+    let sum = &a + &a_col;
+    assert_eq!(sum.layout, Layout::RowMajor);
+    assert_eq!(sum.data, a.map(|x| 2.0 * x).data);
+    let diff = &a_col - &a;
+    assert_eq!(diff.layout, Layout::ColumnMajor);
+    assert_eq!(diff.max_abs(), 0.0);
+    assert_eq!((2.0 * &a - &a - a.clone()).max_abs(), 0.0);
+    assert_eq!((-&a + &a).max_abs(), 0.0);
+    assert_eq!((&a * 3.0).sum(), 3.0 * a.sum());
+
+    let mut b = a_col.clone();
+    b *= 0.5;
+    b += &a;
+    b -= a.map(|x| 0.5 * x);
+    assert_eq!((&b - &a).max_abs(), 0.0);
+
+    // Product against the textbook loop, also through Index/IndexMut
+    let product = &a * &a_col;
+    let mut reference: Dense = Dense::new_zeros(a.shape);
+    for i in 0..a.shape.0 {
+        for j in 0..a.shape.1 {
+            reference[(i, j)] = (0..a.shape.1).map(|k| a[(i, k)] * a_col[(k, j)]).sum();
+        }
+    }
+    assert_eq!(product.shape, a.shape);
+    assert!((&product - &reference).max_abs() < 1e-10);
+    assert_eq!(a_col[(1, 2)], a.get(1, 2));
+
+    let c: Dense = Dense::new_zeros((2, 3));
+    let count = c.zip_map(&c, |x, y| if x == y { 1.0 } else { 0.0 }).sum();
+    assert_eq!(count, 6.0);
+    assert_eq!(c.fold(0, |n, _| n + 1), 6);
+}
+
+#[test]
+fn test_accuracy() {
+    let fname = Path::new(DATA_PATH).join(Path::new("a001.mtx"));
+    let coo: COO = COO::read_mtx(&fname, false).expect("Failed reading matrix during test");
+    let csr = CSR::from_coo(&coo);
+    let dense = coo.to_dense();
+
+    // Norms don't depend on the format
+    assert!(cmp_float(csr.norm_fro(), dense.norm_fro(), 1e-12));
+    assert_eq!(csr.norm_one(), dense.norm_one());
+    assert_eq!(csr.norm_inf(), dense.norm_inf());
+    assert_eq!(csr.norm_max(), dense.norm_max());
+    assert_eq!(dense.as_column_major().norm_one(), dense.norm_one());
+    let squares: f64 = dense.data.iter().map(|x| x * x).sum();
+    assert!(cmp_float(dense.norm_fro(), squares.sqrt(), 1e-12));
+
+    // Formats can be compared with each other
+    let report = accuracy::compare(&csr, &dense).unwrap();
+    assert_eq!(report.relative_error, 0.0);
+    assert_eq!(report.max_ulp, 0);
+    assert!(report.worst.is_empty());
+    assert_eq!(accuracy::relative_error(&coo, &csr), 0.0);
+
+    // One entry off, one entry missing
+    let mut perturbed = dense.clone();
+    let (i, j, x) = coo.data[0];
+    perturbed[(i, j)] = x + 1.0;
+    let (k, l, y) = coo.data[1];
+    perturbed[(k, l)] = 0.0;
+    let report = accuracy::compare(&perturbed, &csr).unwrap();
+    let expected = (1.0 + y * y).sqrt() / dense.norm_fro();
+    assert!(cmp_float(report.relative_error, expected, 1e-12));
+    assert_eq!(report.max_abs_error, 1f64.max(y.abs()));
+    assert_eq!(report.pattern_mismatches, 1);
+    assert_eq!(report.max_componentwise_error, 1.0);
+    assert_eq!(report.worst.len(), 2);
+    assert!(report.is_within(1.0) && !report.is_within(1e-3));
+
+    // Vectors, e.g. SpMV results
+    assert_eq!(
+        accuracy::max_ulp_distance(&[1.0, 2.0][..], &[1.0, 2.0][..]),
+        0
+    );
+    assert!(accuracy::compare(&[1.0, 2.0][..], &[1.0][..]).is_none());
+    assert_eq!(accuracy::relative_error(&dense, &[1.0][..]), f64::INFINITY);
+
+    // ULP distance counts the representable numbers in between, also across zero
+    let next = f32::from_bits(1.0f32.to_bits() + 1);
+    assert_eq!(1.0f32.ulp_distance(next), 1);
+    assert_eq!((-0.0f64).ulp_distance(0.0), 0);
+    assert_eq!(f64::from_bits(1).ulp_distance(-f64::from_bits(1)), 2);
+    assert_eq!(1.0f64.ulp_distance(f64::NAN), u64::MAX);
+
+    // A NaN entry shows up in all norms and is the worst entry of the report
+    let mut broken = perturbed.clone();
+    broken[(k, l)] = f64::NAN;
+    let broken_csr = CSR::from_coo(&COO {
+        shape: broken.shape,
+        data: vec![(0, 0, 1.0), (k, l, f64::NAN)],
+    });
+    for norm in [
+        broken.max_abs(),
+        broken.norm_one(),
+        broken.norm_inf(),
+        broken.norm_max(),
+        broken.norm_fro(),
+        broken_csr.norm_one(),
+        broken_csr.norm_inf(),
+        broken_csr.norm_max(),
+    ] {
+        assert!(norm.is_nan());
+    }
+    let report = accuracy::compare(&broken, &csr).unwrap();
+    assert!(report.relative_error.is_nan());
+    assert!(report.max_abs_error.is_nan());
+    assert!(report.max_componentwise_error.is_nan());
+    assert_eq!(report.max_ulp, u64::MAX);
+    assert_eq!(report.worst.len(), 2);
+    assert_eq!((report.worst[0].0, report.worst[0].1), (k, l));
+    assert!(report.worst[0].2.is_nan());
+}
+
+#[test]
+fn test_generate() {
+    // Same seed, same matrix
+    let a: COO = generate::random_sparse((50, 40), 0.1, &mut generate::Rng::new(7));
+    let b: COO = generate::random_sparse((50, 40), 0.1, &mut generate::Rng::new(7));
+    let c: COO = generate::random_sparse((50, 40), 0.1, &mut generate::Rng::new(8));
+    assert_eq!(a.data, b.data);
+    assert_ne!(a.data, c.data);
+
+    // Exactly density * m * n distinct positions, sorted
+    assert_eq!(a.data.len(), 200);
+    assert!(a.is_canonical());
+    assert!(a
+        .data
+        .iter()
+        .all(|(i, j, x)| *i < 50 && *j < 40 && (0.0..1.0).contains(x)));
+    let full: COO = generate::random_sparse((3, 4), 1.0, &mut generate::Rng::new(1));
+    assert_eq!(full.data.len(), 12);
+
+    let d: Dense = generate::random_dense((4, 5), &mut generate::Rng::new(7));
+    assert_eq!(d.data.len(), 20);
+    assert!(d.data.iter().all(|x| (0.0..1.0).contains(x)));
+
+    // File names like generate_matrices_experiments.py, readable by the runner
+    let set = generate::ExperimentSet {
+        examples: 2,
+        dense_sizes: vec![3],
+        sparse_sizes: vec![10],
+        sparse_density: 0.0,
+        sparse_vs_dense_densities: vec![0.25],
+        size_sparse_vs_dense: 8,
+        structured: vec![],
+    };
+    let out_dir = std::env::temp_dir().join("matrix_base_test_generate");
+    let written = set
+        .write(&out_dir, 5)
+        .expect("Failed writing matrices during test");
+    let names: Vec<String> = written
+        .iter()
+        .map(|p| p.strip_prefix(&out_dir).unwrap().display().to_string())
+        .collect();
+    assert_eq!(names.len(), 12);
+    assert_eq!(names[0], "dense/dense_3_A0.mtx");
+    assert_eq!(names[3], "sparse/sparse_10_B0.mtx");
+    assert_eq!(names[4], "sparse-vs-dense/s-vs-d_0.25_A0.mtx");
+    assert_eq!(names[11], "sparse-vs-dense/s-vs-d_0.25_B1.mtx");
+
+    let sparse: COO = COO::read_mtx(&written[2], false).expect("Failed reading matrix during test");
+    assert_eq!(sparse.data.len(), 1); // never empty
+    let s_vs_d: COO = COO::read_mtx(&written[4], false).expect("Failed reading matrix during test");
+    assert_eq!((s_vs_d.shape, s_vs_d.data.len()), ((8, 8), 16));
+    let dense: Dense = Dense::read_mtx(&written[0]).expect("Failed reading matrix during test");
+    assert_eq!(dense.shape, (3, 3));
+
+    // Each file only depends on the seed and its name
+    let again = generate::ExperimentSet {
+        dense_sizes: vec![],
+        sparse_sizes: vec![],
+        ..set.clone()
+    };
+    let out_dir_again = std::env::temp_dir().join("matrix_base_test_generate_again");
+    let written_again = again
+        .write(&out_dir_again, 5)
+        .expect("Failed writing matrices during test");
+    assert_eq!(
+        std::fs::read(&written[4]).unwrap(),
+        std::fs::read(&written_again[0]).unwrap()
+    );
+
+    std::fs::remove_dir_all(&out_dir).unwrap();
+    std::fs::remove_dir_all(&out_dir_again).unwrap();
+}
+
+#[test]
+fn test_generate_structured() {
+    // 5-point Laplacian on a 3 x 3 grid: 4 on the diagonal, -1 for the neighbours
+    let lap: CSR = generate::laplacian(generate::Stencil::Point5, 3);
+    assert!(lap.validate().is_ok());
+    let dense = lap.to_dense();
+    assert_eq!(lap.shape, (9, 9));
+    assert_eq!(lap.values.len(), 9 + 2 * 12);
+    assert_eq!(
+        (
+            dense.get(4, 4),
+            dense.get(4, 1),
+            dense.get(4, 3),
+            dense.get(4, 0)
+        ),
+        (4.0, -1.0, -1.0, 0.0)
+    );
+    assert_eq!(dense.get(2, 3), 0.0); // no wrap-around at the end of a grid row
+    let (lap9, lap7, lap27): (CSR, CSR, CSR) = (
+        generate::laplacian(generate::Stencil::Point9, 4),
+        generate::laplacian(generate::Stencil::Point7, 4),
+        generate::laplacian(generate::Stencil::Point27, 4),
+    );
+    for (a, points, diagonal) in [(&lap9, 9, 8.0), (&lap7, 7, 6.0), (&lap27, 27, 26.0)] {
+        assert!(a.validate().is_ok());
+        // Every inner point has the full stencil, all row sums are >= 0
+        assert_eq!(
+            a.row_pos.windows(2).map(|w| w[1] - w[0]).max(),
+            Some(points)
+        );
+        assert_eq!(a.norm_max(), diagonal);
+        let d = a.to_dense();
+        assert_eq!(d.data, d.as_column_major().data); // symmetric
+    }
+    assert_eq!(lap27.shape, (64, 64));
+    assert_eq!(lap27.values.len(), 10 * 10 * 10); // (3n - 2)^3 for n = 4
+
+    // Toeplitz and banded matrices
+    let t: CSR = generate::toeplitz((4, 5), &[(1, 2.0), (-1, 3.0), (0, 1.0)]);
+    assert_eq!(t.values.len(), 3 + 4 + 4);
+    assert_eq!(t.col_pos[..2], [0, 1]);
+    assert_eq!((t.to_dense().get(2, 1), t.to_dense().get(3, 4)), (3.0, 2.0));
+    let band: CSR = generate::banded((6, 6), &[-2, 0, 2], &mut generate::Rng::new(1));
+    assert!(band.validate().is_ok());
+    assert!(DIA::try_from_csr(&band, 2.).is_some());
+
+    // R-MAT: 2^scale vertices, the first rows get the most entries
+    let g: COO = generate::rmat(8, 8, generate::RMAT_GRAPH500, &mut generate::Rng::new(3));
+    assert_eq!(g.shape, (256, 256));
+    assert!(g.is_canonical() && g.data.len() <= 8 * 256 && g.data.len() > 256);
+    let g = CSR::from_coo(&g);
+    let row_len = |i: usize| g.row_pos[i + 1] - g.row_pos[i];
+    assert!(row_len(0) > (128..256).map(row_len).sum::<usize>() / 128 * 4);
+
+    // Kronecker product against its definition
+    let k = generate::kronecker(&t, &lap);
+    assert!(k.validate().is_ok());
+    assert_eq!(k.shape, (36, 45));
+    assert_eq!(k.values.len(), t.values.len() * lap.values.len());
+    assert_eq!(k.to_dense().get(2 * 9 + 4, 9 + 1), -3.0); // t_21 * lap_41
+
+    // Power-law rows: a few long rows, many short ones
+    let p: CSR = generate::power_law_rows((2000, 2000), 10., 2.1, &mut generate::Rng::new(5));
+    assert!(p.validate().is_ok());
+    let mut lengths: Vec<usize> = p.row_pos.windows(2).map(|w| w[1] - w[0]).collect();
+    lengths.sort_unstable();
+    let average = p.values.len() as f64 / 2000.;
+    assert!(average > 5. && average <= 10.);
+    assert!(lengths[1999] as f64 > 10. * average);
+    assert!((lengths[1000] as f64) < average);
+
+    // Names for the command line and the file names
+    let specs: Vec<generate::Structured> = ["lap27:16", "band:100:3", "rmat:10", "powerlaw:500"]
+        .iter()
+        .map(|s| s.parse().unwrap())
+        .collect();
+    let names: Vec<String> = specs.iter().map(|s| s.name()).collect();
+    assert_eq!(names, ["lap27-16", "band-100-3", "rmat-10", "powerlaw-500"]);
+    assert!("lap6:10".parse::<generate::Structured>().is_err());
+    assert!("band:10".parse::<generate::Structured>().is_err());
+    let set = generate::ExperimentSet {
+        structured: vec![specs[1]],
+        ..Default::default()
+    };
+    let out_dir = std::env::temp_dir().join("matrix_base_test_generate_structured");
+    let written = set
+        .write(&out_dir, 0)
+        .expect("Failed writing matrices during test");
+    assert_eq!(
+        written[0],
+        out_dir.join("sparse").join("sparse_band-100-3_A0.mtx")
+    );
+    let a: COO = COO::read_mtx(&written[0], false).expect("Failed reading matrix during test");
+    assert_eq!(a.data.len(), 7 * 100 - 2 * (1 + 2 + 3));
+    std::fs::remove_dir_all(&out_dir).unwrap();
+}
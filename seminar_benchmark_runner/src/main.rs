@@ -1,75 +1,79 @@
 use std::{
+    cell::OnceCell,
     cmp::{min},
     env,
     fs::{self, File},
-    io::{stdout, BufRead, Write},
+    io::{stdout, Write},
     path::{Path, PathBuf},
 };
-use matrix_base::{Dense, COO, CSR};
-use fakscpu::{dense::DenseProd, sparse::SparseProd};
+use matrix_base::{CanonicalizeOptions, Dense, Layout, BSR, COO, CSR, DIA, ELL, SELL};
+use matrix_base::accuracy::{self, MatrixEntries};
+use fakscpu::{block::BlockProd, dense::DenseProd, diagonal::DiaProd, sliced::SlicedProd, sparse::SparseProd};
+use fakscpu::spmm::{DenseSparseProd, SparseDenseProd};
 use gpu::WgpuTask; // WgpuTask von gpu-Crate
 use tools::TimingResult; // TimingResult von tools-Crate
 
+// Parameter für SELL-C-sigma: C entspricht der Workgroup-Breite auf der GPU,
+// sigma ist das Fenster, in dem die Zeilen nach Länge sortiert werden
+const SELL_CHUNK_SIZE: usize = 32;
+const SELL_SIGMA: usize = 256;
+
+// Matrizen gelten als Bandmatrizen (und werden im DIA-Format multipliziert),
+// wenn die Diagonalen höchstens so viele Werte pro Eintrag speichern
+const DIA_MAX_FILL_RATIO: f64 = 2.;
+
+// Spaltenblöcke der dichten Matrix im geblockten Sparse x Dense Produkt
+const SPMM_BLOCK_COLS: usize = 64;
+
+// Ab diesem relativen Fehler werden die am stärksten abweichenden Einträge ausgegeben.
+// Nur ein Hinweis, die Genauigkeit jedes Backends steht ohnehin in der Tabelle und in der CSV-Datei.
+const RELATIVE_ERROR_DETAILS: f64 = 1e-3;
+
 /// Benchmark matrix multiplication using different libraries
 /// load all matrices from provided folder path or default and benchmark all possible combinations
 fn main() {
     // Default values
     let mut repeat_count: usize = 10;
     let mut folder_path = "./matrix_instances/generated/dense";
+    // Block size for the BSR product, e.g. 3x3 for FEM matrices with 3 degrees of freedom per node
+    let mut block_shape: (usize, usize) = (4, 4);
 
     // Get command-line arguments if provided
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().collect();
+    // --spmv darf an beliebiger Stelle stehen und wird aus den Positionsargumenten entfernt
+    let spmv_mode = args.iter().any(|arg| arg == "--spmv");
+    args.retain(|arg| arg != "--spmv");
     if args.len() >= 2 {
         repeat_count = args[1].parse().expect("Failed to parse repeat count");
     }
     if args.len() >= 3 {
         folder_path = &args[2];
     }
+    if args.len() >= 4 {
+        block_shape = parse_block_shape(&args[3]).expect("Failed to parse block shape, expected e.g. 4x4");
+    }
 
-    // NEU: Initialisierung der Ergebnis-Vektoren für detaillierte CSVs
-    // results[0] -> Raw Multiplication
-    // results[1] -> H2D
-    // results[2] -> D2H
-    // results[3] -> Initialization + Cleanup (reine API-Overheads ohne Kopien für WGPU; mit Kopien für cuBLAS/cuSPARSE)
-    // results[4] -> Total
-    // results[5] -> Combined Overhead (initialization + h2d + d2h + cleanup)
-    let mut results: Vec<Vec<String>> = vec![Vec::new(); 6];
+    if spmv_mode {
+        benchmark_spmv_folder(folder_path, repeat_count);
+        return;
+    }
 
-    // Header für die CSV-Dateien generieren
-    let common_header_components = "Matrix1,Matrix2";
     let libraries = [
-        "cuBlas", "cuSparse", "gpuDense", "gpuSparse", "Blas", "cpuSparseParallel", "cpuDenseParallel"
+        "cuBlas", "cuSparse", "gpuDense", "gpuSparse", "Blas", "cpuSparseParallel", "cpuDenseParallel", "cpuBlockParallel",
+        "cpuEllParallel", "cpuSellParallel", "cpuDiaParallel", "cpuCsrDenseParallel", "cpuDenseCsrParallel",
+        "cpuCsrDenseBlocked"
     ];
+    // Header für die CSV-Dateien generieren
+    let mut results = csv_headers("Matrix1,Matrix2", &libraries);
 
-    let mut raw_mult_header = common_header_components.to_string();
-    let mut h2d_header = common_header_components.to_string();
-    let mut d2h_header = common_header_components.to_string();
-    let mut init_cleanup_header = common_header_components.to_string();
-    let mut total_header = common_header_components.to_string();
-    let mut combined_overhead_header = common_header_components.to_string(); // <-- NEU: Header für Combined Overhead
+    let matrix_paths = get_readable_matrix_paths(folder_path);
 
+    // Generate table headers for console output (can stay simple with total times)
+    let mut table_head = format!("{:<20}{:<20}", "Matrix 1", "Matrix 2");
     for lib in &libraries {
-        raw_mult_header.push_str(&format!(",{}_Raw (µs)", lib));
-        h2d_header.push_str(&format!(",{}_H2D (µs)", lib));
-        d2h_header.push_str(&format!(",{}_D2H (µs)", lib));
-        init_cleanup_header.push_str(&format!(",{}_InitCleanup (µs)", lib));
-        total_header.push_str(&format!(",{}_Total (µs)", lib));
-        combined_overhead_header.push_str(&format!(",{}_CombinedOverhead (µs)", lib));
+        table_head += &format!("{:<width$}", format!("{} (µs)", lib), width = column_width(lib));
     }
-
-    results[0].push(raw_mult_header);
-    results[1].push(h2d_header);
-    results[2].push(d2h_header);
-    results[3].push(init_cleanup_header);
-    results[4].push(total_header);
-    results[5].push(combined_overhead_header);
-
-    // search matrices in the folder
-    let matrix_paths = get_matrix_paths(folder_path);
-
-    // Generate table headers for console output (can stay simple with total times)
-    let table_head = &format!("{:<20}{:<20}{:<15}{:<15}{:<15}{:<15}{:<15}{:<25}{:<25}",
-                              "Matrix 1", "Matrix 2", "cuBlas (µs)", "cuSparse (µs)", "gpuDense (µs)", "gpuSparse (µs)", "Blas (µs)", "cpuSparseParallel (µs)", "cpuDenseParallel (µs)");
+    let table_head = &table_head;
 
     println!("\nTotal Times:");
     println!("{}", table_head);
@@ -84,6 +88,13 @@ fn main() {
     let mut multiplication_table = String::from("Raw Multiplication Times:\n");
     multiplication_table += table_head;
 
+    // Fehler haben keine Einheit, eigener Tabellenkopf
+    let mut accuracy_table = String::from("Relative Errors (Frobenius norm, - = not run):\n");
+    accuracy_table += &format!("{:<20}{:<20}", "Matrix 1", "Matrix 2");
+    for lib in &libraries {
+        accuracy_table += &format!("{:<width$}", lib, width = column_width(lib));
+    }
+
     // Benchmark all possible combinations of matrices
     for matrix1_path in &matrix_paths {
         for matrix2_path in &matrix_paths {
@@ -100,71 +111,131 @@ fn main() {
                     "sparse"
                 }else if matrix1_name.starts_with("s-vs-d_") {
                     "s-vs-d"
+                } else if matrix1_name.starts_with("toeplit_") {
+                    // Toeplitz-Matrizen aus generate_toeplitz.py sind dünnbesetzt
+                    "sparse"
                 } else {
                     "unknown"
                 };
 
-                if current_matrix_type == "s-vs-d" && !strings_match_except_last_n_chars(matrix1_name,matrix2_name,7){
+                if current_matrix_type == "s-vs-d" && pair_name(matrix1_path) != pair_name(matrix2_path) {
                     continue;
                 }
                 // benchmark_matrix gibt Vec<TimingResult> zurück
-                let min_times = benchmark_matrix(matrix1_path, matrix2_path, repeat_count, current_matrix_type);
+                let (min_times, errors) = match benchmark_matrix(matrix1_path, matrix2_path, repeat_count, current_matrix_type, block_shape) {
+                    Ok(results) => results,
+                    Err(e) => {
+                        println!();
+                        eprintln!("Skipping {} x {}: {}", matrix1_name, matrix2_name, e);
+                        continue;
+                    }
+                };
 
                 // generate table rows for console (using total and raw_multiply from min_times)
-                multiplication_table += &format!("\n{:<20}{:<20}{:<15}{:<15}{:<15}{:<15}{:<15}{:<25}{:<25}",
-                                                 matrix1_name, matrix2_name, min_times[0].raw_multiply_us, min_times[1].raw_multiply_us,
-                                                 min_times[2].raw_multiply_us, min_times[3].raw_multiply_us, min_times[4].raw_multiply_us,
-                                                 min_times[5].raw_multiply_us, min_times[6].raw_multiply_us);
-
-                overhead_table += &format!("\n{:<20}{:<20}{:<15}{:<15}{:<15}{:<15}{:<15}{:<25}{:<25}",
-                                           matrix1_name, matrix2_name,
-                                           min_times[0].initialization_us + min_times[0].h2d_us + min_times[0].d2h_us + min_times[0].cleanup_us, // Summe der Overheads für cuBlas
-                                           min_times[1].initialization_us + min_times[1].h2d_us + min_times[1].d2h_us + min_times[1].cleanup_us, // Summe der Overheads für cuSparse
-                                           min_times[2].initialization_us + min_times[2].h2d_us + min_times[2].d2h_us + min_times[2].cleanup_us, // Summe der Overheads für gpuDense
-                                           min_times[3].initialization_us + min_times[3].h2d_us + min_times[3].d2h_us + min_times[3].cleanup_us, // Summe der Overheads für gpuSparse
-                                           min_times[4].initialization_us + min_times[4].h2d_us + min_times[4].d2h_us + min_times[4].cleanup_us, // Summe der Overheads für Blas
-                                           min_times[5].initialization_us + min_times[5].h2d_us + min_times[5].d2h_us + min_times[5].cleanup_us, // Summe der Overheads für cpuSparseParallel
-                                           min_times[6].initialization_us + min_times[6].h2d_us + min_times[6].d2h_us + min_times[6].cleanup_us  // Summe der Overheads für cpuDenseParallel
-                );
-
-                total_table += &format!("\n{:<20}{:<20}{:<15}{:<15}{:<15}{:<15}{:<15}{:<25}{:<25}",
-                                        matrix1_name, matrix2_name, min_times[0].total_us, min_times[1].total_us,
-                                        min_times[2].total_us, min_times[3].total_us, min_times[4].total_us,
-                                        min_times[5].total_us, min_times[6].total_us);
-
-
-                let mut raw_mult_row = format!("{},{}", matrix1_name, matrix2_name);
-                let mut h2d_row = format!("{},{}", matrix1_name, matrix2_name);
-                let mut d2h_row = format!("{},{}", matrix1_name, matrix2_name);
-                let mut init_cleanup_row = format!("{},{}", matrix1_name, matrix2_name);
-                let mut total_row = format!("{},{}", matrix1_name, matrix2_name);
-                let mut combined_overhead_row = format!("{},{}", matrix1_name, matrix2_name); // <-- NEU
-
-                for res in &min_times {
-                    raw_mult_row.push_str(&format!(",{}", res.raw_multiply_us));
-                    h2d_row.push_str(&format!(",{}", res.h2d_us));
-                    d2h_row.push_str(&format!(",{}", res.d2h_us));
-                    init_cleanup_row.push_str(&format!(",{}", res.initialization_us + res.cleanup_us));
-                    total_row.push_str(&format!(",{}", res.total_us));
-                    // NEU: Berechnung des kombinierten Overheads
-                    let current_combined_overhead = res.initialization_us + res.h2d_us + res.d2h_us + res.cleanup_us;
-                    combined_overhead_row.push_str(&format!(",{}", current_combined_overhead)); // <-- NEU
+                // min_times has the same order as libraries
+                multiplication_table += &format!("\n{:<20}{:<20}", matrix1_name, matrix2_name);
+                overhead_table += &format!("\n{:<20}{:<20}", matrix1_name, matrix2_name);
+                total_table += &format!("\n{:<20}{:<20}", matrix1_name, matrix2_name);
+                accuracy_table += &format!("\n{:<20}{:<20}", matrix1_name, matrix2_name);
+                for (lib, error) in libraries.iter().zip(&errors) {
+                    accuracy_table += &format!("{:<width$}", format_error(*error), width = column_width(lib));
+                }
+                for (lib, res) in libraries.iter().zip(&min_times) {
+                    let width = column_width(lib);
+                    multiplication_table += &format!("{:<width$}", res.raw_multiply_us);
+                    // Summe der Overheads
+                    overhead_table += &format!("{:<width$}", res.initialization_us + res.h2d_us + res.d2h_us + res.cleanup_us);
+                    total_table += &format!("{:<width$}", res.total_us);
                 }
 
-                results[0].push(raw_mult_row);     // Raw Multiplication
-                results[1].push(h2d_row);          // H2D
-                results[2].push(d2h_row);          // D2H
-                results[3].push(init_cleanup_row); // Initialization + Cleanup
-                results[4].push(total_row);        // Total
-                results[5].push(combined_overhead_row); // <-- NEU
+
+                push_csv_rows(&mut results, &format!("{},{}", matrix1_name, matrix2_name), &min_times, &errors);
             }
         }
     }
 
     // print tables to console
-    println!("\n\n{}\n\n{}", overhead_table, multiplication_table);
+    println!("\n\n{}\n\n{}\n\n{}", overhead_table, multiplication_table, accuracy_table);
 
     // generate output files for all detailed times
+    write_csv_files(folder_path, "", repeat_count, &results);
+}
+
+// NEU: Initialisierung der Ergebnis-Vektoren für detaillierte CSVs
+// results[0] -> Raw Multiplication
+// results[1] -> H2D
+// results[2] -> D2H
+// results[3] -> Initialization + Cleanup (reine API-Overheads ohne Kopien für WGPU; mit Kopien für cuBLAS/cuSPARSE)
+// results[4] -> Total
+// results[5] -> Combined Overhead (initialization + h2d + d2h + cleanup)
+// results[6] -> Relativer Fehler gegen die CPU-Referenz (Frobenius-Norm)
+fn csv_headers(common_header_components: &str, libraries: &[&str]) -> Vec<Vec<String>> {
+    let mut raw_mult_header = common_header_components.to_string();
+    let mut h2d_header = common_header_components.to_string();
+    let mut d2h_header = common_header_components.to_string();
+    let mut init_cleanup_header = common_header_components.to_string();
+    let mut total_header = common_header_components.to_string();
+    let mut combined_overhead_header = common_header_components.to_string(); // <-- NEU: Header für Combined Overhead
+    let mut relative_error_header = common_header_components.to_string();
+
+    for lib in libraries {
+        raw_mult_header.push_str(&format!(",{}_Raw (µs)", lib));
+        h2d_header.push_str(&format!(",{}_H2D (µs)", lib));
+        d2h_header.push_str(&format!(",{}_D2H (µs)", lib));
+        init_cleanup_header.push_str(&format!(",{}_InitCleanup (µs)", lib));
+        total_header.push_str(&format!(",{}_Total (µs)", lib));
+        combined_overhead_header.push_str(&format!(",{}_CombinedOverhead (µs)", lib));
+        relative_error_header.push_str(&format!(",{}_RelativeError", lib));
+    }
+
+    vec![
+        vec![raw_mult_header],
+        vec![h2d_header],
+        vec![d2h_header],
+        vec![init_cleanup_header],
+        vec![total_header],
+        vec![combined_overhead_header],
+        vec![relative_error_header],
+    ]
+}
+
+// Eine Zeile pro CSV-Datei, row_name sind die ersten Spalten (z.B. "Matrix1,Matrix2")
+// errors sind die relativen Fehler in derselben Reihenfolge wie min_times, NaN = nicht gelaufen
+fn push_csv_rows(results: &mut [Vec<String>], row_name: &str, min_times: &[TimingResult], errors: &[f64]) {
+    let mut raw_mult_row = row_name.to_string();
+    let mut h2d_row = row_name.to_string();
+    let mut d2h_row = row_name.to_string();
+    let mut init_cleanup_row = row_name.to_string();
+    let mut total_row = row_name.to_string();
+    let mut combined_overhead_row = row_name.to_string(); // <-- NEU
+    let mut relative_error_row = row_name.to_string();
+
+    for res in min_times {
+        raw_mult_row.push_str(&format!(",{}", res.raw_multiply_us));
+        h2d_row.push_str(&format!(",{}", res.h2d_us));
+        d2h_row.push_str(&format!(",{}", res.d2h_us));
+        init_cleanup_row.push_str(&format!(",{}", res.initialization_us + res.cleanup_us));
+        total_row.push_str(&format!(",{}", res.total_us));
+        // NEU: Berechnung des kombinierten Overheads
+        let current_combined_overhead = res.initialization_us + res.h2d_us + res.d2h_us + res.cleanup_us;
+        combined_overhead_row.push_str(&format!(",{}", current_combined_overhead)); // <-- NEU
+    }
+
+    results[0].push(raw_mult_row);     // Raw Multiplication
+    results[1].push(h2d_row);          // H2D
+    results[2].push(d2h_row);          // D2H
+    results[3].push(init_cleanup_row); // Initialization + Cleanup
+    results[4].push(total_row);        // Total
+    results[5].push(combined_overhead_row); // <-- NEU
+
+    for error in errors {
+        relative_error_row.push_str(&format!(",{:e}", error));
+    }
+    results[6].push(relative_error_row);
+}
+
+// Schreibt die sechs CSV-Dateien nach ./output/data/<Input-Ordner>/, file_prefix unterscheidet die Modi (z.B. "spmv_")
+fn write_csv_files(folder_path: &str, file_prefix: &str, repeat_count: usize, results: &[Vec<String>]) {
     let base_output_dir = Path::new("./output/data");
     let input_folder_name = Path::new(folder_path).file_name()
         .and_then(|s| s.to_str())
@@ -175,9 +246,10 @@ fn main() {
 
     let write_csv = |file_suffix: &str, data: &Vec<String>, result_type: &str| {
         // Ändere den Dateipfad, um den neuen output_dir zu verwenden
-        let filename = format!("{}/{}_{}_repeat_count_{}.csv",
+        let filename = format!("{}/{}_{}{}_repeat_count_{}.csv",
                                output_dir.display(), // Nutze den neuen, angepassten output_dir
                                chrono::Local::now().format("%Y-%m-%d_%H-%M-%S"),
+                               file_prefix,
                                file_suffix,
                                repeat_count);
         let mut file = File::create(&filename).expect(&format!("Failed to create {} output file at {}", result_type, filename));
@@ -193,22 +265,26 @@ fn main() {
     write_csv("init_cleanup_times", &results[3], "initialization and cleanup");
     write_csv("total_times", &results[4], "total");
     write_csv("combined_overhead_times", &results[5], "combined overhead");
+    write_csv("relative_errors", &results[6], "relative error");
 }
 
-fn import_matrix(matrix_path: &Path) -> (Dense, CSR, COO) {
-    let matrix_coo = COO::read_mtx(matrix_path, false)
-        .expect(format!("failed reading matrix at {}", matrix_path.display()).as_str());
+fn import_matrix(matrix_path: &Path) -> Result<(Dense, CSR, COO), matrix_base::Error> {
+    // Beim ersten Lesen wird ein Binär-Cache neben der mtx-Datei angelegt, danach wird nur noch dieser geladen
+    let mut matrix_coo = COO::read_mtx_cached(matrix_path)?;
+    // Doppelte Einträge werden aufsummiert, damit alle Formate dieselbe Matrix sehen
+    matrix_coo.canonicalize(&CanonicalizeOptions::default());
     let matrix_dense = matrix_coo.to_dense();
     let matrix_csr = CSR::from_coo(&matrix_coo);
-    (matrix_dense, matrix_csr, matrix_coo)
+    Ok((matrix_dense, matrix_csr, matrix_coo))
 }
 
 // Benchmark matrix multiplication
-fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usize, matrix_type: &str) -> Vec<TimingResult> {
-    let (matrix1_dense, matrix1_csr, matrix1_coo) = import_matrix(matrix1_path);
+// Gibt die minimalen Zeiten und den größten relativen Fehler jedes Backends zurück (NaN = nicht gelaufen)
+fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usize, matrix_type: &str, block_shape: (usize, usize)) -> Result<(Vec<TimingResult>, Vec<f64>), matrix_base::Error> {
+    let (matrix1_dense, matrix1_csr, matrix1_coo) = import_matrix(matrix1_path)?;
     print!("{:<20}", matrix1_path.file_name().unwrap().to_str().unwrap().chars().take(19).collect::<String>());
     stdout().flush().unwrap();
-    let (matrix2_dense, matrix2_csr, matrix2_coo) = import_matrix(matrix2_path);
+    let (matrix2_dense, matrix2_csr, matrix2_coo) = import_matrix(matrix2_path)?;
     print!("{:<20}", matrix2_path.file_name().unwrap().to_str().unwrap().chars().take(19).collect::<String>());
     stdout().flush().unwrap();
 
@@ -220,6 +296,30 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
     let mut times_gpu_dense = Vec::with_capacity(repeat_count);
     let mut times_gpu_sparse = Vec::with_capacity(repeat_count);
     let mut times_blas = Vec::with_capacity(repeat_count);
+    let mut times_cpu_block_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_ell_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_sell_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_dia_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_csr_dense_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_dense_csr_parallel = Vec::with_capacity(repeat_count);
+    let mut times_cpu_csr_dense_blocked = Vec::with_capacity(repeat_count);
+
+    // Größter relativer Fehler über alle Läufe, NaN bleibt für nicht gelaufene Backends stehen
+    let mut error_cpu_dense_parallel = f64::NAN;
+    let mut error_cpu_sparse_parallel = f64::NAN;
+    let mut error_cublas = f64::NAN;
+    let mut error_cusparse = f64::NAN;
+    let mut error_gpu_dense = f64::NAN;
+    let mut error_gpu_sparse = f64::NAN;
+    let mut error_blas = f64::NAN;
+    let mut error_cpu_block_parallel = f64::NAN;
+    let mut error_cpu_ell_parallel = f64::NAN;
+    let mut error_cpu_sell_parallel = f64::NAN;
+    let mut error_cpu_dia_parallel = f64::NAN;
+    let mut error_cpu_csr_dense_parallel = f64::NAN;
+    let mut error_cpu_dense_csr_parallel = f64::NAN;
+    let mut error_cpu_csr_dense_blocked = f64::NAN;
+    let pair_name = format!("{} x {}", matrix1_path.display(), matrix2_path.display());
 
     // **REFERENZBERECHNUNG AUF DER CPU (EINMALIG PRO MATRIX-PAAR)**
     // Diese Ergebnisse werden für die Korrektheitsprüfung verwendet.
@@ -227,6 +327,31 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
     let reference_result_dense_cpu = matrix1_dense.product_dense_par(&matrix2_dense);
     let reference_result_sparse_coo_cpu = matrix1_csr.product_sparse_to_coo_par(&matrix2_csr);
 
+    // Die GPU-Backends rechnen in f32, daher wird für sie auch in f32 referenziert.
+    // Sonst vergleicht man Rundungsfehler statt Korrektheit.
+    // Erst beim ersten Vergleich berechnet, ohne GPU-Backend für den Matrixtyp gar nicht.
+    let reference_result_dense_cpu_f32 = OnceCell::new();
+    let reference_dense_f32 = || {
+        reference_result_dense_cpu_f32.get_or_init(|| {
+            matrix1_dense
+                .cast::<f32>()
+                .product_dense_par(&matrix2_dense.cast::<f32>())
+                .cast::<f64>()
+        })
+    };
+    let reference_result_sparse_coo_cpu_f32 = OnceCell::new();
+    let reference_sparse_coo_f32 = || {
+        reference_result_sparse_coo_cpu_f32.get_or_init(|| {
+            matrix1_csr
+                .cast::<f32>()
+                .product_sparse_to_coo_par(&matrix2_csr.cast::<f32>())
+                .cast::<f64>()
+        })
+    };
+
+    // Fehlende Einträge zählen beim Vergleich als 0, Formate mit Padding (BSR, DIA)
+    // können also direkt mit der Referenz verglichen werden, auch wenn diese Nullen enthält
+
 
     // cuBLAS (Dense)
     if matrix_type == "dense" {
@@ -234,7 +359,7 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let start_total = std::time::Instant::now();
             let (res_matrix_cublas_dense, time_raw_multiply, time_total_internal, time_h2d_cublas, time_d2h_cublas) = cublas::multiply(&matrix1_dense, &matrix2_dense).unwrap();
 
-            let mut timing_result = TimingResult {
+            let timing_result = TimingResult {
                 initialization_us: time_total_internal - time_raw_multiply - time_h2d_cublas - time_d2h_cublas, // Overhead ohne H2D/D2H
                 h2d_us: time_h2d_cublas,
                 raw_multiply_us: time_raw_multiply,
@@ -243,18 +368,15 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
                 total_us: start_total.elapsed().as_micros(),
             };
 
-            // Korrektheitsprüfung
-            if !check_results_dense(&res_matrix_cublas_dense.data, &reference_result_dense_cpu.data) {
-                eprintln!("WARNING: cuBlas result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            // Genauigkeit gegen die f32-Referenz
+            error_cublas = error_cublas.max(relative_error("cuBlas", &pair_name, &res_matrix_cublas_dense, reference_dense_f32()));
             times_cublas.push(timing_result);
         }
     } else {
         // Nicht anwendbar für diesen Matrixtyp
         for _ in 0..repeat_count { times_cublas.push(TimingResult::zero()); }
     }
-    print!("{:<15}", times_cublas.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_cublas.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cuBlas"));
     stdout().flush().unwrap();
 
     // cuSPARSE (Sparse)
@@ -263,7 +385,7 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let start_total = std::time::Instant::now();
             let (res_matrix_cusparse, time_raw_multiply, time_total_internal, time_h2d_cusparse, time_d2h_cusparse) = cusparse::multiply(&matrix1_csr, &matrix2_csr).unwrap();
 
-            let mut timing_result = TimingResult {
+            let timing_result = TimingResult {
                 initialization_us: time_total_internal - time_raw_multiply - time_h2d_cusparse - time_d2h_cusparse, // Overhead ohne H2D/D2H
                 h2d_us: time_h2d_cusparse, // NEU: Gemessener H2D-Wert
                 raw_multiply_us: time_raw_multiply,
@@ -272,18 +394,13 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
                 total_us: start_total.elapsed().as_micros(),
             };
 
-            let cusparse_result_coo = CSR::to_coo(&res_matrix_cusparse);
-
-            if matrix_type != "s-vs-d" && !check_results_sparse_coo(&cusparse_result_coo, &reference_result_sparse_coo_cpu) {
-                eprintln!("WARNING: cuSparse result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            error_cusparse = error_cusparse.max(relative_error("cuSparse", &pair_name, &res_matrix_cusparse, reference_sparse_coo_f32()));
             times_cusparse.push(timing_result);
         }
     } else {
         for _ in 0..repeat_count { times_cusparse.push(TimingResult::zero()); }
     }
-    print!("{:<15}", times_cusparse.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_cusparse.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cuSparse"));
     stdout().flush().unwrap();
 
     // GPU Dense Parallel
@@ -294,19 +411,20 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let (gpu_dense_result_vec, mut timing_result) = gpu::dense::multiply_for_benchmark(&matrix1_dense, &matrix2_dense, 1000*1000*1000);
             timing_result.total_us = start_total.elapsed().as_micros();
 
-            // Korrektheitsprüfung: gpu_dense_result_vec ist Vec<f32>, muss zu Vec<f64> konvertiert werden
-            let gpu_dense_result_vec_f64: Vec<f64> = gpu_dense_result_vec.iter().map(|&x| x as f64).collect();
-            if !check_results_dense(&gpu_dense_result_vec_f64, &reference_result_dense_cpu.data) {
-                eprintln!("WARNING: gpuDense result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            // Genauigkeit: gpu_dense_result_vec ist Vec<f32> (zeilenweise), muss zu f64 konvertiert werden
+            let gpu_dense_result = Dense {
+                data: gpu_dense_result_vec.iter().map(|&x| x as f64).collect(),
+                shape: reference_dense_f32().shape,
+                layout: Layout::RowMajor,
+            };
+            error_gpu_dense = error_gpu_dense.max(relative_error("gpuDense", &pair_name, &gpu_dense_result, reference_dense_f32()));
             times_gpu_dense.push(timing_result);
         }
     } else {
         // Nicht anwendbar
         for _ in 0..repeat_count { times_gpu_dense.push(TimingResult::zero()); }
     }
-    print!("{:<15}", times_gpu_dense.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_gpu_dense.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("gpuDense"));
     stdout().flush().unwrap();
 
     // GPU Sparse
@@ -344,14 +462,12 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
                 total_us: start_total.elapsed().as_micros(),
             };
 
-            // Korrektheitsprüfung für Sparse
+            // Genauigkeit für Sparse
             if let Some(gpu_sparse_result_coo) = gpusm.cast_result() {
-                if matrix_type != "s-vs-d" && !check_results_sparse_coo(&gpu_sparse_result_coo, &reference_result_sparse_coo_cpu) {
-                    eprintln!("WARNING: gpuSparse result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                    final_timing = TimingResult::max_values();
-                }
+                error_gpu_sparse = error_gpu_sparse.max(relative_error("gpuSparse", &pair_name, &gpu_sparse_result_coo, reference_sparse_coo_f32()));
             } else {
                 eprintln!("WARNING: gpuSparse result for {} x {} returned None!", matrix1_path.display(), matrix2_path.display());
+                error_gpu_sparse = f64::INFINITY;
                 final_timing = TimingResult::max_values();
             }
             times_gpu_sparse.push(final_timing);
@@ -360,7 +476,7 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
         // Nicht anwendbar
         for _ in 0..repeat_count { times_gpu_sparse.push(TimingResult::zero()); }
     }
-    print!("{:<15}", times_gpu_sparse.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_gpu_sparse.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("gpuSparse"));
     stdout().flush().unwrap();
 
     // BLAS (Dense)
@@ -377,7 +493,7 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let blas_result_dense = a.prod(&b); // Ergebnis von BLAS
             let raw_multiply_us = start_raw_multiply.elapsed().as_micros();
 
-            let mut timing_result = TimingResult {
+            let timing_result = TimingResult {
                 initialization_us: init_h2d_us,
                 h2d_us: 0,
                 raw_multiply_us,
@@ -386,18 +502,20 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
                 total_us: start_total.elapsed().as_micros(),
             };
 
-            // Korrektheitsprüfung: blas_result_dense.data ist Vec<f64>
-            if !check_results_dense(&blas_result_dense.data, &matrix_base::Dense::as_column_major(&reference_result_dense_cpu).data) {
-                eprintln!("WARNING: Blas result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            // Genauigkeit: blas_result_dense.data ist spaltenweise gespeichert
+            let blas_result = Dense {
+                data: blas_result_dense.data,
+                shape: (blas_result_dense.shape.0 as usize, blas_result_dense.shape.1 as usize),
+                layout: Layout::ColumnMajor,
+            };
+            error_blas = error_blas.max(relative_error("Blas", &pair_name, &blas_result, &reference_result_dense_cpu));
             times_blas.push(timing_result);
         }
     } else {
         // Nicht anwendbar
         for _ in 0..repeat_count { times_blas.push(TimingResult::zero()); }
     }
-    print!("{:<15}", times_blas.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_blas.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("Blas"));
     stdout().flush().unwrap();
 
     // CPU Sparse Parallel
@@ -408,25 +526,21 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let cpu_sparse_result_coo = matrix1_csr.product_sparse_to_coo_par(&matrix2_csr);
             let total_us = start_total.elapsed().as_micros();
 
-            let mut timing_result = TimingResult {
+            let timing_result = TimingResult {
                 initialization_us: 0, h2d_us: 0,
                 raw_multiply_us: total_us,
                 d2h_us: 0, cleanup_us: 0,
                 total_us,
             };
 
-            // Korrektheitsprüfung
-            if matrix_type != "s-vs-d" && !check_results_sparse_coo(&cpu_sparse_result_coo, &reference_result_sparse_coo_cpu) {
-                eprintln!("WARNING: cpuSparseParallel result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            error_cpu_sparse_parallel = error_cpu_sparse_parallel.max(relative_error("cpuSparseParallel", &pair_name, &cpu_sparse_result_coo, &reference_result_sparse_coo_cpu));
             times_cpu_sparse_parallel.push(timing_result);
         }
     } else {
         // Nicht anwendbar
         for _ in 0..repeat_count { times_cpu_sparse_parallel.push(TimingResult::zero()); }
     }
-    print!("{:<25}", times_cpu_sparse_parallel.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_cpu_sparse_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuSparseParallel"));
     stdout().flush().unwrap();
 
     // CPU Dense Parallel
@@ -437,26 +551,222 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
             let cpu_dense_result = matrix1_dense.product_dense_par(&matrix2_dense);
             let total_us = start_total.elapsed().as_micros();
 
-            let mut timing_result = TimingResult {
+            let timing_result = TimingResult {
                 initialization_us: 0, h2d_us: 0,
                 raw_multiply_us: total_us,
                 d2h_us: 0, cleanup_us: 0,
                 total_us,
             };
 
-            // Korrektheitsprüfung
-            // Da dies unsere Referenz ist, sollte es immer korrekt sein, aber wir prüfen es trotzdem.
-            if !check_results_dense(&cpu_dense_result.data, &reference_result_dense_cpu.data) {
-                eprintln!("WARNING: cpuDenseParallel result for {} x {} is INCORRECT!", matrix1_path.display(), matrix2_path.display());
-                timing_result = TimingResult::max_values();
-            }
+            // Da dies unsere Referenz ist, sollte der Fehler immer 0 sein, aber wir prüfen es trotzdem.
+            error_cpu_dense_parallel = error_cpu_dense_parallel.max(relative_error("cpuDenseParallel", &pair_name, &cpu_dense_result, &reference_result_dense_cpu));
             times_cpu_dense_parallel.push(timing_result);
         }
     } else {
         // Nicht anwendbar
         for _ in 0..repeat_count { times_cpu_dense_parallel.push(TimingResult::zero()); }
     }
-    print!("{:<25}", times_cpu_dense_parallel.iter().map(|t| t.total_us).min().unwrap_or(0));
+    print!("{:<width$}", times_cpu_dense_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuDenseParallel"));
+    stdout().flush().unwrap();
+
+    // CPU Block Parallel (BSR)
+    // Führe nur aus, wenn der Matrixtyp "sparse" ist
+    if matrix_type == "sparse" ||  matrix_type == "s-vs-d"{
+        // Die Blockgröße von B muss zu den Spalten der Blöcke von A passen
+        let matrix2_block_shape = (block_shape.1, block_shape.0);
+
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let matrix1_bsr = BSR::from_csr(&matrix1_csr, block_shape);
+            let matrix2_bsr = BSR::from_csr(&matrix2_csr, matrix2_block_shape);
+            let initialization_us = start_total.elapsed().as_micros();
+
+            let start_raw_multiply = std::time::Instant::now();
+            let cpu_block_result = matrix1_bsr.product_block_par(&matrix2_bsr);
+            let raw_multiply_us = start_raw_multiply.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us, h2d_us: 0,
+                raw_multiply_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us: start_total.elapsed().as_micros(),
+            };
+
+            error_cpu_block_parallel = error_cpu_block_parallel.max(relative_error("cpuBlockParallel", &pair_name, &cpu_block_result.to_coo(), &reference_result_sparse_coo_cpu));
+            times_cpu_block_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_block_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_block_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuBlockParallel"));
+    stdout().flush().unwrap();
+
+    // CPU ELL Parallel
+    // Führe nur aus, wenn der Matrixtyp "sparse" ist
+    if matrix_type == "sparse" ||  matrix_type == "s-vs-d"{
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let matrix1_ell = ELL::from_csr(&matrix1_csr);
+            let initialization_us = start_total.elapsed().as_micros();
+
+            let start_raw_multiply = std::time::Instant::now();
+            let cpu_ell_result = matrix1_ell.product_sparse_par(&matrix2_csr);
+            let raw_multiply_us = start_raw_multiply.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us, h2d_us: 0,
+                raw_multiply_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us: start_total.elapsed().as_micros(),
+            };
+
+            error_cpu_ell_parallel = error_cpu_ell_parallel.max(relative_error("cpuEllParallel", &pair_name, &cpu_ell_result, &reference_result_sparse_coo_cpu));
+            times_cpu_ell_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_ell_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_ell_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuEllParallel"));
+    stdout().flush().unwrap();
+
+    // CPU SELL-C-sigma Parallel
+    // Führe nur aus, wenn der Matrixtyp "sparse" ist
+    if matrix_type == "sparse" ||  matrix_type == "s-vs-d"{
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let matrix1_sell = SELL::from_csr(&matrix1_csr, SELL_CHUNK_SIZE, SELL_SIGMA);
+            let initialization_us = start_total.elapsed().as_micros();
+
+            let start_raw_multiply = std::time::Instant::now();
+            let cpu_sell_result = matrix1_sell.product_sparse_par(&matrix2_csr);
+            let raw_multiply_us = start_raw_multiply.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us, h2d_us: 0,
+                raw_multiply_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us: start_total.elapsed().as_micros(),
+            };
+
+            error_cpu_sell_parallel = error_cpu_sell_parallel.max(relative_error("cpuSellParallel", &pair_name, &cpu_sell_result, &reference_result_sparse_coo_cpu));
+            times_cpu_sell_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_sell_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_sell_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuSellParallel"));
+    stdout().flush().unwrap();
+
+    // CPU DIA Parallel
+    // Führe nur aus, wenn der Matrixtyp "sparse" ist und beide Matrizen Bandmatrizen sind.
+    // is_banded sammelt nur die Diagonalen, die Umwandlung wird pro Wiederholung gemessen.
+    if (matrix_type == "sparse" ||  matrix_type == "s-vs-d")
+        && DIA::is_banded(&matrix1_csr, DIA_MAX_FILL_RATIO)
+        && DIA::is_banded(&matrix2_csr, DIA_MAX_FILL_RATIO)
+    {
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let matrix1_dia = DIA::from_csr(&matrix1_csr);
+            let matrix2_dia = DIA::from_csr(&matrix2_csr);
+            let initialization_us = start_total.elapsed().as_micros();
+
+            let start_raw_multiply = std::time::Instant::now();
+            let cpu_dia_result = matrix1_dia.product_dia_par(&matrix2_dia);
+            let raw_multiply_us = start_raw_multiply.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us, h2d_us: 0,
+                raw_multiply_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us: start_total.elapsed().as_micros(),
+            };
+
+            error_cpu_dia_parallel = error_cpu_dia_parallel.max(relative_error("cpuDiaParallel", &pair_name, &cpu_dia_result.to_coo(), &reference_result_sparse_coo_cpu));
+            times_cpu_dia_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_dia_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_dia_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuDiaParallel"));
+    stdout().flush().unwrap();
+
+    // CPU Sparse x Dense Parallel (A als CSR, B dicht)
+    // Führe nur aus, wenn der Matrixtyp "s-vs-d" ist
+    if matrix_type == "s-vs-d" {
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let result_dense = matrix1_csr.product_dense_par(&matrix2_dense);
+            let total_us = start_total.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us: 0, h2d_us: 0,
+                raw_multiply_us: total_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us,
+            };
+
+            error_cpu_csr_dense_parallel = error_cpu_csr_dense_parallel.max(relative_error("cpuCsrDenseParallel", &pair_name, &result_dense, &reference_result_dense_cpu));
+            times_cpu_csr_dense_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_csr_dense_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_csr_dense_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuCsrDenseParallel"));
+    stdout().flush().unwrap();
+
+    // CPU Dense x Sparse Parallel (A dicht, B als CSR)
+    // Führe nur aus, wenn der Matrixtyp "s-vs-d" ist
+    if matrix_type == "s-vs-d" {
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let result_dense = matrix1_dense.product_csr_par(&matrix2_csr);
+            let total_us = start_total.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us: 0, h2d_us: 0,
+                raw_multiply_us: total_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us,
+            };
+
+            error_cpu_dense_csr_parallel = error_cpu_dense_csr_parallel.max(relative_error("cpuDenseCsrParallel", &pair_name, &result_dense, &reference_result_dense_cpu));
+            times_cpu_dense_csr_parallel.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_dense_csr_parallel.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_dense_csr_parallel.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuDenseCsrParallel"));
+    stdout().flush().unwrap();
+
+    // CPU Sparse x Dense Parallel, B in Spaltenblöcken
+    // Führe nur aus, wenn der Matrixtyp "s-vs-d" ist
+    if matrix_type == "s-vs-d" {
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let result_dense = matrix1_csr.product_dense_blocked_par(&matrix2_dense, SPMM_BLOCK_COLS);
+            let total_us = start_total.elapsed().as_micros();
+
+            let timing_result = TimingResult {
+                initialization_us: 0, h2d_us: 0,
+                raw_multiply_us: total_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us,
+            };
+
+            error_cpu_csr_dense_blocked = error_cpu_csr_dense_blocked.max(relative_error("cpuCsrDenseBlocked", &pair_name, &result_dense, &reference_result_dense_cpu));
+            times_cpu_csr_dense_blocked.push(timing_result);
+        }
+    } else {
+        // Nicht anwendbar
+        for _ in 0..repeat_count { times_cpu_csr_dense_blocked.push(TimingResult::zero()); }
+    }
+    print!("{:<width$}", times_cpu_csr_dense_blocked.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width("cpuCsrDenseBlocked"));
     stdout().flush().unwrap();
     println!();
 
@@ -469,112 +779,244 @@ fn benchmark_matrix(matrix1_path: &Path, matrix2_path: &Path, repeat_count: usiz
         times_blas,
         times_cpu_sparse_parallel,
         times_cpu_dense_parallel,
+        times_cpu_block_parallel,
+        times_cpu_ell_parallel,
+        times_cpu_sell_parallel,
+        times_cpu_dia_parallel,
+        times_cpu_csr_dense_parallel,
+        times_cpu_dense_csr_parallel,
+        times_cpu_csr_dense_blocked,
     ];
 
-    let min_results: Vec<TimingResult> = all_times.into_iter().map(|times| {
-        // Findet das Minimum für jede Zeitkomponente, ignoriert dabei u128::MAX (Fehlerwerte)
-        times.iter().fold(TimingResult {
-            initialization_us: u128::MAX,
-            h2d_us: u128::MAX,
-            raw_multiply_us: u128::MAX,
-            d2h_us: u128::MAX,
-            cleanup_us: u128::MAX,
-            total_us: u128::MAX,
-        }, |mut acc, time| {
-            // Nur wenn der aktuelle Zeitwert kein Fehlerwert ist, wird das Minimum aktualisiert.
-            // Sonst bleibt acc auf MAX, wenn alle Werte MAX sind.
-            if time.initialization_us != u128::MAX { acc.initialization_us = min(acc.initialization_us, time.initialization_us); }
-            if time.h2d_us != u128::MAX { acc.h2d_us = min(acc.h2d_us, time.h2d_us); }
-            if time.raw_multiply_us != u128::MAX { acc.raw_multiply_us = min(acc.raw_multiply_us, time.raw_multiply_us); }
-            if time.d2h_us != u128::MAX { acc.d2h_us = min(acc.d2h_us, time.d2h_us); }
-            if time.cleanup_us != u128::MAX { acc.cleanup_us = min(acc.cleanup_us, time.cleanup_us); }
-            if time.total_us != u128::MAX { acc.total_us = min(acc.total_us, time.total_us); }
-            acc
-        })
-    }).collect();
+    let min_results: Vec<TimingResult> = all_times.iter().map(|times| min_timing(times)).collect();
+
+    // Gleiche Reihenfolge wie all_times
+    let all_errors = vec![
+        error_cublas,
+        error_cusparse,
+        error_gpu_dense,
+        error_gpu_sparse,
+        error_blas,
+        error_cpu_sparse_parallel,
+        error_cpu_dense_parallel,
+        error_cpu_block_parallel,
+        error_cpu_ell_parallel,
+        error_cpu_sell_parallel,
+        error_cpu_dia_parallel,
+        error_cpu_csr_dense_parallel,
+        error_cpu_dense_csr_parallel,
+        error_cpu_csr_dense_blocked,
+    ];
 
-    min_results
+    Ok((min_results, all_errors))
 }
 
-/// Checks if two dense matrix results (flattened Vec<f64> vs Dense.data) are approximately equal.
-/// Both inputs are expected as slices of f64 for consistent comparison.
-fn check_results_dense(result_tested_data: &[f64], reference_data: &[f64]) -> bool {
+// Minimums über alle Läufe eines Backends
+fn min_timing(times: &[TimingResult]) -> TimingResult {
+    // Findet das Minimum für jede Zeitkomponente, ignoriert dabei u128::MAX (Fehlerwerte)
+    times.iter().fold(TimingResult::max_values(), |mut acc, time| {
+        // Nur wenn der aktuelle Zeitwert kein Fehlerwert ist, wird das Minimum aktualisiert.
+        // Sonst bleibt acc auf MAX, wenn alle Werte MAX sind.
+        if time.initialization_us != u128::MAX { acc.initialization_us = min(acc.initialization_us, time.initialization_us); }
+        if time.h2d_us != u128::MAX { acc.h2d_us = min(acc.h2d_us, time.h2d_us); }
+        if time.raw_multiply_us != u128::MAX { acc.raw_multiply_us = min(acc.raw_multiply_us, time.raw_multiply_us); }
+        if time.d2h_us != u128::MAX { acc.d2h_us = min(acc.d2h_us, time.d2h_us); }
+        if time.cleanup_us != u128::MAX { acc.cleanup_us = min(acc.cleanup_us, time.cleanup_us); }
+        if time.total_us != u128::MAX { acc.total_us = min(acc.total_us, time.total_us); }
+        acc
+    })
+}
+
+// SpMV-Modus (--spmv): y = A*x bzw. y = A^T*x für jede Matrix im Ordner,
+// mit derselben Aufteilung der Zeiten wie bei den Matrixprodukten
+fn benchmark_spmv_folder(folder_path: &str, repeat_count: usize) {
+    let libraries = ["cpuSparse", "cpuSparseParallel", "cpuSparseTParallel", "cpuEllParallel", "cpuSellParallel"];
+    let mut results = csv_headers("Matrix", &libraries);
 
-    if result_tested_data.len() != reference_data.len() {
-        eprintln!("Error: Result lengths mismatch: Tested {} vs Reference {}", result_tested_data.len(), reference_data.len());
-        return false;
+    let mut table_head = format!("{:<20}", "Matrix");
+    for lib in &libraries {
+        table_head += &format!("{:<width$}", format!("{} (µs)", lib), width = column_width(lib));
     }
 
-    let tolerance = 1e-2; // Absolute Toleranz für f32/f64 Vergleich
-    let mut errors_found = 0;
-    let max_errors_to_print = 5; // Begrenze die Anzahl der ausgegebenen Fehler
+    println!("\nSpMV Total Times:");
+    println!("{}", table_head);
+
+    let mut multiplication_table = String::from("SpMV Raw Multiplication Times:\n");
+    multiplication_table += &table_head;
+
+    let mut accuracy_table = String::from("SpMV Relative Errors (Euclidean norm):\n");
+    accuracy_table += &format!("{:<20}", "Matrix");
+    for lib in &libraries {
+        accuracy_table += &format!("{:<width$}", lib, width = column_width(lib));
+    }
 
-    for i in 0..result_tested_data.len() {
-        let diff = (result_tested_data[i] - reference_data[i]).abs();
-        if diff > tolerance {
-            errors_found += 1;
-            if errors_found <= max_errors_to_print {
-                eprintln!("Mismatch at index {}: Tested={:.2e} Reference={:.2e} Diff={:.2e}", i, result_tested_data[i], reference_data[i], diff);
+    for matrix_path in get_readable_matrix_paths(folder_path) {
+        let matrix_name = matrix_path.file_name().unwrap().to_str().unwrap();
+        let (min_times, errors) = match benchmark_spmv(&matrix_path, repeat_count, &libraries) {
+            Ok(results) => results,
+            Err(e) => {
+                println!();
+                eprintln!("Skipping {}: {}", matrix_name, e);
+                continue;
             }
+        };
+
+        multiplication_table += &format!("\n{:<20}", matrix_name);
+        for (lib, res) in libraries.iter().zip(&min_times) {
+            multiplication_table += &format!("{:<width$}", res.raw_multiply_us, width = column_width(lib));
         }
-    }
-    if errors_found > max_errors_to_print {
-        eprintln!("... ({} more errors not printed)", errors_found - max_errors_to_print);
-    }
-    if errors_found > 0 {
-        eprintln!("Total mismatches: {}", errors_found);
+        accuracy_table += &format!("\n{:<20}", matrix_name);
+        for (lib, error) in libraries.iter().zip(&errors) {
+            accuracy_table += &format!("{:<width$}", format_error(*error), width = column_width(lib));
+        }
+        push_csv_rows(&mut results, matrix_name, &min_times, &errors);
     }
 
+    println!("\n\n{}\n\n{}", multiplication_table, accuracy_table);
 
-    errors_found == 0
+    write_csv_files(folder_path, "spmv_", repeat_count, &results);
 }
 
+// Benchmark SpMV, libraries gibt nur die Spaltenbreiten für die Konsole vor.
+// Gibt die minimalen Zeiten und den größten relativen Fehler jeder Variante zurück.
+fn benchmark_spmv(matrix_path: &Path, repeat_count: usize, libraries: &[&str]) -> Result<(Vec<TimingResult>, Vec<f64>), matrix_base::Error> {
+    let mut matrix_coo = COO::read_mtx_cached(matrix_path)?;
+    matrix_coo.canonicalize(&CanonicalizeOptions::default());
+    let matrix_csr = CSR::from_coo(&matrix_coo);
+    print!("{:<20}", matrix_path.file_name().unwrap().to_str().unwrap().chars().take(19).collect::<String>());
+    stdout().flush().unwrap();
 
-/// Helper for Sparse COO results. Compares two COO matrices.
-fn check_results_sparse_coo(result_gpu_coo: &COO, result_cpu_coo: &COO) -> bool {
-    if result_gpu_coo.shape != result_cpu_coo.shape {
-        eprintln!("Error: Sparse shapes mismatch: GPU {:?} vs CPU {:?}", result_gpu_coo.shape, result_cpu_coo.shape);
-        return false;
-    }
+    // Feste Vektoren, damit die Läufe vergleichbar sind
+    let (m, n) = matrix_csr.shape;
+    let x: Vec<f64> = (0..n).map(|j| 1. + (j % 7) as f64 / 7.).collect();
+    let x_transpose: Vec<f64> = (0..m).map(|i| 1. + (i % 7) as f64 / 7.).collect();
+
+    // **REFERENZBERECHNUNG AUF DER CPU (EINMALIG PRO MATRIX)**
+    let mut reference = vec![0.; m];
+    matrix_csr.spmv(1., &x, 0., &mut reference);
+    let mut reference_transpose = vec![0.; n];
+    matrix_csr.spmv_transpose(1., &x_transpose, 0., &mut reference_transpose);
+
+    // Misst eine Variante repeat_count mal, run gibt (y, Initialisierung, Rechenzeit) zurück
+    let matrix_name = matrix_path.display().to_string();
+    let time_spmv = |lib: &str, reference: &[f64], run: &dyn Fn() -> (Vec<f64>, u128, u128)| {
+        let mut times = Vec::with_capacity(repeat_count);
+        let mut error = f64::NAN;
+        for _ in 0..repeat_count {
+            let start_total = std::time::Instant::now();
+            let (y, initialization_us, raw_multiply_us) = run();
 
-    // Für einen robusten COO-Vergleich müssen beide Listen der (i, j, x) Triplets sortiert werden,
-    // da die Reihenfolge der Nicht-Null-Elemente in COO variieren kann.
-    let mut gpu_data_sorted = result_gpu_coo.data.clone();
-    gpu_data_sorted.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1))); // Sort by row, then by column
+            times.push(TimingResult {
+                initialization_us, h2d_us: 0,
+                raw_multiply_us,
+                d2h_us: 0, cleanup_us: 0,
+                total_us: start_total.elapsed().as_micros(),
+            });
+            error = error.max(relative_error(lib, &matrix_name, y.as_slice(), reference));
+        }
+        print!("{:<width$}", times.iter().map(|t| t.total_us).min().unwrap_or(0), width = column_width(lib));
+        stdout().flush().unwrap();
+        (times, error)
+    };
 
-    let mut cpu_data_sorted = result_cpu_coo.data.clone();
-    cpu_data_sorted.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1))); // Sort by row, then by column
+    // CPU Sparse (sequentiell)
+    let results_cpu_sparse = time_spmv(libraries[0], &reference, &|| {
+        let mut y = vec![0.; m];
+        let start_raw_multiply = std::time::Instant::now();
+        matrix_csr.spmv(1., &x, 0., &mut y);
+        (y, 0, start_raw_multiply.elapsed().as_micros())
+    });
 
-    if gpu_data_sorted.len() != cpu_data_sorted.len() {
-        eprintln!("Error: Sparse NNZ count mismatch: GPU {} vs CPU {}", gpu_data_sorted.len(), cpu_data_sorted.len());
-        return false;
-    }
+    // CPU Sparse Parallel
+    let results_cpu_sparse_parallel = time_spmv(libraries[1], &reference, &|| {
+        let mut y = vec![0.; m];
+        let start_raw_multiply = std::time::Instant::now();
+        matrix_csr.spmv_par(1., &x, 0., &mut y);
+        (y, 0, start_raw_multiply.elapsed().as_micros())
+    });
+
+    // CPU Sparse Parallel mit A^T, ohne die Transponierte aufzubauen
+    let results_cpu_sparse_transpose_parallel = time_spmv(libraries[2], &reference_transpose, &|| {
+        let mut y = vec![0.; n];
+        let start_raw_multiply = std::time::Instant::now();
+        matrix_csr.spmv_transpose_par(1., &x_transpose, 0., &mut y);
+        (y, 0, start_raw_multiply.elapsed().as_micros())
+    });
+
+    // CPU ELL Parallel
+    let results_cpu_ell_parallel = time_spmv(libraries[3], &reference, &|| {
+        let start_init = std::time::Instant::now();
+        let matrix_ell = ELL::from_csr(&matrix_csr);
+        let initialization_us = start_init.elapsed().as_micros();
+
+        let start_raw_multiply = std::time::Instant::now();
+        let y = matrix_ell.spmv_par(&x);
+        (y, initialization_us, start_raw_multiply.elapsed().as_micros())
+    });
+
+    // CPU SELL-C-sigma Parallel
+    let results_cpu_sell_parallel = time_spmv(libraries[4], &reference, &|| {
+        let start_init = std::time::Instant::now();
+        let matrix_sell = SELL::from_csr(&matrix_csr, SELL_CHUNK_SIZE, SELL_SIGMA);
+        let initialization_us = start_init.elapsed().as_micros();
+
+        let start_raw_multiply = std::time::Instant::now();
+        let y = matrix_sell.spmv_par(&x);
+        (y, initialization_us, start_raw_multiply.elapsed().as_micros())
+    });
+    println!();
 
-    let tolerance = 1e-1;
-    let mut errors_found = 0;
-    let max_errors_to_print = 5;
+    // Minimums über alle Läufe berechnen, gleiche Reihenfolge wie libraries
+    let all_results = [
+        results_cpu_sparse,
+        results_cpu_sparse_parallel,
+        results_cpu_sparse_transpose_parallel,
+        results_cpu_ell_parallel,
+        results_cpu_sell_parallel,
+    ];
 
-    for i in 0..gpu_data_sorted.len() {
-        let (r_g, c_g, v_g) = gpu_data_sorted[i];
-        let (r_c, c_c, v_c) = cpu_data_sorted[i];
+    Ok(all_results.iter().map(|(times, error)| (min_timing(times), *error)).unzip())
+}
 
-        // Prüfe Index und Wert
-        if r_g != r_c || c_g != c_c || (v_g - v_c).abs() > tolerance {
-            errors_found += 1;
-            if errors_found <= max_errors_to_print {
-                eprintln!("Sparse Mismatch at sorted index {}: GPU=({},{},{:.2e}) CPU=({},{},{:.2e}) Diff={:.2e}",
-                          i, r_g, c_g, v_g, r_c, c_c, v_c, (v_g - v_c).abs());
-            }
+// Relativer Fehler (Frobenius-Norm) von result gegenüber der CPU-Referenz.
+// Liegt er über RELATIVE_ERROR_DETAILS, werden die am stärksten abweichenden Einträge ausgegeben.
+// Unterschiedliche Formen und NaN im Ergebnis zählen als unendlicher Fehler.
+fn relative_error<A, B>(lib: &str, name: &str, result: &A, reference: &B) -> f64
+where
+    A: MatrixEntries<f64> + ?Sized,
+    B: MatrixEntries<f64> + ?Sized,
+{
+    let report = match accuracy::compare(result, reference) {
+        Some(report) => report,
+        None => {
+            eprintln!("WARNING: {} result for {} has shape {:?} instead of {:?}!", lib, name, result.shape(), reference.shape());
+            return f64::INFINITY;
         }
+    };
+    if !report.is_within(RELATIVE_ERROR_DETAILS) {
+        eprintln!("WARNING: {} result for {}: {}", lib, name, report);
     }
-    if errors_found > max_errors_to_print {
-        eprintln!("... ({} more sparse errors not printed)", errors_found - max_errors_to_print);
-    }
-    if errors_found > 0 {
-        eprintln!("Total sparse mismatches: {}", errors_found);
-    }
+    if report.relative_error.is_nan() { f64::INFINITY } else { report.relative_error }
+}
+
+// Fehler für die Konsolentabellen, "-" wenn das Backend nicht gelaufen ist
+fn format_error(error: f64) -> String {
+    if error.is_nan() { String::from("-") } else { format!("{:.2e}", error) }
+}
 
-    errors_found == 0
+// search matrices in the folder
+// Files with broken header are skipped, the error tells what is wrong with them
+fn get_readable_matrix_paths(folder_path: &str) -> Vec<PathBuf> {
+    get_matrix_paths(folder_path)
+        .into_iter()
+        .filter(|path| match read_matrix_shape(path) {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("Skipping matrix: {}", e);
+                false
+            }
+        })
+        .collect()
 }
 
 // Returns all paths to matrices inside folder_path
@@ -586,11 +1028,15 @@ fn get_matrix_paths(folder_path: &str) -> Vec<PathBuf> {
         return matrix_paths;
     }
 
-    // Iterate over directory entries and add .mtx file paths to matrix_paths
+    // Iterate over directory entries and add .mtx file paths to matrix_paths,
+    // compressed ones (.mtx.gz, .mtx.bz2, .mtx.xz) and Rutherford-Boeing files (.rb, .rua, ...) too
     let entries = fs::read_dir(path).expect("Failed to read directory");
     for entry in entries.flatten() {
         let file_path = entry.path();
-        if file_path.is_file() && file_path.extension().unwrap_or_default() == "mtx" {
+        if file_path.is_file()
+            && (matrix_base::mtx::is_mtx_path(&file_path)
+                || matrix_base::rb::is_rb_path(&file_path))
+        {
             matrix_paths.push(file_path);
         }
     }
@@ -598,46 +1044,44 @@ fn get_matrix_paths(folder_path: &str) -> Vec<PathBuf> {
     matrix_paths
 }
 
+// Only reads the header, files with broken header are already filtered out in main
 fn get_matrix_shape(file_name: &PathBuf) -> (usize, usize) {
-    let file = File::open(file_name).expect("Error parsing file.");
-    let file = std::io::BufReader::new(file);
-
-    let mut liter = file.lines();
-
-    // "Header" / comments
-    // Ignore all comments / type codes (assume 'MatrixMarket matrix coordinate real general format')
-    for line in liter.by_ref() {
-        let line = String::from(line.map_err(|_| ("Error parsing line")).unwrap().trim());
-        if line.starts_with("%") {
-            continue;
-        } else {
-            let mut hspl = line.split(' ');
-            let m = hspl.next().unwrap().parse().unwrap();
-            let n = hspl.next().unwrap().parse().unwrap();
-            let _l: usize = hspl.next().unwrap().parse().unwrap();
-            return (m, n);
-        }
+    read_matrix_shape(file_name).unwrap_or_else(|e| panic!("{}", e))
+}
+
+// Header of a mtx- or Rutherford-Boeing file
+fn read_matrix_shape(file_name: &Path) -> Result<(usize, usize), matrix_base::Error> {
+    if matrix_base::rb::is_rb_path(file_name) {
+        matrix_base::rb::read_shape(file_name)
+    } else {
+        matrix_base::mtx::read_shape(file_name)
     }
-    panic!(
-        "{}",
-        format!(
-            "no shape found in file {}",
-            file_name.file_name().unwrap().to_str().unwrap()
-        )
-    )
 }
 
-fn strings_match_except_last_n_chars(s1: &str, s2: &str, n: usize) -> bool {
-    // Überprüfen, ob die Strings lang genug sind, um 'n' Zeichen zu ignorieren.
-    if s1.len() < n || s2.len() < n {
-        return false; // Oder eine Fehlermeldung ausgeben, je nach Anforderung.
+// Width of a library's column in the console tables
+fn column_width(lib: &str) -> usize {
+    if lib.len() + 8 > 15 { 25 } else { 15 }
+}
+
+// Parses a block shape like "4x4" or "2x3"
+fn parse_block_shape(s: &str) -> Option<(usize, usize)> {
+    let (r, c) = s.split_once('x')?;
+    let shape = (r.trim().parse().ok()?, c.trim().parse().ok()?);
+    if shape.0 == 0 || shape.1 == 0 {
+        return None;
     }
+    Some(shape)
+}
 
-    // Die Präfixe extrahieren (alles außer den letzten 'n' Zeichen)
-    let prefix1 = &s1[..(s1.len() - n)];
-    let prefix2 = &s2[..(s2.len() - n)];
-    //println!("{}, {}, {}, {},", s1, s2, prefix1, prefix2);
-    // Die Präfixe vergleichen
-    prefix1 == prefix2
+// Name eines Matrixpaars ohne Endung und ohne Markierung _A<k>/_B<k>,
+// s-vs-d_0.1_A1.mtx.gz -> s-vs-d_0.1, auch für komprimierte Dateien
+fn pair_name(matrix_path: &Path) -> Option<String> {
+    let path = match matrix_base::mtx::Compression::from_path(matrix_path) {
+        matrix_base::mtx::Compression::None => matrix_path.to_path_buf(),
+        _ => matrix_path.with_extension(""),
+    };
+    let stem = path.file_stem()?.to_str()?;
+    let (name, _marker) = stem.rsplit_once('_')?;
+    Some(name.to_string())
 }
 
@@ -5,7 +5,7 @@ use std::{
     cmp::{max, min},
     env,
     fs::{self, File},
-    io::{stdout, BufRead, Write},
+    io::{stdout, Write},
     path::{Path, PathBuf},
 };
 
@@ -189,8 +189,12 @@ fn main() {
 }
 
 fn import_matrix(matrix_path: &Path) -> (Dense, CSR, COO) {
-    let matrix_coo = COO::read_mtx(matrix_path, false)
-        .expect(format!("failed reading matrix at {}", matrix_path.display()).as_str());
+    let matrix_coo = if matrix_base::rb::is_rb_path(matrix_path) {
+        COO::read_rb(matrix_path)
+    } else {
+        COO::read_mtx(matrix_path, false)
+    }
+    .unwrap_or_else(|e| panic!("{}", e));
     let matrix_dense = matrix_coo.to_dense();
     let matrix_csr = CSR::from_coo(&matrix_coo);
     (matrix_dense, matrix_csr, matrix_coo)
@@ -446,11 +450,15 @@ fn get_matrix_paths(folder_path: &str) -> Vec<PathBuf> {
         return matrix_paths;
     }
 
-    // Iterate over directory entries and add .mtx file paths to matrix_paths
+    // Iterate over directory entries and add .mtx file paths to matrix_paths,
+    // compressed ones (.mtx.gz, .mtx.bz2, .mtx.xz) and Rutherford-Boeing files (.rb, .rua, ...) too
     let entries = fs::read_dir(path).expect("Failed to read directory");
     for entry in entries.flatten() {
         let file_path = entry.path();
-        if file_path.is_file() && file_path.extension().unwrap_or_default() == "mtx" {
+        if file_path.is_file()
+            && (matrix_base::mtx::is_mtx_path(&file_path)
+                || matrix_base::rb::is_rb_path(&file_path))
+        {
             matrix_paths.push(file_path);
         }
     }
@@ -459,30 +467,10 @@ fn get_matrix_paths(folder_path: &str) -> Vec<PathBuf> {
 }
 
 fn get_matrix_shape(file_name: &PathBuf) -> (usize, usize) {
-    let file = File::open(file_name).expect("Error parsing file.");
-    let file = std::io::BufReader::new(file);
-
-    let mut liter = file.lines();
-
-    // "Header" / comments
-    // Ignore all comments / type codes (assume 'MatrixMarket matrix coordinate real general format')
-    for line in liter.by_ref() {
-        let line = String::from(line.map_err(|_| ("Error parsing line")).unwrap().trim());
-        if line.starts_with("%") {
-            continue;
-        } else {
-            let mut hspl = line.split(' ');
-            let m = hspl.next().unwrap().parse().unwrap();
-            let n = hspl.next().unwrap().parse().unwrap();
-            let _l: usize = hspl.next().unwrap().parse().unwrap();
-            return (m, n);
-        }
+    if matrix_base::rb::is_rb_path(file_name) {
+        matrix_base::rb::read_shape(file_name)
+    } else {
+        matrix_base::mtx::read_shape(file_name)
     }
-    panic!(
-        "{}",
-        format!(
-            "no shape found in file {}",
-            file_name.file_name().unwrap().to_str().unwrap()
-        )
-    )
+    .unwrap_or_else(|e| panic!("{}", e))
 }